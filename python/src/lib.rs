@@ -1,3 +1,6 @@
+// pyo3 0.22 macros trigger false positives with newer clippy versions.
+#![allow(clippy::useless_conversion)]
+
 use pyo3::prelude::*;
 
 /// Render a .shapdf script to a PDF file.
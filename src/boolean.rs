@@ -0,0 +1,325 @@
+use crate::path::{winding, FillRule, Path, Polyline, FLATTEN_TOLERANCE};
+use crate::units::Pt;
+use std::collections::{HashMap, HashSet};
+
+type Point = (f64, f64);
+
+/// Boolean operation combining the areas covered by two paths (see [`Path::boolean`]).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathOp {
+    /// Area covered by either path.
+    Union,
+    /// Area covered by both paths.
+    Intersection,
+    /// Area covered by the first path but not the second.
+    Difference,
+    /// Area covered by exactly one of the paths.
+    Xor,
+}
+
+impl PathOp {
+    fn apply(&self, a: bool, b: bool) -> bool {
+        match self {
+            PathOp::Union => a || b,
+            PathOp::Intersection => a && b,
+            PathOp::Difference => a && !b,
+            PathOp::Xor => a != b,
+        }
+    }
+}
+
+impl Path {
+    /// Outline of the area covered by `self` or `other` (see [`boolean`](Self::boolean)).
+    ///
+    /// # Example
+    /// ```
+    /// use shapdf::*;
+    /// let mut generator = Generator::new("output/stencil.pdf".into());
+    /// generator.add_page();
+    /// // Merged outline of a tab and its rounded end, with a hole through both
+    /// let tab = Path::rectangle(Mm(20.), Mm(20.), Mm(40.), Mm(20.))
+    ///     .union(&Path::circle(Mm(60.), Mm(30.), Mm(10.)))
+    ///     .difference(&Path::circle(Mm(60.), Mm(30.), Mm(4.)));
+    /// generator.path(tab).with_color(NamedColor("gray")).draw().unwrap();
+    /// ```
+    pub fn union(&self, other: &Path) -> Path {
+        self.boolean(other, PathOp::Union, FillRule::NonZero)
+    }
+
+    /// Outline of the area covered by both `self` and `other`.
+    pub fn intersection(&self, other: &Path) -> Path {
+        self.boolean(other, PathOp::Intersection, FillRule::NonZero)
+    }
+
+    /// Outline of the area covered by `self` but not by `other`, e.g. to cut holes.
+    pub fn difference(&self, other: &Path) -> Path {
+        self.boolean(other, PathOp::Difference, FillRule::NonZero)
+    }
+
+    /// Outline of the area covered by exactly one of `self` and `other`.
+    pub fn xor(&self, other: &Path) -> Path {
+        self.boolean(other, PathOp::Xor, FillRule::NonZero)
+    }
+
+    /// Outline of the area combining the areas of `self` and `other` with `op`, where
+    /// each path covers the area it fills with `fill_rule` (open subpaths are closed).
+    ///
+    /// Curves are approximated by lines within 0.01 points. The outlines of the result
+    /// run counterclockwise around the covered areas and clockwise around their holes,
+    /// so it does not overlap itself and fills the same with either fill rule.
+    pub fn boolean(&self, other: &Path, op: PathOp, fill_rule: FillRule) -> Path {
+        let shapes = [rings(self), rings(other)];
+        let edges: Vec<(Point, Point)> = shapes
+            .iter()
+            .flatten()
+            .flat_map(|ring| {
+                let points = &ring.points;
+                (0..points.len()).map(move |i| (points[i], points[(i + 1) % points.len()]))
+            })
+            .collect();
+
+        let covered = |point: Point| {
+            let [a, b] = &shapes;
+            op.apply(
+                fill_rule.is_inside(winding(a, point)),
+                fill_rule.is_inside(winding(b, point)),
+            )
+        };
+        // Pieces of the edges with the result on their left only
+        let mut seen = HashSet::new();
+        let mut boundary = Vec::new();
+        for (p, q) in split_edges(&edges) {
+            let (dx, dy) = (q.0 - p.0, q.1 - p.1);
+            let length = dx.hypot(dy);
+            let offset = (length * 0.01).min(1e-4) / length;
+            let (mx, my) = ((p.0 + q.0) / 2.0, (p.1 + q.1) / 2.0);
+            let left = covered((mx - dy * offset, my + dx * offset));
+            let right = covered((mx + dy * offset, my - dx * offset));
+            let piece = match (left, right) {
+                (true, false) => (p, q),
+                (false, true) => (q, p),
+                _ => continue,
+            };
+            // Edges shared by both paths are kept once
+            if seen.insert(key(piece)) {
+                boundary.push(piece);
+            }
+        }
+
+        link(&boundary).into_iter().fold(Path::new(), |path, ring| {
+            let points: Vec<(Pt, Pt)> = ring.into_iter().map(|(x, y)| (Pt(x), Pt(y))).collect();
+            path.append(Path::polygon(&points))
+        })
+    }
+}
+
+/// Subpaths of a path flattened to closed rings without repeated points.
+fn rings(path: &Path) -> Vec<Polyline> {
+    path.flatten(FLATTEN_TOLERANCE)
+        .into_iter()
+        .filter_map(|polyline| {
+            let mut points = polyline.points;
+            points.dedup();
+            if points.len() > 1 && points.first() == points.last() {
+                points.pop();
+            }
+            (points.len() >= 3).then_some(Polyline {
+                points,
+                closed: true,
+            })
+        })
+        .collect()
+}
+
+fn cross((ax, ay): Point, (bx, by): Point) -> f64 {
+    ax * by - ay * bx
+}
+
+fn sub(a: Point, b: Point) -> Point {
+    (a.0 - b.0, a.1 - b.1)
+}
+
+/// Pieces of the edges between their intersections (and the endpoints of the
+/// overlapping parts of collinear edges).
+fn split_edges(edges: &[(Point, Point)]) -> Vec<(Point, Point)> {
+    const EPSILON: f64 = 1e-9;
+    // Points splitting each edge, by position along it
+    let mut splits: Vec<Vec<(f64, Point)>> = vec![Vec::new(); edges.len()];
+    let param = |(a, b): (Point, Point), point: Point| {
+        let r = sub(b, a);
+        let d = sub(point, a);
+        (d.0 * r.0 + d.1 * r.1) / (r.0 * r.0 + r.1 * r.1)
+    };
+    let interior = |t: f64| t > EPSILON && t < 1.0 - EPSILON;
+    for i in 0..edges.len() {
+        let (a1, b1) = edges[i];
+        let r = sub(b1, a1);
+        for j in i + 1..edges.len() {
+            let (a2, b2) = edges[j];
+            let s = sub(b2, a2);
+            let qp = sub(a2, a1);
+            let denominator = cross(r, s);
+            let scale = r.0.hypot(r.1) * s.0.hypot(s.1);
+            if denominator.abs() > EPSILON * scale {
+                let t = cross(qp, s) / denominator;
+                let u = cross(qp, r) / denominator;
+                if !(-EPSILON..=1.0 + EPSILON).contains(&t)
+                    || !(-EPSILON..=1.0 + EPSILON).contains(&u)
+                {
+                    continue;
+                }
+                // Reuse the exact endpoints where edges touch, so that the pieces link up
+                match (interior(t), interior(u)) {
+                    (true, true) => {
+                        let point = (a1.0 + t * r.0, a1.1 + t * r.1);
+                        splits[i].push((t, point));
+                        splits[j].push((u, point));
+                    }
+                    (true, false) => splits[i].push((t, if u < 0.5 { a2 } else { b2 })),
+                    (false, true) => splits[j].push((u, if t < 0.5 { a1 } else { b1 })),
+                    (false, false) => {}
+                }
+            } else if cross(qp, r).abs() <= EPSILON * r.0.hypot(r.1) * qp.0.hypot(qp.1).max(1.0) {
+                // Collinear: split each edge at the endpoints of the other lying on it
+                for point in [a2, b2] {
+                    let t = param(edges[i], point);
+                    if interior(t) {
+                        splits[i].push((t, point));
+                    }
+                }
+                for point in [a1, b1] {
+                    let u = param(edges[j], point);
+                    if interior(u) {
+                        splits[j].push((u, point));
+                    }
+                }
+            }
+        }
+    }
+
+    let mut pieces = Vec::new();
+    for (&(a, b), mut points) in edges.iter().zip(splits) {
+        points.sort_by(|x, y| x.0.total_cmp(&y.0));
+        let mut start = a;
+        for point in points.into_iter().map(|(_, point)| point).chain([b]) {
+            if point != start {
+                pieces.push((start, point));
+                start = point;
+            }
+        }
+    }
+    pieces
+}
+
+/// Key identifying a piece by the exact coordinates of its endpoints.
+fn key(((x1, y1), (x2, y2)): (Point, Point)) -> [u64; 4] {
+    [x1.to_bits(), y1.to_bits(), x2.to_bits(), y2.to_bits()]
+}
+
+/// Chain directed pieces into closed rings, dropping the points in the middle of
+/// straight runs.
+fn link(pieces: &[(Point, Point)]) -> Vec<Vec<Point>> {
+    let point_key = |(x, y): Point| [x.to_bits(), y.to_bits()];
+    let mut starting: HashMap<[u64; 2], Vec<usize>> = HashMap::new();
+    for (i, (p, _)) in pieces.iter().enumerate() {
+        starting.entry(point_key(*p)).or_default().push(i);
+    }
+    let mut used = vec![false; pieces.len()];
+    let mut rings = Vec::new();
+    for first in 0..pieces.len() {
+        if used[first] {
+            continue;
+        }
+        used[first] = true;
+        let (start, mut current) = pieces[first];
+        let mut ring = vec![start];
+        while current != start {
+            ring.push(current);
+            let next = starting
+                .get(&point_key(current))
+                .and_then(|candidates| candidates.iter().copied().find(|&i| !used[i]));
+            match next {
+                Some(i) => {
+                    used[i] = true;
+                    current = pieces[i].1;
+                }
+                // Left open by rounding errors: close it directly
+                None => break,
+            }
+        }
+        let ring = simplify(ring);
+        if ring.len() >= 3 {
+            rings.push(ring);
+        }
+    }
+    rings
+}
+
+/// Drop the points of a ring in the middle of straight runs.
+fn simplify(ring: Vec<Point>) -> Vec<Point> {
+    let n = ring.len();
+    (0..n)
+        .filter(|&i| {
+            let (previous, point, next) = (ring[(i + n - 1) % n], ring[i], ring[(i + 1) % n]);
+            let (u, v) = (sub(point, previous), sub(next, point));
+            cross(u, v).abs() > 1e-12 * u.0.hypot(u.1) * v.0.hypot(v.1)
+                || u.0 * v.0 + u.1 * v.1 < 0.0
+        })
+        .map(|i| ring[i])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::area;
+
+    fn square(x: f64, y: f64, size: f64) -> Path {
+        Path::rectangle(Pt(x), Pt(y), Pt(size), Pt(size))
+    }
+
+    #[test]
+    fn overlapping_squares_combine() {
+        let (a, b) = (square(0., 0., 10.), square(5., 5., 10.));
+        assert_eq!(area(&a.union(&b)), 175.);
+        assert_eq!(area(&a.intersection(&b)), 25.);
+        assert_eq!(area(&a.difference(&b)), 75.);
+        assert_eq!(area(&a.xor(&b)), 150.);
+        // Straight runs are merged: the union has 8 corners
+        assert_eq!(a.union(&b).segments().len(), 9);
+        // Shared edges do not leave slivers
+        let beside = square(10., 0., 10.);
+        let merged = a.union(&beside);
+        assert_eq!(area(&merged), 200.);
+        assert_eq!(merged.segments().len(), 5);
+        assert!(a.intersection(&square(20., 20., 1.)).is_empty());
+    }
+
+    #[test]
+    fn holes_and_curves_follow_the_fill_rule() {
+        let ring = square(0., 0., 10.).difference(&square(3., 3., 4.));
+        assert_eq!(area(&ring), 84.);
+        let polylines = rings(&ring);
+        assert_eq!(winding(&polylines, (5., 5.)), 0);
+        assert_eq!(winding(&polylines, (1., 5.)), 1);
+
+        // The hole of an even-odd compound path is kept out of the union
+        let frame = square(0., 0., 10.).append(square(3., 3., 4.));
+        let union = frame.boolean(&square(8., 4., 4.), PathOp::Union, FillRule::EvenOdd);
+        assert_eq!(area(&union), 84. + 8.);
+
+        let lens = Path::circle(Pt(0.), Pt(0.), Pt(10.)).intersection(&Path::circle(
+            Pt(10.),
+            Pt(0.),
+            Pt(10.),
+        ));
+        // Two circular segments of a third of the circle each
+        let expected = 2. * (100. * std::f64::consts::PI / 3. - 100. * 3f64.sqrt() / 4.);
+        assert!((area(&lens) - expected).abs() < 0.5);
+
+        // Identical outlines are kept once
+        let circle = Path::circle(Pt(0.), Pt(0.), Pt(300.));
+        assert!((area(&circle.union(&circle)) - area(&circle)).abs() < 1e-6);
+        assert!(circle.xor(&circle).is_empty());
+    }
+}
@@ -0,0 +1,269 @@
+use std::{error::Error, fmt};
+
+/// Smallest page dimension (in points) allowed by the PDF 1.7 implementation limits.
+pub const MIN_PAGE_DIMENSION: f64 = 3.0;
+/// Largest page dimension (in points) allowed by the PDF 1.7 implementation limits.
+pub const MAX_PAGE_DIMENSION: f64 = 14400.0;
+/// Maximum length (in bytes) of a PDF string allowed by the PDF 1.7 implementation limits.
+pub const MAX_STRING_LENGTH: usize = 32767;
+
+const SRGB_DESCRIPTION: &str = "sRGB IEC61966-2.1";
+
+/// Conformance level of the generated PDF.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Conformance {
+    /// Plain PDF without any archival guarantees.
+    #[default]
+    None,
+    /// PDF/A-2b (ISO 19005-2, level B) for long-term archiving.
+    PdfA2b,
+}
+
+impl Conformance {
+    /// Whether the conformance level is one of the PDF/A flavors.
+    pub fn is_pdfa(&self) -> bool {
+        matches!(self, Conformance::PdfA2b)
+    }
+
+    pub(crate) fn pdfa_part_and_level(&self) -> Option<(u8, char)> {
+        match self {
+            Conformance::None => None,
+            Conformance::PdfA2b => Some((2, 'B')),
+        }
+    }
+}
+
+impl fmt::Display for Conformance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Conformance::None => f.pad("none"),
+            Conformance::PdfA2b => f.pad("PDF/A-2b"),
+        }
+    }
+}
+
+/// Error raised when a requested feature would break the selected conformance level.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConformanceError {
+    pub conformance: Conformance,
+    pub message: String,
+}
+
+impl ConformanceError {
+    pub(crate) fn new(conformance: Conformance, message: impl Into<String>) -> Self {
+        Self {
+            conformance,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConformanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} violation: {}", self.conformance, self.message)
+    }
+}
+
+impl Error for ConformanceError {}
+
+/// Check the page dimensions against the implementation limits required by PDF/A.
+pub(crate) fn check_page_size(
+    conformance: Conformance,
+    page: usize,
+    width: f64,
+    height: f64,
+) -> Result<(), ConformanceError> {
+    if !conformance.is_pdfa() {
+        return Ok(());
+    }
+    let valid = |v: f64| (MIN_PAGE_DIMENSION..=MAX_PAGE_DIMENSION).contains(&v);
+    if valid(width) && valid(height) {
+        Ok(())
+    } else {
+        Err(ConformanceError::new(
+            conformance,
+            format!(
+                "page {} is {}pt x {}pt, but dimensions must be between {}pt and {}pt",
+                page + 1,
+                width,
+                height,
+                MIN_PAGE_DIMENSION,
+                MAX_PAGE_DIMENSION
+            ),
+        ))
+    }
+}
+
+/// Check the encoded length of a text string against the limits required by PDF/A.
+pub(crate) fn check_string(
+    conformance: Conformance,
+    what: &str,
+    value: &str,
+) -> Result<(), ConformanceError> {
+    if !conformance.is_pdfa() {
+        return Ok(());
+    }
+    // Non-ASCII text is written as UTF-16 with a byte order mark
+    let len = if value.is_ascii() {
+        value.len()
+    } else {
+        2 + 2 * value.encode_utf16().count()
+    };
+    if len > MAX_STRING_LENGTH {
+        return Err(ConformanceError::new(
+            conformance,
+            format!("{what} exceeds {MAX_STRING_LENGTH} bytes"),
+        ));
+    }
+    Ok(())
+}
+
+/// Output intent dictionary referencing the sRGB profile object.
+pub(crate) fn output_intent(profile_obj: usize) -> String {
+    format!(
+        "<< /Type /OutputIntent /S /GTS_PDFA1 /OutputConditionIdentifier ({0}) /Info ({0}) /DestOutputProfile {1} 0 R >>",
+        SRGB_DESCRIPTION, profile_obj
+    )
+}
+
+/// Build a compact ICC v2 display profile describing sRGB (D50 adapted colorants).
+pub(crate) fn srgb_icc_profile() -> Vec<u8> {
+    fn s15f16(v: f64) -> [u8; 4] {
+        ((v * 65536.0).round() as i32).to_be_bytes()
+    }
+    fn xyz(x: f64, y: f64, z: f64) -> Vec<u8> {
+        let mut tag = b"XYZ \0\0\0\0".to_vec();
+        tag.extend_from_slice(&s15f16(x));
+        tag.extend_from_slice(&s15f16(y));
+        tag.extend_from_slice(&s15f16(z));
+        tag
+    }
+
+    // sRGB transfer function sampled into a curve table.
+    let mut trc = b"curv\0\0\0\0".to_vec();
+    let n_samples = 1024u32;
+    trc.extend_from_slice(&n_samples.to_be_bytes());
+    for i in 0..n_samples {
+        let v = i as f64 / (n_samples - 1) as f64;
+        let linear = if v <= 0.04045 {
+            v / 12.92
+        } else {
+            ((v + 0.055) / 1.055).powf(2.4)
+        };
+        trc.extend_from_slice(&((linear * 65535.0).round() as u16).to_be_bytes());
+    }
+
+    let mut desc = b"desc\0\0\0\0".to_vec();
+    desc.extend_from_slice(&(SRGB_DESCRIPTION.len() as u32 + 1).to_be_bytes());
+    desc.extend_from_slice(SRGB_DESCRIPTION.as_bytes());
+    desc.push(0);
+    desc.extend_from_slice(&[0; 4 + 4 + 2 + 1 + 67]); // empty Unicode and ScriptCode records
+
+    let mut cprt = b"text\0\0\0\0".to_vec();
+    cprt.extend_from_slice(b"No copyright, use freely\0");
+
+    let d50 = xyz(0.9642, 1.0, 0.8249);
+    let tags: Vec<(&[u8; 4], Vec<u8>)> = vec![
+        (b"desc", desc),
+        (b"cprt", cprt),
+        (b"wtpt", d50.clone()),
+        (b"rXYZ", xyz(0.4361, 0.2225, 0.0139)),
+        (b"gXYZ", xyz(0.3851, 0.7169, 0.0971)),
+        (b"bXYZ", xyz(0.1431, 0.0606, 0.7141)),
+        (b"rTRC", trc.clone()),
+        (b"gTRC", trc.clone()),
+        (b"bTRC", trc),
+    ];
+
+    let header_len = 128 + 4 + 12 * tags.len();
+    let mut table = Vec::new();
+    let mut data: Vec<u8> = Vec::new();
+    let mut trc_offset = None;
+    for (signature, tag) in &tags {
+        let is_trc = &signature[1..] == b"TRC";
+        let offset = match (is_trc, trc_offset) {
+            (true, Some(offset)) => offset,
+            _ => {
+                let offset = header_len + data.len();
+                data.extend_from_slice(tag);
+                while !data.len().is_multiple_of(4) {
+                    data.push(0);
+                }
+                if is_trc {
+                    trc_offset = Some(offset);
+                }
+                offset
+            }
+        };
+        table.extend_from_slice(*signature);
+        table.extend_from_slice(&(offset as u32).to_be_bytes());
+        table.extend_from_slice(&(tag.len() as u32).to_be_bytes());
+    }
+
+    let size = header_len + data.len();
+    let mut profile = Vec::with_capacity(size);
+    profile.extend_from_slice(&(size as u32).to_be_bytes());
+    profile.extend_from_slice(&[0; 4]); // preferred CMM
+    profile.extend_from_slice(&[0x02, 0x10, 0, 0]); // version 2.1
+    profile.extend_from_slice(b"mntrRGB XYZ ");
+    for part in [2025u16, 1, 1, 0, 0, 0] {
+        profile.extend_from_slice(&part.to_be_bytes());
+    }
+    profile.extend_from_slice(b"acsp");
+    profile.extend_from_slice(&[0; 4 + 4 + 4 + 4 + 8]); // platform, flags, device, attributes
+    profile.extend_from_slice(&[0; 4]); // perceptual rendering intent
+    profile.extend_from_slice(&d50[8..]);
+    profile.extend_from_slice(&[0; 4 + 16 + 28]); // creator, profile ID, reserved
+    profile.extend_from_slice(&(tags.len() as u32).to_be_bytes());
+    profile.extend_from_slice(&table);
+    profile.extend_from_slice(&data);
+    profile
+}
+
+/// Incremental 128-bit FNV-1a hash, used to derive stable document identifiers.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Fnv1a128(u128);
+
+impl Fnv1a128 {
+    const OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+
+    pub fn new() -> Self {
+        Self(Self::OFFSET)
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.0 = data.iter().fold(self.0, |hash, byte| {
+            (hash ^ *byte as u128).wrapping_mul(Self::PRIME)
+        });
+    }
+
+    pub fn finish(&self) -> u128 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn icc_profile_is_well_formed() {
+        let profile = srgb_icc_profile();
+        let size = u32::from_be_bytes(profile[0..4].try_into().unwrap()) as usize;
+        assert_eq!(size, profile.len());
+        assert_eq!(&profile[36..40], b"acsp");
+        assert_eq!(&profile[12..24], b"mntrRGB XYZ ");
+        assert!(profile.len().is_multiple_of(4));
+    }
+
+    #[test]
+    fn page_size_limits() {
+        assert!(check_page_size(Conformance::PdfA2b, 0, 612.0, 792.0).is_ok());
+        assert!(check_page_size(Conformance::PdfA2b, 0, 20000.0, 792.0).is_err());
+        assert!(check_page_size(Conformance::None, 0, 20000.0, 792.0).is_ok());
+        let long = "a".repeat(MAX_STRING_LENGTH + 1);
+        assert!(check_string(Conformance::PdfA2b, "title", &long).is_err());
+        assert!(check_string(Conformance::None, "title", &long).is_ok());
+    }
+}
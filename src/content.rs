@@ -0,0 +1,141 @@
+use crate::number::{write_matrix, write_number, write_op, COLOR_PRECISION};
+use crate::shapes::CapType;
+
+/// Graphics state parameters set by the drawing operators (`None` if unknown).
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+struct GraphicsState {
+    stroke_color: Option<(f64, f64, f64)>,
+    fill_color: Option<(f64, f64, f64)>,
+    line_width: Option<f64>,
+    cap: Option<i32>,
+}
+
+/// Content stream of a page or symbol.
+///
+/// The stream keeps track of the graphics state, so that operators setting
+/// the color, line width or cap style are only written when the value changes.
+#[derive(Debug, Default, Clone)]
+pub struct ContentStream {
+    pub(crate) bytes: Vec<u8>,
+    state: GraphicsState,
+    saved: Vec<GraphicsState>,
+}
+
+impl ContentStream {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Encoded operators written so far.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Append operators that do not change the tracked graphics state
+    /// (e.g. marked content or balanced `q`/`Q` pairs).
+    pub(crate) fn extend_from_slice(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    /// Append the operators of `other`, taking on the graphics state it leaves set.
+    ///
+    /// Parameters that `other` never set (outside its own `q`/`Q` pairs) keep their value.
+    pub(crate) fn append(&mut self, other: &ContentStream) {
+        self.bytes.extend_from_slice(&other.bytes);
+        let (state, set) = (&mut self.state, other.state);
+        state.stroke_color = set.stroke_color.or(state.stroke_color);
+        state.fill_color = set.fill_color.or(state.fill_color);
+        state.line_width = set.line_width.or(state.line_width);
+        state.cap = set.cap.or(state.cap);
+    }
+
+    /// Append an operator with its operands, e.g. `10 20 m`.
+    pub(crate) fn op(&mut self, operands: &[f64], operator: &str, decimals: u8) {
+        write_op(&mut self.bytes, operands, operator, decimals);
+    }
+
+    /// Append a `cm` operator.
+    pub(crate) fn transform(&mut self, matrix: [f64; 6], decimals: u8) {
+        write_matrix(&mut self.bytes, matrix, decimals);
+    }
+
+    pub(crate) fn stroke_color(&mut self, (r, g, b): (f64, f64, f64)) {
+        if self.state.stroke_color != Some((r, g, b)) {
+            self.state.stroke_color = Some((r, g, b));
+            write_op(&mut self.bytes, &[r, g, b], "RG", COLOR_PRECISION);
+        }
+    }
+
+    pub(crate) fn fill_color(&mut self, (r, g, b): (f64, f64, f64)) {
+        if self.state.fill_color != Some((r, g, b)) {
+            self.state.fill_color = Some((r, g, b));
+            write_op(&mut self.bytes, &[r, g, b], "rg", COLOR_PRECISION);
+        }
+    }
+
+    pub(crate) fn line_width(&mut self, width: f64, decimals: u8) {
+        if self.state.line_width != Some(width) {
+            self.state.line_width = Some(width);
+            write_number(&mut self.bytes, width, decimals);
+            self.bytes.extend_from_slice(b" w\n");
+        }
+    }
+
+    pub(crate) fn cap(&mut self, cap_type: CapType) {
+        let cap = cap_type.to_int();
+        if self.state.cap != Some(cap) {
+            self.state.cap = Some(cap);
+            self.bytes
+                .extend_from_slice(format!("{} J\n", cap).as_bytes());
+        }
+    }
+
+    /// Save the graphics state (`q`).
+    pub(crate) fn save(&mut self) {
+        self.saved.push(self.state);
+        self.bytes.extend_from_slice(b"q\n");
+    }
+
+    /// Restore the graphics state saved by the matching [`save`](Self::save) (`Q`).
+    pub(crate) fn restore(&mut self) {
+        self.state = self.saved.pop().unwrap_or_default();
+        self.bytes.extend_from_slice(b"Q\n");
+    }
+
+    /// Take the encoded operators, leaving an empty stream.
+    pub(crate) fn take_bytes(&mut self) -> Vec<u8> {
+        std::mem::take(self).bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redundant_state_operators_are_skipped() {
+        let mut content = ContentStream::new();
+        for _ in 0..2 {
+            content.stroke_color((1., 0., 0.));
+            content.line_width(2., 3);
+            content.cap(CapType::Round);
+        }
+        content.save();
+        content.fill_color((0., 0., 1.));
+        content.restore();
+        content.fill_color((0., 0., 1.));
+        assert_eq!(
+            String::from_utf8(content.take_bytes()).unwrap(),
+            "1 0 0 RG\n2 w\n1 J\nq\n0 0 1 rg\nQ\n0 0 1 rg\n"
+        );
+        assert!(content.is_empty());
+    }
+}
@@ -0,0 +1,875 @@
+use crate::drawable::{Canvas, Drawable};
+use crate::error::Error;
+use crate::generator::Generator;
+use crate::link::Link;
+use crate::page::PageOptions;
+use crate::path::{FillRule, Path};
+use crate::shapes::{union_rect, Anchor, CapType, Shape, ShapeType};
+use crate::symbol::SymbolId;
+use crate::tag::Tag;
+use crate::transform::Transform;
+use crate::units::*;
+use std::collections::HashSet;
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Source of new node ids, shared by all threads so that nodes built anywhere differ.
+static NEXT_NODE_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Identifier of a [`Node`], unique within its page.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(u64);
+
+impl NodeId {
+    fn next() -> Self {
+        NodeId(NEXT_NODE_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// Handle to a symbol defined with [`Document::define_symbol`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DocumentSymbolId(usize);
+
+/// Document kept as a scene of nodes that can be inspected and changed before
+/// it is written with [`render`](Self::render).
+///
+/// Unlike drawing on a [`Generator`], where shapes are encoded as soon as they
+/// are drawn, a document owns its [`Page`]s and their [`Node`]s until it is rendered.
+///
+/// # Example
+/// ```
+/// use shapdf::*;
+/// let mut document = Document::new();
+/// let page = document.add_page(Page::a4());
+/// let frame = page.add(ShapeNode::rectangle(Mm(20.), Mm(20.), Mm(50.), Mm(30.)));
+/// let dot = page.add(ShapeNode::circle(Mm(45.), Mm(35.), Mm(5.)).with_color(NamedColor("red")));
+/// // Move the dot and draw the frame over it
+/// if let Some(NodeKind::Shape(shape)) = page.get_mut(dot).map(|node| &mut node.kind) {
+///     shape.geometry = Geometry::Circle { x: 100., y: 100., radius: 10. };
+/// }
+/// page.bring_to_front(frame);
+///
+/// let mut generator = Generator::new("output/document.pdf".into());
+/// document.render(&mut generator).unwrap();
+/// generator.write_pdf().unwrap();
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Document {
+    pages: Vec<Page>,
+    symbols: Vec<Vec<Node>>,
+}
+
+impl Document {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a page and return it for adding nodes.
+    pub fn add_page(&mut self, page: Page) -> &mut Page {
+        self.insert_page(self.pages.len(), page)
+    }
+
+    /// Insert a page at the zero-based `index` (appending if `index` is past the end).
+    pub fn insert_page(&mut self, index: usize, page: Page) -> &mut Page {
+        let index = index.min(self.pages.len());
+        self.pages.insert(index, page);
+        &mut self.pages[index]
+    }
+
+    /// Remove the page at `index`, if any.
+    pub fn remove_page(&mut self, index: usize) -> Option<Page> {
+        (index < self.pages.len()).then(|| self.pages.remove(index))
+    }
+
+    /// Move the page at `from` to `to` (clamped to the last page).
+    pub fn move_page(&mut self, from: usize, to: usize) {
+        if from < self.pages.len() {
+            let page = self.pages.remove(from);
+            self.pages.insert(to.min(self.pages.len()), page);
+        }
+    }
+
+    pub fn page(&self, index: usize) -> Option<&Page> {
+        self.pages.get(index)
+    }
+
+    pub fn page_mut(&mut self, index: usize) -> Option<&mut Page> {
+        self.pages.get_mut(index)
+    }
+
+    pub fn pages(&self) -> &[Page] {
+        &self.pages
+    }
+
+    pub fn pages_mut(&mut self) -> &mut [Page] {
+        &mut self.pages
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Define a reusable symbol from `nodes`, to be placed with [`SymbolInstance`]s.
+    ///
+    /// Symbols are written once as Form XObjects (see [`Generator::define_symbol`]).
+    pub fn define_symbol(&mut self, nodes: impl IntoIterator<Item = Node>) -> DocumentSymbolId {
+        self.symbols.push(nodes.into_iter().collect());
+        DocumentSymbolId(self.symbols.len() - 1)
+    }
+
+    pub fn symbol(&self, symbol: DocumentSymbolId) -> Option<&[Node]> {
+        self.symbols.get(symbol.0).map(Vec::as_slice)
+    }
+
+    pub fn symbol_mut(&mut self, symbol: DocumentSymbolId) -> Option<&mut Vec<Node>> {
+        self.symbols.get_mut(symbol.0)
+    }
+
+    /// Draw the document into `generator`, appending its pages.
+    ///
+    /// Nodes are drawn in the order of their [`z_index`](Node::z_index), and in
+    /// insertion order for equal indices. Options not set on a shape are taken
+    /// from the style of the generator.
+    pub fn render<W: Write>(&self, generator: &mut Generator<W>) -> Result<(), Error> {
+        let mut symbols: Vec<SymbolId> = Vec::with_capacity(self.symbols.len());
+        for nodes in &self.symbols {
+            let mut result = Ok(());
+            let id = generator.define_symbol(|g| result = draw_nodes(g, nodes, &symbols));
+            result?;
+            symbols.push(id);
+        }
+        for page in &self.pages {
+            generator.add_page_with_options(Pt(page.width), Pt(page.height), page.options.clone());
+            draw_nodes(generator, &page.nodes, &symbols)?;
+        }
+        Ok(())
+    }
+}
+
+/// Page of a [`Document`], holding its nodes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page {
+    /// Width in points.
+    pub width: f64,
+    /// Height in points.
+    pub height: f64,
+    pub options: PageOptions,
+    nodes: Vec<Node>,
+}
+
+impl Page {
+    pub fn new<L: Length>(width: L, height: L) -> Self {
+        Self {
+            width: width.to_points(),
+            height: height.to_points(),
+            options: PageOptions::default(),
+            nodes: Vec::new(),
+        }
+    }
+
+    pub fn a4() -> Self {
+        Self::new(Mm(210.0), Mm(297.0))
+    }
+
+    pub fn letter() -> Self {
+        Self::new(Inch(8.5), Inch(11.0))
+    }
+
+    /// Set the page boxes, rotation or page label (see [`PageOptions`]).
+    pub fn with_options(mut self, options: PageOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Add a node on top of the page, returning its id.
+    ///
+    /// A node (or child of a group) whose id is already used on the page, such as a clone
+    /// of a node on it, gets a new id.
+    pub fn add(&mut self, node: impl Into<Node>) -> NodeId {
+        let mut node = node.into();
+        let mut used: HashSet<NodeId> = self.iter().map(|node| node.id).collect();
+        renumber_duplicates(&mut node, &mut used);
+        let id = node.id;
+        self.nodes.push(node);
+        id
+    }
+
+    /// Top-level nodes in insertion order.
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    pub fn nodes_mut(&mut self) -> &mut Vec<Node> {
+        &mut self.nodes
+    }
+
+    /// All nodes, including the children of groups, depth first.
+    pub fn iter(&self) -> impl Iterator<Item = &Node> {
+        let mut all = Vec::new();
+        collect(&self.nodes, &mut all);
+        all.into_iter()
+    }
+
+    /// Find a node by id, also inside groups.
+    pub fn get(&self, id: NodeId) -> Option<&Node> {
+        self.iter().find(|node| node.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+        find_mut(&mut self.nodes, id)
+    }
+
+    /// Remove a node (with its children), also inside groups.
+    pub fn remove(&mut self, id: NodeId) -> Option<Node> {
+        remove(&mut self.nodes, id)
+    }
+
+    /// Bounding box `[x1, y1, x2, y2]` in points of all nodes, with symbol
+    /// instances resolved in `document`.
+    pub fn bounds(&self, document: &Document) -> Option<[f64; 4]> {
+        union_bounds(&self.nodes, document)
+    }
+
+    /// Top-level nodes painting the point `(x, y)`, topmost first.
+    pub fn nodes_at(&self, document: &Document, x: impl Length, y: impl Length) -> Vec<NodeId> {
+        let point = (x.to_points(), y.to_points());
+        let mut hits: Vec<&Node> = self
+            .nodes
+            .iter()
+            .filter(|node| node.contains_point(document, point))
+            .collect();
+        hits.sort_by_key(|node| node.z_index);
+        hits.iter().rev().map(|node| node.id).collect()
+    }
+
+    /// Draw a node above its siblings, returning whether it was found.
+    pub fn bring_to_front(&mut self, id: NodeId) -> bool {
+        self.restack(id, |siblings| siblings.map(|z| z.saturating_add(1)).max())
+    }
+
+    /// Draw a node below its siblings, returning whether it was found.
+    pub fn send_to_back(&mut self, id: NodeId) -> bool {
+        self.restack(id, |siblings| siblings.map(|z| z.saturating_sub(1)).min())
+    }
+
+    fn restack(
+        &mut self,
+        id: NodeId,
+        z_index: impl FnOnce(&mut dyn Iterator<Item = i32>) -> Option<i32>,
+    ) -> bool {
+        let Some(siblings) = siblings_mut(&mut self.nodes, id) else {
+            return false;
+        };
+        let mut others = siblings
+            .iter()
+            .filter(|node| node.id != id)
+            .map(|node| node.z_index);
+        if let Some(z) = z_index(&mut others) {
+            if let Some(node) = siblings.iter_mut().find(|node| node.id == id) {
+                node.z_index = z;
+            }
+        }
+        true
+    }
+}
+
+/// Element of a [`Page`]: a shape, a group or a symbol instance.
+///
+/// A clone keeps the id of the node, so that it can be put back after editing; adding
+/// it next to the original gives it a new id.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    id: NodeId,
+    /// Nodes with a higher index are drawn over their siblings.
+    pub z_index: i32,
+    pub kind: NodeKind,
+}
+
+impl Node {
+    /// Create a node with a new id.
+    pub fn new(kind: NodeKind) -> Self {
+        Self {
+            id: NodeId::next(),
+            z_index: 0,
+            kind,
+        }
+    }
+
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    pub fn with_z_index(mut self, z_index: i32) -> Self {
+        self.z_index = z_index;
+        self
+    }
+
+    /// Bounding box `[x1, y1, x2, y2]` of the node in the space of its parent, with
+    /// symbol instances resolved in `document`.
+    pub fn bounds(&self, document: &Document) -> Option<[f64; 4]> {
+        match &self.kind {
+            NodeKind::Shape(shape) => shape.bounds(),
+            NodeKind::Group(group) => {
+                union_bounds(&group.children, document).map(|rect| group.transform.apply_rect(rect))
+            }
+            NodeKind::Symbol(instance) => union_bounds(document.symbol(instance.symbol)?, document)
+                .map(|rect| instance.placement().apply_rect(rect)),
+        }
+    }
+
+    /// Whether the node paints the point `(x, y)` of the space of its parent.
+    pub fn contains(&self, document: &Document, x: impl Length, y: impl Length) -> bool {
+        self.contains_point(document, (x.to_points(), y.to_points()))
+    }
+
+    fn contains_point(&self, document: &Document, (x, y): (f64, f64)) -> bool {
+        let children_contain = |nodes: &[Node], t: Transform| {
+            let Some((x, y)) = t.inverse().map(|inverse| inverse.apply(x, y)) else {
+                return false;
+            };
+            nodes
+                .iter()
+                .any(|node| node.contains_point(document, (x, y)))
+        };
+        match &self.kind {
+            NodeKind::Shape(shape) => shape.contains(Pt(x), Pt(y)),
+            NodeKind::Group(group) => children_contain(&group.children, group.transform),
+            NodeKind::Symbol(instance) => document
+                .symbol(instance.symbol)
+                .is_some_and(|nodes| children_contain(nodes, instance.placement())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeKind {
+    Shape(ShapeNode),
+    Group(Group),
+    Symbol(SymbolInstance),
+}
+
+impl From<NodeKind> for Node {
+    fn from(kind: NodeKind) -> Self {
+        Node::new(kind)
+    }
+}
+
+impl From<ShapeNode> for Node {
+    fn from(shape: ShapeNode) -> Self {
+        Node::new(NodeKind::Shape(shape))
+    }
+}
+
+impl From<Group> for Node {
+    fn from(group: Group) -> Self {
+        Node::new(NodeKind::Group(group))
+    }
+}
+
+impl From<SymbolInstance> for Node {
+    fn from(instance: SymbolInstance) -> Self {
+        Node::new(NodeKind::Symbol(instance))
+    }
+}
+
+/// Geometry of a [`ShapeNode`] in points.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Geometry {
+    Line {
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+    },
+    Circle {
+        x: f64,
+        y: f64,
+        radius: f64,
+    },
+    Rectangle {
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    },
+    /// Closed polygon through `points`.
+    Polygon {
+        points: Vec<(f64, f64)>,
+    },
+    Path(Path),
+}
+
+/// Shape owned by a [`Page`], with the same options as a [`Shape`](crate::Shape).
+///
+/// Shape nodes are also [`Drawable`], e.g. to draw them directly with [`Generator::draw`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapeNode {
+    pub geometry: Geometry,
+    pub width: Option<f64>,
+    pub angle: Option<f64>, // angle in radians
+    pub anchor: Option<Anchor>,
+    pub cap_type: Option<CapType>,
+    pub color: Option<(f64, f64, f64)>,
+    /// Fill rule of polygons and paths.
+    pub fill_rule: Option<FillRule>,
+    /// Color of the outline of polygons and paths, which are only filled if `None`.
+    pub stroke: Option<(f64, f64, f64)>,
+    pub link: Option<Link>,
+    pub tag: Option<Tag>,
+}
+
+impl ShapeNode {
+    pub fn new(geometry: Geometry) -> Self {
+        Self {
+            geometry,
+            width: None,
+            angle: None,
+            anchor: None,
+            cap_type: None,
+            color: None,
+            fill_rule: None,
+            stroke: None,
+            link: None,
+            tag: None,
+        }
+    }
+
+    pub fn line(x1: impl Length, y1: impl Length, x2: impl Length, y2: impl Length) -> Self {
+        Self::new(Geometry::Line {
+            x1: x1.to_points(),
+            y1: y1.to_points(),
+            x2: x2.to_points(),
+            y2: y2.to_points(),
+        })
+    }
+
+    pub fn circle(x: impl Length, y: impl Length, radius: impl Length) -> Self {
+        Self::new(Geometry::Circle {
+            x: x.to_points(),
+            y: y.to_points(),
+            radius: radius.to_points(),
+        })
+    }
+
+    pub fn rectangle(
+        x: impl Length,
+        y: impl Length,
+        width: impl Length,
+        height: impl Length,
+    ) -> Self {
+        Self::new(Geometry::Rectangle {
+            x: x.to_points(),
+            y: y.to_points(),
+            width: width.to_points(),
+            height: height.to_points(),
+        })
+    }
+
+    pub fn polygon<L: Length>(points: &[(L, L)]) -> Self {
+        Self::new(Geometry::Polygon {
+            points: points
+                .iter()
+                .map(|(x, y)| (x.to_points(), y.to_points()))
+                .collect(),
+        })
+    }
+
+    pub fn path(path: Path) -> Self {
+        Self::new(Geometry::Path(path))
+    }
+
+    pub fn with_width(mut self, width: impl Length) -> Self {
+        self.width = Some(width.to_points());
+        self
+    }
+
+    pub fn with_angle(mut self, angle: impl Angle) -> Self {
+        self.angle = Some(angle.to_radians());
+        self
+    }
+
+    pub fn with_anchor(mut self, anchor: Anchor) -> Self {
+        self.anchor = Some(anchor);
+        self
+    }
+
+    pub fn with_cap_type(mut self, cap_type: CapType) -> Self {
+        self.cap_type = Some(cap_type);
+        self
+    }
+
+    pub fn with_color(mut self, color: impl Color) -> Self {
+        self.color = Some(color.to_rgb());
+        self
+    }
+
+    pub fn with_fill_rule(mut self, fill_rule: FillRule) -> Self {
+        self.fill_rule = Some(fill_rule);
+        self
+    }
+
+    pub fn with_stroke(mut self, color: impl Color) -> Self {
+        self.stroke = Some(color.to_rgb());
+        self
+    }
+
+    pub fn with_link(mut self, link: Link) -> Self {
+        self.link = Some(link);
+        self
+    }
+
+    pub fn with_tag(mut self, tag: Tag) -> Self {
+        self.tag = Some(tag);
+        self
+    }
+}
+
+impl ShapeNode {
+    /// Bounding box `[x1, y1, x2, y2]` in points (see [`Shape::bounds`](crate::Shape::bounds)).
+    ///
+    /// Options not set on the node are taken from the default [`Style`](crate::Style).
+    pub fn bounds(&self) -> Option<[f64; 4]> {
+        self.shape().bounds()
+    }
+
+    /// Whether the point `(x, y)` is on the painted area of the shape.
+    pub fn contains(&self, x: impl Length, y: impl Length) -> bool {
+        self.shape().contains(x, y)
+    }
+
+    /// Detached shape with the geometry and options of the node.
+    fn shape(&self) -> Shape<'static> {
+        let (enum_type, x, y, radius, path) = match &self.geometry {
+            Geometry::Line { x1, y1, x2, y2 } => (
+                ShapeType::Line,
+                vec![*x1, *x2],
+                vec![*y1, *y2],
+                None,
+                Path::new(),
+            ),
+            Geometry::Circle { x, y, radius } => (
+                ShapeType::Circle,
+                vec![*x],
+                vec![*y],
+                Some(*radius),
+                Path::new(),
+            ),
+            Geometry::Rectangle {
+                x,
+                y,
+                width,
+                height,
+            } => (
+                ShapeType::Rectangle,
+                vec![*x, *width],
+                vec![*y, *height],
+                None,
+                Path::new(),
+            ),
+            Geometry::Polygon { points } => (
+                ShapeType::Polygon,
+                Vec::new(),
+                Vec::new(),
+                None,
+                polygon_path(points),
+            ),
+            Geometry::Path(path) => (ShapeType::Path, Vec::new(), Vec::new(), None, path.clone()),
+        };
+        Shape {
+            enum_type,
+            x,
+            y,
+            radius,
+            width: self.width,
+            angle: self.angle,
+            anchor: self.anchor,
+            cap_type: self.cap_type,
+            color: self.color,
+            path,
+            fill_rule: self.fill_rule,
+            stroke: self.stroke,
+            ..Default::default()
+        }
+    }
+}
+
+impl Drawable for ShapeNode {
+    fn draw(&self, canvas: &mut Canvas<'_>) -> Result<(), Error> {
+        let mut shape = match &self.geometry {
+            Geometry::Line { x1, y1, x2, y2 } => canvas.line(Pt(*x1), Pt(*y1), Pt(*x2), Pt(*y2)),
+            Geometry::Circle { x, y, radius } => canvas.circle(Pt(*x), Pt(*y), Pt(*radius)),
+            Geometry::Rectangle {
+                x,
+                y,
+                width,
+                height,
+            } => canvas.rectangle(Pt(*x), Pt(*y), Pt(*width), Pt(*height)),
+            Geometry::Polygon { points } => {
+                let points: Vec<(Pt, Pt)> = points.iter().map(|(x, y)| (Pt(*x), Pt(*y))).collect();
+                canvas.polygon(&points)
+            }
+            Geometry::Path(path) => canvas.path(path.clone()),
+        };
+        shape.width = self.width;
+        shape.angle = self.angle;
+        shape.anchor = self.anchor;
+        shape.cap_type = self.cap_type;
+        shape.color = self.color;
+        shape.fill_rule = self.fill_rule;
+        shape.stroke = self.stroke;
+        shape.link = self.link.clone();
+        shape.tag = self.tag.clone();
+        shape.draw()
+    }
+}
+
+/// Nodes drawn with a common transform.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Group {
+    pub transform: Transform,
+    pub children: Vec<Node>,
+}
+
+impl Group {
+    pub fn new(transform: Transform) -> Self {
+        Self {
+            transform,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn with_child(mut self, node: impl Into<Node>) -> Self {
+        self.add(node);
+        self
+    }
+
+    /// Add a child on top of the group, returning its id (new if already used in the group).
+    pub fn add(&mut self, node: impl Into<Node>) -> NodeId {
+        let mut node = node.into();
+        let mut all = Vec::new();
+        collect(&self.children, &mut all);
+        let mut used: HashSet<NodeId> = all.iter().map(|node| node.id).collect();
+        renumber_duplicates(&mut node, &mut used);
+        let id = node.id;
+        self.children.push(node);
+        id
+    }
+}
+
+/// Placement of a symbol defined with [`Document::define_symbol`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SymbolInstance {
+    pub symbol: DocumentSymbolId,
+    /// Origin of the symbol in points.
+    pub x: f64,
+    pub y: f64,
+    /// Transform applied before moving the symbol to `(x, y)`.
+    pub transform: Transform,
+}
+
+impl SymbolInstance {
+    pub fn new(symbol: DocumentSymbolId, x: impl Length, y: impl Length) -> Self {
+        Self {
+            symbol,
+            x: x.to_points(),
+            y: y.to_points(),
+            transform: Transform::identity(),
+        }
+    }
+
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    /// Transform from the symbol space to the space of the parent.
+    fn placement(&self) -> Transform {
+        self.transform
+            .then(Transform::translate(Pt(self.x), Pt(self.y)))
+    }
+}
+
+/// Draw `nodes` by z-index, with `symbols` mapping document symbols to the generator.
+fn draw_nodes<W: Write>(
+    generator: &mut Generator<W>,
+    nodes: &[Node],
+    symbols: &[SymbolId],
+) -> Result<(), Error> {
+    let mut ordered: Vec<&Node> = nodes.iter().collect();
+    ordered.sort_by_key(|node| node.z_index);
+    for node in ordered {
+        match &node.kind {
+            NodeKind::Shape(shape) => generator.draw(shape)?,
+            NodeKind::Group(group) => generator
+                .with_transform(group.transform, |g| draw_nodes(g, &group.children, symbols))?,
+            NodeKind::Symbol(instance) => {
+                let symbol = *symbols.get(instance.symbol.0).ok_or(Error::UnknownSymbol)?;
+                generator.place(symbol, Pt(instance.x), Pt(instance.y), instance.transform)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn polygon_path(points: &[(f64, f64)]) -> Path {
+    let points: Vec<(Pt, Pt)> = points.iter().map(|(x, y)| (Pt(*x), Pt(*y))).collect();
+    Path::polygon(&points)
+}
+
+/// Give `node` and its children ids that are not in `used`, adding them to it.
+fn renumber_duplicates(node: &mut Node, used: &mut HashSet<NodeId>) {
+    while !used.insert(node.id) {
+        node.id = NodeId::next();
+    }
+    if let NodeKind::Group(group) = &mut node.kind {
+        for child in &mut group.children {
+            renumber_duplicates(child, used);
+        }
+    }
+}
+
+fn union_bounds(nodes: &[Node], document: &Document) -> Option<[f64; 4]> {
+    nodes
+        .iter()
+        .filter_map(|node| node.bounds(document))
+        .reduce(|acc, rect| union_rect(Some(acc), rect))
+}
+
+fn collect<'a>(nodes: &'a [Node], all: &mut Vec<&'a Node>) {
+    for node in nodes {
+        all.push(node);
+        if let NodeKind::Group(group) = &node.kind {
+            collect(&group.children, all);
+        }
+    }
+}
+
+fn find_mut(nodes: &mut [Node], id: NodeId) -> Option<&mut Node> {
+    for node in nodes {
+        if node.id == id {
+            return Some(node);
+        }
+        if let NodeKind::Group(group) = &mut node.kind {
+            if let Some(found) = find_mut(&mut group.children, id) {
+                return Some(found);
+            }
+        }
+    }
+    None
+}
+
+fn remove(nodes: &mut Vec<Node>, id: NodeId) -> Option<Node> {
+    if let Some(index) = nodes.iter().position(|node| node.id == id) {
+        return Some(nodes.remove(index));
+    }
+    nodes.iter_mut().find_map(|node| match &mut node.kind {
+        NodeKind::Group(group) => remove(&mut group.children, id),
+        _ => None,
+    })
+}
+
+/// List of nodes containing the node `id`.
+fn siblings_mut(nodes: &mut Vec<Node>, id: NodeId) -> Option<&mut Vec<Node>> {
+    if nodes.iter().any(|node| node.id == id) {
+        return Some(nodes);
+    }
+    nodes.iter_mut().find_map(|node| match &mut node.kind {
+        NodeKind::Group(group) => siblings_mut(&mut group.children, id),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn render(document: &Document) -> String {
+        let mut generator = Generator::new(PathBuf::new());
+        generator.set_reproducible(true);
+        document.render(&mut generator).unwrap();
+        String::from_utf8_lossy(&generator.to_pdf_bytes().unwrap()).into_owned()
+    }
+
+    #[test]
+    fn nodes_are_drawn_by_z_index() {
+        let mut document = Document::new();
+        let page = document.add_page(Page::new(Pt(100.), Pt(100.)));
+        let red = page.add(ShapeNode::circle(Pt(10.), Pt(10.), Pt(5.)).with_color(Rgb(1., 0., 0.)));
+        page.add(ShapeNode::circle(Pt(20.), Pt(10.), Pt(5.)).with_color(Rgb(0., 0., 1.)));
+        let text = render(&document);
+        assert!(text.find("1 0 0 RG").unwrap() < text.find("0 0 1 RG").unwrap());
+
+        let page = document.page_mut(0).unwrap();
+        assert!(page.bring_to_front(red));
+        let text = render(&document);
+        assert!(text.find("0 0 1 RG").unwrap() < text.find("1 0 0 RG").unwrap());
+    }
+
+    #[test]
+    fn cloned_nodes_get_new_ids() {
+        let mut document = Document::new();
+        let page = document.add_page(Page::a4());
+        let triangle = Node::from(
+            ShapeNode::polygon(&[(Pt(0.), Pt(0.)), (Pt(10.), Pt(0.)), (Pt(5.), Pt(8.))])
+                .with_stroke(NamedColor("red")),
+        );
+        let first = page.add(triangle.clone());
+        let second = page.add(triangle.clone());
+        assert_eq!(first, triangle.id());
+        assert_ne!(first, second);
+        assert!(page.remove(second).is_some());
+        assert!(page.get(first).is_some());
+
+        let mut group = Group::new(Transform::identity());
+        let inner = group.add(triangle.clone());
+        assert_ne!(group.add(triangle), inner);
+        let group = page.add(group);
+        let ids: HashSet<NodeId> = page.iter().map(Node::id).collect();
+        assert_eq!(ids.len(), 4);
+        assert!(page.get(group).is_some());
+
+        let path = Path::new()
+            .move_to(Pt(20.), Pt(20.))
+            .line_to(Pt(30.), Pt(20.))
+            .line_to(Pt(25.), Pt(30.));
+        page.add(ShapeNode::path(path).with_fill_rule(FillRule::EvenOdd));
+        let text = render(&document);
+        assert!(text.contains("1 0 0 RG\n1 w\n0 J\n0 0 m\n10 0 l\n5 8 l\nh\nB\n"));
+        assert!(text.contains("25 30 l\nf*\n"));
+    }
+
+    #[test]
+    fn groups_can_be_queried_and_edited() {
+        let mut document = Document::new();
+        let mark =
+            document.define_symbol([ShapeNode::rectangle(Pt(0.), Pt(0.), Pt(2.), Pt(2.)).into()]);
+        let page = document.add_page(Page::a4());
+        let mut group = Group::new(Transform::translate(Pt(50.), Pt(50.)));
+        let line = group.add(
+            ShapeNode::line(Pt(0.), Pt(0.), Pt(10.), Pt(0.))
+                .with_link(Link::uri("https://example.com")),
+        );
+        let dot = group.add(ShapeNode::circle(Pt(0.), Pt(0.), Pt(1.)));
+        group.add(SymbolInstance::new(mark, Pt(5.), Pt(5.)));
+        let group = page.add(group);
+        assert_eq!(page.iter().count(), 4);
+        assert!(page.get(line).is_some());
+
+        assert!(page.remove(dot).is_some());
+        assert!(page.get(dot).is_none());
+        if let Some(NodeKind::Group(group)) = page.get_mut(group).map(|node| &mut node.kind) {
+            group.transform = Transform::translate(Pt(20.), Pt(30.));
+        }
+        let text = render(&document);
+        assert!(text.contains("q\n1 0 0 1 20 30 cm\n"));
+        assert!(!text.contains("0 0 m\n0 0 l\n"));
+        assert!(text.contains("/S0 Do"));
+        let page = document.page(0).unwrap();
+        assert_eq!(page.bounds(&document), Some([20., 29.5, 30., 37.]));
+        assert_eq!(page.nodes_at(&document, Pt(26.), Pt(36.)), vec![group]);
+        assert!(page.nodes_at(&document, Pt(33.), Pt(33.)).is_empty());
+        // The link area is mapped through the group transform
+        assert!(text.contains("/Rect [20 29.5 30 30.5]"));
+    }
+}
@@ -0,0 +1,331 @@
+use crate::content::ContentStream;
+use crate::error::Error;
+use crate::link::LinkArea;
+use crate::path::Path;
+use crate::shapes::{union_rect, Shape, ShapeType};
+use crate::style::Style;
+use crate::tag::StructureTree;
+use crate::transform::Transform;
+use crate::units::*;
+
+/// Something that can be drawn by composing primitives, such as a domain-specific shape.
+///
+/// Draw it with [`Generator::draw`](crate::Generator::draw) or
+/// [`PageBuilder::draw`](crate::PageBuilder::draw). Options not set on the primitives
+/// are inherited from the style of the [`Canvas`].
+///
+/// Owned primitives are drawable as well: a [`ShapeNode`](crate::ShapeNode) keeps a line,
+/// circle, rectangle, polygon or path with its options, and a [`Path`] is filled with the
+/// style of the canvas. A [`Shape`] is not drawable itself, since it already draws on the
+/// page or canvas that created it with [`Shape::draw`]; keep the geometry and options of
+/// a shape to draw later in a [`ShapeNode`](crate::ShapeNode).
+///
+/// # Example
+/// ```
+/// use shapdf::*;
+///
+/// struct Valve {
+///     x: Mm,
+///     y: Mm,
+/// }
+///
+/// impl Drawable for Valve {
+///     fn draw(&self, canvas: &mut Canvas<'_>) -> Result<(), Error> {
+///         canvas.transformed(Transform::translate(self.x, self.y), |canvas| {
+///             canvas.line(Mm(-5.), Mm(-3.), Mm(5.), Mm(3.)).draw()?;
+///             canvas.line(Mm(-5.), Mm(3.), Mm(5.), Mm(-3.)).draw()?;
+///             canvas.circle(Mm(0.), Mm(0.), Mm(1.)).draw()
+///         })
+///     }
+/// }
+///
+/// let mut generator = Generator::new("output/valves.pdf".into());
+/// generator.add_page();
+/// generator.set_default_color(NamedColor("blue"));
+/// let valves: Vec<Valve> = (0..5).map(|i| Valve { x: Mm(20. + 15. * i as f64), y: Mm(20.) }).collect();
+/// generator.draw(&valves[..]).unwrap();
+/// ```
+pub trait Drawable {
+    fn draw(&self, canvas: &mut Canvas<'_>) -> Result<(), Error>;
+}
+
+impl<T: Drawable> Drawable for [T] {
+    fn draw(&self, canvas: &mut Canvas<'_>) -> Result<(), Error> {
+        self.iter().try_for_each(|drawable| canvas.draw(drawable))
+    }
+}
+
+impl Drawable for Path {
+    fn draw(&self, canvas: &mut Canvas<'_>) -> Result<(), Error> {
+        canvas.path(self.clone()).draw()
+    }
+}
+
+impl<T: Drawable + ?Sized> Drawable for Box<T> {
+    fn draw(&self, canvas: &mut Canvas<'_>) -> Result<(), Error> {
+        (**self).draw(canvas)
+    }
+}
+
+/// Content stream that a [`Drawable`] draws on, with the style inherited from
+/// the generator or page.
+pub struct Canvas<'a> {
+    content: &'a mut ContentStream,
+    links: &'a mut Vec<LinkArea>,
+    bounds: &'a mut Option<[f64; 4]>,
+    structure: Option<&'a mut StructureTree>,
+    style: Style,
+    coordinates: Transform,
+}
+
+impl<'a> Canvas<'a> {
+    pub(crate) fn new(
+        content: &'a mut ContentStream,
+        links: &'a mut Vec<LinkArea>,
+        bounds: &'a mut Option<[f64; 4]>,
+        structure: Option<&'a mut StructureTree>,
+        style: Style,
+        coordinates: Transform,
+    ) -> Self {
+        Self {
+            content,
+            links,
+            bounds,
+            structure,
+            style,
+            coordinates,
+        }
+    }
+
+    pub fn get_style(&self) -> Style {
+        self.style
+    }
+
+    /// Set the style of the shapes drawn next, until the end of the current
+    /// [`draw`](Drawable::draw).
+    pub fn set_style(&mut self, style: Style) {
+        self.style = style;
+    }
+
+    /// Draw a nested [`Drawable`], which inherits the current style.
+    pub fn draw(&mut self, drawable: &(impl Drawable + ?Sized)) -> Result<(), Error> {
+        let style = self.style;
+        let result = drawable.draw(self);
+        self.style = style;
+        result
+    }
+
+    /// Run `draw` with `transform` applied to everything it draws.
+    ///
+    /// The transform is applied in the coordinate system of the page (see
+    /// [`Coordinates`](crate::Coordinates)). Link areas are mapped to the bounding
+    /// boxes of the transformed shapes.
+    pub fn transformed(
+        &mut self,
+        transform: Transform,
+        draw: impl FnOnce(&mut Canvas<'_>) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let scope = TransformScope::begin(
+            transform.in_coordinates(&self.coordinates)?,
+            self.content,
+            self.links,
+            self.bounds,
+            self.style.precision,
+        )?;
+        let result = draw(self);
+        scope.end(self.content, self.links, self.bounds);
+        result
+    }
+
+    fn shape(&mut self, enum_type: ShapeType, x: Vec<f64>, y: Vec<f64>) -> Shape<'_> {
+        Shape {
+            content_stream: Some(&mut *self.content),
+            links: Some(&mut *self.links),
+            bounds: Some(&mut *self.bounds),
+            structure: self.structure.as_deref_mut(),
+            style: self.style,
+            coordinates: self.coordinates,
+            enum_type,
+            x,
+            y,
+            ..Default::default()
+        }
+    }
+
+    pub fn line(
+        &mut self,
+        x1: impl Length,
+        y1: impl Length,
+        x2: impl Length,
+        y2: impl Length,
+    ) -> Shape<'_> {
+        self.shape(
+            ShapeType::Line,
+            vec![x1.to_points(), x2.to_points()],
+            vec![y1.to_points(), y2.to_points()],
+        )
+    }
+
+    pub fn circle(&mut self, x: impl Length, y: impl Length, radius: impl Length) -> Shape<'_> {
+        let mut shape = self.shape(ShapeType::Circle, vec![x.to_points()], vec![y.to_points()]);
+        shape.radius = Some(radius.to_points());
+        shape
+    }
+
+    pub fn rectangle(
+        &mut self,
+        x: impl Length,
+        y: impl Length,
+        width: impl Length,
+        height: impl Length,
+    ) -> Shape<'_> {
+        self.shape(
+            ShapeType::Rectangle,
+            vec![x.to_points(), width.to_points()],
+            vec![y.to_points(), height.to_points()],
+        )
+    }
+
+    /// Filled polygon through the points `(x, y)`.
+    pub fn polygon<L: Length>(&mut self, points: &[(L, L)]) -> Shape<'_> {
+        let mut shape = self.shape(ShapeType::Polygon, Vec::new(), Vec::new());
+        shape.path = Path::polygon(points);
+        shape
+    }
+
+    /// Filled path, possibly made of several subpaths (see [`Path`]).
+    pub fn path(&mut self, path: Path) -> Shape<'_> {
+        let mut shape = self.shape(ShapeType::Path, Vec::new(), Vec::new());
+        shape.path = path;
+        shape
+    }
+}
+
+/// Transform applied with `q`/`cm` until [`end`](Self::end), which restores the
+/// graphics state and maps the bounds and link areas drawn meanwhile to the outer space.
+pub(crate) struct TransformScope {
+    transform: Transform,
+    first_link: usize,
+    bounds: Option<[f64; 4]>,
+}
+
+impl TransformScope {
+    pub(crate) fn begin(
+        t: Transform,
+        content: &mut ContentStream,
+        links: &[LinkArea],
+        bounds: &mut Option<[f64; 4]>,
+        decimals: u8,
+    ) -> Result<Self, Error> {
+        t.validate()?;
+        content.save();
+        content.transform([t.a, t.b, t.c, t.d, t.e, t.f], decimals);
+        Ok(Self {
+            transform: t,
+            first_link: links.len(),
+            bounds: bounds.take(),
+        })
+    }
+
+    pub(crate) fn end(
+        self,
+        content: &mut ContentStream,
+        links: &mut [LinkArea],
+        bounds: &mut Option<[f64; 4]>,
+    ) {
+        content.restore();
+        let t = self.transform;
+        for area in &mut links[self.first_link..] {
+            area.rect = t.apply_rect(area.rect);
+        }
+        *bounds = match bounds.take() {
+            Some(rect) => Some(union_rect(self.bounds, t.apply_rect(rect))),
+            None => self.bounds,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::ShapeNode;
+    use crate::generator::Generator;
+    use crate::link::Link;
+    use crate::page::PageBuilder;
+    use std::path::PathBuf;
+
+    struct Sensor;
+
+    impl Drawable for Sensor {
+        fn draw(&self, canvas: &mut Canvas<'_>) -> Result<(), Error> {
+            let mut style = canvas.get_style();
+            style.width = 2.0;
+            canvas.set_style(style);
+            canvas.line(Pt(0.), Pt(0.), Pt(10.), Pt(0.)).draw()?;
+            canvas
+                .circle(Pt(5.), Pt(5.), Pt(2.))
+                .with_link(Link::page(0))
+                .draw()
+        }
+    }
+
+    struct Panel;
+
+    impl Drawable for Panel {
+        fn draw(&self, canvas: &mut Canvas<'_>) -> Result<(), Error> {
+            canvas.transformed(Transform::translate(Pt(100.), Pt(50.)), |canvas| {
+                canvas.draw(&Sensor)
+            })?;
+            // The width set by the sensor does not leak out of it
+            canvas.line(Pt(0.), Pt(0.), Pt(0.), Pt(10.)).draw()
+        }
+    }
+
+    #[test]
+    fn drawables_inherit_style_and_transforms() {
+        let mut generator = Generator::new(PathBuf::new());
+        generator.set_reproducible(true);
+        generator.add_page();
+        generator.set_default_color(Rgb(1., 0., 0.));
+        generator.draw(&Panel).unwrap();
+        let text = String::from_utf8_lossy(&generator.to_pdf_bytes().unwrap()).into_owned();
+        assert!(text.contains("q\n1 0 0 1 100 50 cm\n1 0 0 RG\n2 w\n"));
+        assert!(text.contains("Q\n1 0 0 RG\n1 w\n0 J\n0 0 m\n0 10 l\n"));
+        assert!(text.contains("/Rect [103 53 107 57]"));
+    }
+
+    #[test]
+    fn slices_draw_each_item() {
+        let mut page = PageBuilder::letter();
+        page.draw(&[Sensor, Sensor][..]).unwrap();
+        let mut single = PageBuilder::letter();
+        single.draw(&Sensor).unwrap();
+        single.draw(&Sensor).unwrap();
+        assert_eq!(page.content.as_bytes(), single.content.as_bytes());
+        let mut generator = Generator::new(PathBuf::new());
+        generator.add_page();
+        assert!(generator
+            .draw_transformed(&Sensor, Transform::scale(f64::NAN, 1.))
+            .is_err());
+    }
+
+    #[test]
+    fn owned_primitives_are_drawable() {
+        let shapes: Vec<Box<dyn Drawable>> = vec![
+            Box::new(ShapeNode::line(Pt(0.), Pt(0.), Pt(10.), Pt(0.))),
+            Box::new(ShapeNode::circle(Pt(5.), Pt(5.), Pt(2.))),
+            Box::new(ShapeNode::rectangle(Pt(0.), Pt(0.), Pt(4.), Pt(3.))),
+            Box::new(Path::polygon(&[
+                (Pt(0.), Pt(0.)),
+                (Pt(4.), Pt(0.)),
+                (Pt(2.), Pt(3.)),
+            ])),
+        ];
+        let mut page = PageBuilder::letter();
+        page.draw(&shapes[..]).unwrap();
+        let text = String::from_utf8_lossy(page.content.as_bytes()).into_owned();
+        assert!(text.contains("0 0 m\n10 0 l\nS\n"));
+        assert!(text.contains("0 0 4 3 re f\n"));
+        assert!(text.ends_with("0 0 m\n4 0 l\n2 3 l\nh\nf\n"));
+    }
+}
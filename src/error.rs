@@ -0,0 +1,143 @@
+use crate::conformance::ConformanceError;
+use std::fmt;
+use std::io;
+
+/// Errors of drawing and generating a PDF.
+#[derive(Debug)]
+pub enum Error {
+    /// A coordinate, length or angle is NaN or infinite.
+    NonFinite { name: &'static str, value: f64 },
+    /// A radius or line width is negative.
+    Negative { name: &'static str, value: f64 },
+    /// A data range of a viewport starts and ends at the same value.
+    EmptyRange(&'static str),
+    /// A color component is outside `0..=1`.
+    ColorOutOfRange { component: f64 },
+    /// A shape is missing coordinates (e.g. a [`Shape`](crate::Shape) built by hand).
+    MissingGeometry(&'static str),
+    /// A symbol that was not defined by this generator.
+    UnknownSymbol,
+    /// A bookmark that was not added by this generator.
+    UnknownBookmark,
+    /// A link or bookmark points at a page (zero-based index) that does not exist.
+    MissingPage(usize),
+    /// Pages were added, selected, moved or deleted while defining a symbol.
+    PageInSymbol,
+    /// A layer was drawn on again with a different visibility.
+    LayerVisibility(String),
+    /// The document violates the requested conformance level.
+    Conformance(ConformanceError),
+    /// Writing to the sink or compressing a stream failed.
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NonFinite { name, value } => {
+                write!(f, "{} must be finite, found {}", name, value)
+            }
+            Error::Negative { name, value } => {
+                write!(f, "{} must not be negative, found {}", name, value)
+            }
+            Error::EmptyRange(name) => write!(f, "{} must not be empty", name),
+            Error::ColorOutOfRange { component } => write!(
+                f,
+                "color components must be between 0 and 1, found {}",
+                component
+            ),
+            Error::MissingGeometry(shape) => write!(f, "{} is missing coordinates", shape),
+            Error::UnknownSymbol => write!(f, "symbol is not defined by this generator"),
+            Error::UnknownBookmark => write!(f, "bookmark is not defined by this generator"),
+            Error::MissingPage(page) => write!(
+                f,
+                "link or bookmark points at page {}, which does not exist",
+                page + 1
+            ),
+            Error::PageInSymbol => write!(f, "pages cannot be changed while defining a symbol"),
+            Error::LayerVisibility(name) => {
+                write!(f, "layer '{}' is used with conflicting visibility", name)
+            }
+            Error::Conformance(err) => err.fmt(f),
+            Error::Io(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Conformance(err) => Some(err),
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl Error {
+    /// Copy of the error, with I/O errors reduced to their kind and message.
+    pub(crate) fn duplicate(&self) -> Error {
+        match self {
+            Error::NonFinite { name, value } => Error::NonFinite {
+                name,
+                value: *value,
+            },
+            Error::Negative { name, value } => Error::Negative {
+                name,
+                value: *value,
+            },
+            Error::EmptyRange(name) => Error::EmptyRange(name),
+            Error::ColorOutOfRange { component } => Error::ColorOutOfRange {
+                component: *component,
+            },
+            Error::MissingGeometry(shape) => Error::MissingGeometry(shape),
+            Error::UnknownSymbol => Error::UnknownSymbol,
+            Error::UnknownBookmark => Error::UnknownBookmark,
+            Error::MissingPage(page) => Error::MissingPage(*page),
+            Error::PageInSymbol => Error::PageInSymbol,
+            Error::LayerVisibility(name) => Error::LayerVisibility(name.clone()),
+            Error::Conformance(err) => Error::Conformance(err.clone()),
+            Error::Io(err) => Error::Io(io::Error::new(err.kind(), err.to_string())),
+        }
+    }
+}
+
+impl From<ConformanceError> for Error {
+    fn from(err: ConformanceError) -> Self {
+        Error::Conformance(err)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+/// Check that `value` is finite.
+pub(crate) fn finite(name: &'static str, value: f64) -> Result<f64, Error> {
+    if value.is_finite() {
+        Ok(value)
+    } else {
+        Err(Error::NonFinite { name, value })
+    }
+}
+
+/// Check that `value` is finite and not negative.
+pub(crate) fn non_negative(name: &'static str, value: f64) -> Result<f64, Error> {
+    if finite(name, value)? < 0.0 {
+        Err(Error::Negative { name, value })
+    } else {
+        Ok(value)
+    }
+}
+
+/// Check that the color components are within `0..=1`.
+pub(crate) fn color((r, g, b): (f64, f64, f64)) -> Result<(), Error> {
+    for component in [r, g, b] {
+        if !(0.0..=1.0).contains(&component) {
+            return Err(Error::ColorOutOfRange { component });
+        }
+    }
+    Ok(())
+}
@@ -1,247 +1,1296 @@
 #[cfg(feature = "compress")]
 use flate2::{write::ZlibEncoder, Compression};
-use once_cell::sync::Lazy;
-use std::error::Error;
 #[cfg(not(target_arch = "wasm32"))]
 use std::fs::File;
-#[cfg(target_arch = "wasm32")]
-use std::io;
 use std::io::Write;
 use std::path;
-use std::sync::Mutex;
 
+pub use crate::boolean::PathOp;
+use crate::conformance;
+pub use crate::conformance::{Conformance, ConformanceError};
+pub use crate::content::ContentStream;
+pub use crate::document::{
+    Document, DocumentSymbolId, Geometry, Group, Node, NodeId, NodeKind, Page, ShapeNode,
+    SymbolInstance,
+};
+use crate::drawable::TransformScope;
+pub use crate::drawable::{Canvas, Drawable};
+use crate::error;
+pub use crate::error::Error;
+use crate::layer;
+pub use crate::layer::{Layer, LayerId, LayerScope};
+use crate::link::PageTarget;
+pub use crate::link::{Link, LinkArea};
+pub use crate::metadata::{DateTime, Metadata};
+use crate::number::{Num, MAX_PRECISION};
+use crate::outline;
+pub use crate::outline::{Bookmark, BookmarkId};
+use crate::page;
+use crate::page::PageState;
+pub use crate::page::{
+    Coordinates, PageBuilder, PageId, PageLabel, PageLabelStyle, PageOptions, PageRotation,
+};
+pub use crate::path::{FillRule, Path, Segment};
 pub use crate::shapes::*;
+pub use crate::stroke::{LineJoin, Stroke};
+pub use crate::style::Style;
+pub use crate::symbol::SymbolId;
+use crate::symbol::{self, Symbol};
+pub use crate::tag::{Role, StructureTree, Tag, TagScope};
+pub use crate::transform::Transform;
 pub use crate::units::*;
+use crate::xref::{self, XrefEntry};
+use std::collections::BTreeSet;
 
 const N_OBJ_RESERVED: usize = 2; // First two objects are reserved for pages.
+const CATALOG_OBJ: usize = 1;
+const OBJECTS_PER_STREAM: usize = 100; // Objects packed into one object stream
+const PAGES_OBJ: usize = 2;
 
-static DEFAULT_PAGE_WIDTH: Lazy<Mutex<f64>> = Lazy::new(|| Inch(8.5).to_points().into());
-static DEFAULT_PAGE_HEIGHT: Lazy<Mutex<f64>> = Lazy::new(|| Inch(11.0).to_points().into());
-
-/// PDF generator.
+/// PDF generator writing to a sink `W`.
+///
+/// [`Generator::new`] keeps the document in memory until [`write_pdf`](Generator::write_pdf),
+/// so that any page can be drawn on until then (see [`select_page`](Generator::select_page)),
+/// while [`Generator::to_writer`] streams each finished page to any [`Write`] sink.
 #[derive(Debug)]
-pub struct Generator {
+pub struct Generator<W: Write = Vec<u8>> {
     #[cfg(not(target_arch = "wasm32"))]
     file_path: path::PathBuf,
     #[cfg(target_arch = "wasm32")]
     _file_path: path::PathBuf,
-    pdf: Vec<u8>,            // PDF binary content
-    pdf_pre: Vec<u8>,        // PDF binary content before the first page
-    offsets: Vec<usize>,     // Object offsets for xref
-    pre_offset: usize,       // Offset before the first page
-    content_stream: Vec<u8>, // Content stream to accumulate drawing commands
-    pages: Vec<usize>,       // Page object numbers
-    finished: bool,          // Whether the PDF was finalized
+    out: W,                                 // Sink receiving the PDF binary content
+    written: usize,                         // Number of bytes written to the sink
+    hasher: conformance::Fnv1a128,          // Hash of the written bytes for the document ID
+    error: Option<Error>,                   // First error kept for finalization (e.g. of the sink)
+    offsets: Vec<XrefEntry>,                // Object locations for xref
+    object_streams: bool,                   // Whether objects are packed into object streams
+    reproducible: bool,                     // Whether the output only depends on the drawing
+    pending_objects: Vec<(usize, Vec<u8>)>, // Objects awaiting the next object stream
+    content_stream: ContentStream,          // Content stream to accumulate drawing commands
+    page_links: Vec<LinkArea>,              // Link areas of the current page
+    content_bounds: Option<[f64; 4]>,       // Bounds of everything in the content stream
+    coordinates: Transform,                 // Map from the page coordinates to PDF points
+    content_symbols: BTreeSet<SymbolId>,    // Symbols used by the content stream
+    symbols: Vec<Symbol>,                   // Defined symbols (Form XObjects)
+    content_layers: BTreeSet<LayerId>,      // Layers used by the content stream
+    layers: Vec<Layer>,                     // Optional content groups
+    open_layers: Vec<(LayerId, usize)>,     // Layers wrapping the drawing, with the tag depth
+    structure: StructureTree,               // Tagged content for the structure tree
+    pending_links: Vec<(usize, LinkArea)>,  // Link annotations awaiting page object numbers
+    pages: Vec<PageState>,                  // Pages by PageId, with their unwritten content
+    page_order: Vec<PageId>,                // Pages in document order
+    current_page: Option<PageId>,           // Page receiving the drawing commands
+    retain_pages: bool,                     // Whether pages stay open until finalization
+    conformance: Conformance,               // Requested conformance level
+    metadata: Metadata,                     // Document information
+    info_obj: Option<usize>,                // Information dictionary object number
+    bookmarks: Vec<Bookmark>,               // Document outline entries
+    style: Style,                           // Defaults for options not set on shapes
+    style_stack: Vec<Style>,                // Styles saved with push_style
+    finished: bool,                         // Whether the PDF was finalized
 }
 
 impl Generator {
+    /// Create a generator that builds the PDF in memory and saves it to `file_path`
+    /// with [`write_pdf`](Self::write_pdf).
     pub fn new(file_path: path::PathBuf) -> Self {
-        Self {
+        Self::with_sink(file_path, Vec::new())
+    }
+
+    pub fn to_pdf_bytes(&mut self) -> Result<Vec<u8>, Error> {
+        self.ensure_finalized()?;
+        // The error stays set: the output stopped at it and is incomplete
+        if let Some(err) = &self.error {
+            return Err(err.duplicate());
+        }
+        Ok(self.out.clone())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn write_pdf(&mut self) -> Result<(), Error> {
+        let bytes = self.to_pdf_bytes()?;
+        // create directories if not exist
+        if let Some(dir) = self.file_path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut file = File::create(&self.file_path)?;
+        file.write_all(&bytes)?;
+        Ok(())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn write_pdf(&mut self) -> Result<(), Error> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "write_pdf is not supported on wasm targets",
+        )
+        .into())
+    }
+}
+
+impl<W: Write> Generator<W> {
+    /// Create a generator that streams the PDF to `out` (a file, a socket, a `Vec<u8>`, ...).
+    ///
+    /// The objects of each page are written as soon as a page is added after it, so memory
+    /// use does not grow with the page contents (earlier pages can then no longer be
+    /// drawn on or deleted, but can still be moved). Document-level objects, the page tree
+    /// and the cross-reference table are written by [`finish`](Self::finish).
+    /// Wrap unbuffered sinks such as [`File`](std::fs::File) in a [`BufWriter`](std::io::BufWriter).
+    ///
+    /// # Example
+    /// ```
+    /// use shapdf::*;
+    /// let mut generator = Generator::to_writer(Vec::new());
+    /// for i in 0..100 {
+    ///     generator.add_page();
+    ///     generator.circle(Mm(20.), Mm(20.), Mm(i as f64 / 10.)).draw().unwrap();
+    /// }
+    /// let pdf: Vec<u8> = generator.finish().unwrap();
+    /// assert!(pdf.starts_with(b"%PDF-1.5"));
+    /// ```
+    pub fn to_writer(out: W) -> Self {
+        let mut generator = Self::with_sink(path::PathBuf::new(), out);
+        generator.retain_pages = false;
+        generator
+    }
+
+    fn with_sink(file_path: path::PathBuf, out: W) -> Self {
+        let mut generator = Self {
             #[cfg(not(target_arch = "wasm32"))]
             file_path,
             #[cfg(target_arch = "wasm32")]
             _file_path: file_path,
-            pdf: Vec::new(),
-            pdf_pre: Vec::new(),
-            offsets: vec![0; N_OBJ_RESERVED], // First two objects are reserved for pages.
-            pre_offset: 0,
-            content_stream: Vec::new(),
+            out,
+            written: 0,
+            hasher: conformance::Fnv1a128::new(),
+            error: None,
+            offsets: vec![XrefEntry::Free; N_OBJ_RESERVED], // First two objects are reserved for pages.
+            object_streams: false,
+            reproducible: false,
+            pending_objects: Vec::new(),
+            content_stream: ContentStream::new(),
+            page_links: Vec::new(),
+            content_bounds: None,
+            coordinates: Transform::identity(),
+            content_symbols: BTreeSet::new(),
+            symbols: Vec::new(),
+            content_layers: BTreeSet::new(),
+            layers: Vec::new(),
+            open_layers: Vec::new(),
+            structure: StructureTree::default(),
+            pending_links: Vec::new(),
             pages: Vec::new(),
+            page_order: Vec::new(),
+            current_page: None,
+            retain_pages: true,
+            conformance: Conformance::None,
+            metadata: Metadata::default(),
+            info_obj: None,
+            bookmarks: Vec::new(),
+            style: Style::default(),
+            style_stack: Vec::new(),
             finished: false,
+        };
+        // Header with a binary comment marking the file as binary
+        generator.emit(b"%PDF-1.5\n%\xe2\xe3\xcf\xd3\n");
+        generator
+    }
+
+    /// Finish the document and return the sink.
+    ///
+    /// Errors of the sink during generation and misuse such as page operations
+    /// inside [`define_symbol`](Self::define_symbol) are reported here.
+    pub fn finish(mut self) -> Result<W, Error> {
+        self.ensure_finalized()?;
+        if let Some(err) = self.error.take() {
+            return Err(err);
         }
+        self.out.flush()?;
+        Ok(self.out)
     }
 
-    fn ensure_finalized(&mut self) {
-        if self.finished {
-            return;
+    /// Write bytes to the sink, keeping the first error for [`finish`](Self::finish).
+    fn emit(&mut self, bytes: &[u8]) {
+        self.written += bytes.len();
+        self.hasher.update(bytes);
+        if self.error.is_none() {
+            if let Err(err) = self.out.write_all(bytes) {
+                self.error = Some(err.into());
+            }
         }
-        self.initialize_pdf();
-        self.finalize_pdf();
-        self.finished = true;
     }
 
-    fn collect_pdf_bytes(&mut self) -> Vec<u8> {
-        self.ensure_finalized();
-        let mut bytes = Vec::with_capacity(self.pdf_pre.len() + self.pdf.len());
-        bytes.extend_from_slice(&self.pdf_pre);
-        bytes.extend_from_slice(&self.pdf);
-        bytes
+    /// Set the conformance level (e.g. [`Conformance::PdfA2b`]) of the generated PDF.
+    ///
+    /// Features that would break the requested conformance cause
+    /// [`write_pdf`](Self::write_pdf) and [`to_pdf_bytes`](Self::to_pdf_bytes) to fail
+    /// with [`Error::Conformance`]: page sizes and the length of metadata, bookmark
+    /// titles, layer names, link URIs and alternate text are checked against the
+    /// PDF/A limits. Everything else `shapdf` writes conforms by construction: Flate
+    /// compression only, no transparency or fonts, printable link annotations (`/F 4`)
+    /// and a named optional content configuration for layers.
+    pub fn set_conformance(&mut self, conformance: Conformance) {
+        self.conformance = conformance;
     }
 
-    pub fn to_pdf_bytes(&mut self) -> Vec<u8> {
-        self.collect_pdf_bytes()
+    pub fn get_conformance(&self) -> Conformance {
+        self.conformance
     }
 
-    #[cfg(not(target_arch = "wasm32"))]
-    pub fn write_pdf(&mut self) -> Result<(), Box<dyn Error>> {
-        let bytes = self.collect_pdf_bytes();
-        // create directories if not exist
-        if let Some(dir) = self.file_path.parent() {
-            std::fs::create_dir_all(dir)?;
+    /// Make the output bytes depend only on the drawing commands and metadata.
+    ///
+    /// All streams are left uncompressed (the compressed bytes differ between zlib
+    /// implementations), including object streams, so documents with a lot of content get
+    /// noticeably larger. The default producer omits the `shapdf` version.
+    /// The document `/ID` is always a hash of the document and no timestamps
+    /// are written unless set with [`set_creation_date`](Self::set_creation_date).
+    ///
+    /// # Example
+    /// ```
+    /// use shapdf::*;
+    /// let render = || {
+    ///     let mut generator = Generator::new("output/reproducible.pdf".into());
+    ///     generator.set_reproducible(true);
+    ///     generator.set_title("Weekly report");
+    ///     generator.add_page();
+    ///     generator.circle(Mm(20.), Mm(20.), Mm(5.)).draw().unwrap();
+    ///     generator.to_pdf_bytes().unwrap()
+    /// };
+    /// assert_eq!(render(), render());
+    /// ```
+    pub fn set_reproducible(&mut self, reproducible: bool) {
+        self.reproducible = reproducible;
+    }
+
+    /// Mark the document as tagged before anything is tagged, so that untagged content
+    /// drawn from now on is marked as an artifact.
+    ///
+    /// Tagging content does this automatically, and it cannot be undone once content
+    /// was tagged.
+    pub fn set_tagged(&mut self, tagged: bool) {
+        self.structure.used |= tagged;
+    }
+
+    pub fn get_tagged(&self) -> bool {
+        self.structure.used
+    }
+
+    /// Whether the output is reproducible (see [`set_reproducible`](Self::set_reproducible)).
+    pub fn get_reproducible(&self) -> bool {
+        self.reproducible
+    }
+
+    /// Pack objects into compressed object streams and write a cross-reference stream
+    /// (PDF 1.5) instead of a classic xref table, shrinking documents with many small
+    /// objects such as pages, annotations and outline entries.
+    ///
+    /// # Example
+    /// ```
+    /// use shapdf::*;
+    /// let mut generator = Generator::new("output/object_streams.pdf".into());
+    /// generator.set_object_streams(true);
+    /// for _ in 0..100 {
+    ///     generator.add_page();
+    /// }
+    /// let pdf = generator.to_pdf_bytes().unwrap();
+    /// assert!(!pdf.windows(7).any(|w| w == b"trailer"));
+    /// ```
+    #[cfg(feature = "compress")]
+    pub fn set_object_streams(&mut self, enabled: bool) {
+        self.object_streams = enabled;
+    }
+
+    #[cfg(feature = "compress")]
+    pub fn get_object_streams(&self) -> bool {
+        self.object_streams
+    }
+
+    pub fn set_title(&mut self, title: impl Into<String>) {
+        self.metadata.title = Some(title.into());
+    }
+
+    pub fn set_author(&mut self, author: impl Into<String>) {
+        self.metadata.author = Some(author.into());
+    }
+
+    pub fn set_subject(&mut self, subject: impl Into<String>) {
+        self.metadata.subject = Some(subject.into());
+    }
+
+    pub fn set_keywords(&mut self, keywords: impl Into<String>) {
+        self.metadata.keywords = Some(keywords.into());
+    }
+
+    /// Set the application that created the original content (`/Creator`).
+    pub fn set_creator(&mut self, creator: impl Into<String>) {
+        self.metadata.creator = Some(creator.into());
+    }
+
+    /// Set the application that produced the PDF (`/Producer`, defaults to `shapdf`).
+    pub fn set_producer(&mut self, producer: impl Into<String>) {
+        self.metadata.producer = Some(producer.into());
+    }
+
+    pub fn set_creation_date(&mut self, date: DateTime) {
+        self.metadata.creation_date = Some(date);
+    }
+
+    /// Replace all document information at once.
+    pub fn set_metadata(&mut self, metadata: Metadata) {
+        self.metadata = metadata;
+    }
+
+    pub fn get_metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    /// Add a top-level bookmark to the document outline.
+    ///
+    /// `page` is the zero-based page index in the final page order and `y` the vertical
+    /// position to scroll to, in the [`Coordinates`] of that page.
+    /// Bookmarks pointing to a page that is never added make finalizing fail with
+    /// [`Error::MissingPage`], and a non-finite `y` with [`Error::NonFinite`].
+    pub fn bookmark(
+        &mut self,
+        title: impl Into<String>,
+        page: usize,
+        y: impl Length,
+    ) -> BookmarkId {
+        self.push_bookmark(None, title.into(), page, y.to_points())
+    }
+
+    /// Add a bookmark nested under `parent`.
+    ///
+    /// A `parent` that was not returned by this generator makes finalizing fail with
+    /// [`Error::UnknownBookmark`].
+    pub fn bookmark_child(
+        &mut self,
+        parent: BookmarkId,
+        title: impl Into<String>,
+        page: usize,
+        y: impl Length,
+    ) -> BookmarkId {
+        self.push_bookmark(Some(parent), title.into(), page, y.to_points())
+    }
+
+    /// Set whether the children of a bookmark are expanded (default `true`).
+    pub fn set_bookmark_open(&mut self, bookmark: BookmarkId, open: bool) {
+        match self.bookmarks.get_mut(bookmark.0) {
+            Some(bookmark) => bookmark.open = open,
+            None => {
+                self.error.get_or_insert(Error::UnknownBookmark);
+            }
+        }
+    }
+
+    pub fn get_bookmarks(&self) -> &[Bookmark] {
+        &self.bookmarks
+    }
+
+    fn push_bookmark(
+        &mut self,
+        parent: Option<BookmarkId>,
+        title: String,
+        page: usize,
+        y: f64,
+    ) -> BookmarkId {
+        if let Err(err) = error::finite("bookmark y", y) {
+            self.error.get_or_insert(err);
+        }
+        // An unknown parent is reported, and the bookmark kept at the top level meanwhile
+        let known = |parent: &BookmarkId| parent.0 < self.bookmarks.len();
+        if parent.is_some_and(|parent| !known(&parent)) {
+            self.error.get_or_insert(Error::UnknownBookmark);
+        }
+        let parent = parent.filter(known);
+        self.bookmarks.push(Bookmark {
+            title,
+            page,
+            y,
+            parent,
+            open: true,
+        });
+        BookmarkId(self.bookmarks.len() - 1)
+    }
+
+    /// Number of pages in the document.
+    pub fn page_count(&self) -> usize {
+        self.page_order.len()
+    }
+
+    /// Size of the page at the zero-based `page` index.
+    ///
+    /// The size of a page fitted to its content (see [`add_page_fit`](Self::add_page_fit))
+    /// follows what has been drawn so far.
+    pub fn get_page_size(&self, page: usize) -> Option<(Pt, Pt)> {
+        self.page_order.get(page).map(|id| {
+            let [x1, y1, x2, y2] = self.media_box(*id);
+            (Pt(x2 - x1), Pt(y2 - y1))
+        })
+    }
+
+    /// Top of the page at the zero-based `page` index, in the coordinates of the page.
+    pub(crate) fn page_top(&self, page: usize) -> Option<f64> {
+        let id = *self.page_order.get(page)?;
+        let state = &self.pages[id.0];
+        let [x1, _, _, y2] = self.media_box(id);
+        let inverse = state.options.coordinates.to_page(state.size).inverse()?;
+        Some(inverse.apply(x1, y2).1)
+    }
+
+    /// Media box `[x1, y1, x2, y2]` of a page, fitted to its content if requested.
+    fn media_box(&self, id: PageId) -> [f64; 4] {
+        let state = &self.pages[id.0];
+        let bounds = if self.current_page == Some(id) {
+            self.content_bounds
+        } else {
+            state.bounds
+        };
+        state.options.media_box(state.size, bounds)
+    }
+
+    /// Bounding box `[x1, y1, x2, y2]` in points of everything drawn on the page at
+    /// the zero-based `page` index, or `None` if nothing was drawn.
+    ///
+    /// The box is in PDF points (origin at the bottom-left corner, `y` up) whatever the
+    /// [`Coordinates`] of the page.
+    ///
+    /// # Example
+    /// ```
+    /// use shapdf::*;
+    /// let mut generator = Generator::new("output/crop.pdf".into());
+    /// generator.add_page();
+    /// generator.circle(Pt(100.), Pt(100.), Pt(10.)).draw().unwrap();
+    /// generator.rectangle(Pt(150.), Pt(80.), Pt(20.), Pt(20.)).draw().unwrap();
+    /// assert_eq!(generator.get_page_bounds(0), Some([90., 80., 170., 110.]));
+    /// ```
+    pub fn get_page_bounds(&self, page: usize) -> Option<[f64; 4]> {
+        let id = *self.page_order.get(page)?;
+        if self.current_page == Some(id) {
+            self.content_bounds
+        } else {
+            self.pages[id.0].bounds
+        }
+    }
+
+    /// Pages in document order.
+    pub fn get_page_ids(&self) -> &[PageId] {
+        &self.page_order
+    }
+
+    /// Zero-based index of a page in the document, or `None` if it was deleted.
+    pub fn page_index(&self, page: PageId) -> Option<usize> {
+        self.page_order.iter().position(|id| *id == page)
+    }
+
+    /// Page receiving the drawing commands.
+    pub fn current_page(&self) -> Option<PageId> {
+        self.current_page
+    }
+
+    fn check_conformance(&self) -> Result<(), ConformanceError> {
+        self.metadata.check_conformance(self.conformance)?;
+        for bookmark in &self.bookmarks {
+            conformance::check_string(self.conformance, "bookmark title", &bookmark.title)?;
+        }
+        for layer in &self.layers {
+            conformance::check_string(self.conformance, "layer name", &layer.name)?;
+        }
+        for element in &self.structure.elements {
+            if let Some(alt) = &element.tag.alt {
+                conformance::check_string(self.conformance, "alternate text", alt)?;
+            }
+        }
+        for area in self.link_areas() {
+            if let Link::Uri(uri) = &area.link {
+                conformance::check_string(self.conformance, "link URI", uri)?;
+            }
+        }
+        for (page, id) in self.page_order.iter().enumerate() {
+            let [x1, y1, x2, y2] = self.media_box(*id);
+            conformance::check_page_size(self.conformance, page, x2 - x1, y2 - y1)?;
         }
-        let mut file = File::create(&self.file_path)?;
-        file.write_all(&bytes)?;
         Ok(())
     }
 
-    #[cfg(target_arch = "wasm32")]
-    pub fn write_pdf(&mut self) -> Result<(), Box<dyn Error>> {
-        Err(io::Error::new(
-            io::ErrorKind::Other,
-            "write_pdf is not supported on wasm targets",
-        )
-        .into())
+    /// Link areas of all pages, whether written, open or current.
+    fn link_areas(&self) -> impl Iterator<Item = &LinkArea> {
+        let written = self.pending_links.iter().map(|(_, area)| area);
+        let open = self
+            .pages
+            .iter()
+            .filter(|page| !page.deleted)
+            .flat_map(|page| &page.links);
+        written.chain(open).chain(&self.page_links)
+    }
+
+    /// Check that links and bookmarks point at existing pages.
+    fn check_targets(&self) -> Result<(), Error> {
+        let count = self.page_order.len();
+        let links = self.link_areas().filter_map(|area| match area.link {
+            Link::Page { page, .. } => Some(page),
+            Link::Uri(_) => None,
+        });
+        let bookmarks = self.bookmarks.iter().map(|bookmark| bookmark.page);
+        match links.chain(bookmarks).find(|page| *page >= count) {
+            Some(page) => Err(Error::MissingPage(page)),
+            None => Ok(()),
+        }
+    }
+
+    fn ensure_finalized(&mut self) -> Result<(), Error> {
+        if self.finished {
+            return Ok(());
+        }
+        self.check_conformance()?;
+        self.check_targets()?;
+        self.initialize_pdf();
+        self.finalize_pdf();
+        self.finished = true;
+        Ok(())
     }
 
     fn initialize_pdf(&mut self) {
         // add remaining content
-        self.add_content();
+        self.leave_page();
+        self.write_open_pages(None);
+        let pages: Vec<PageTarget> = self
+            .page_order
+            .iter()
+            .map(|id| {
+                let state = &self.pages[id.0];
+                PageTarget {
+                    obj: state.obj,
+                    coordinates: state.options.coordinates.to_page(state.size),
+                }
+            })
+            .collect();
+
+        // Link annotations, written once all page objects are known
+        for (obj, area) in std::mem::take(&mut self.pending_links) {
+            self.write_object(
+                obj,
+                area.annotation_dict(&pages, self.style.precision)
+                    .as_bytes(),
+            );
+        }
 
-        self.pdf_pre.extend(b"%PDF-1.5\n");
+        // Document level objects are appended after the last page
+        let mut catalog = String::from("<< /Type /Catalog /Pages 2 0 R");
+        if self.conformance.is_pdfa() || !self.metadata.is_empty() {
+            let mut metadata = self.metadata.clone();
+            if self.reproducible && metadata.producer.is_none() {
+                metadata.producer = Some("shapdf".to_string());
+            }
+            self.info_obj = Some(self.add_object(metadata.info_dict().as_bytes()));
+
+            let xmp = metadata.xmp_packet(self.conformance);
+            let metadata_obj = self.add_encoded_stream_object(
+                "/Type /Metadata /Subtype /XML",
+                xmp.into_bytes(),
+                false,
+            );
+            catalog.push_str(&format!(" /Metadata {} 0 R", metadata_obj));
+        }
+        if !self.bookmarks.is_empty() {
+            let first_obj = self.offsets.len() + 1;
+            for dict in
+                outline::outline_objects(&self.bookmarks, first_obj, &pages, self.style.precision)
+            {
+                self.add_object(dict.as_bytes());
+            }
+            catalog.push_str(&format!(
+                " /Outlines {} 0 R /PageMode /UseOutlines",
+                first_obj
+            ));
+        }
+        if self.structure.used {
+            let first_obj = self.offsets.len() + 1;
+            let objs_by_id: Vec<Option<usize>> = self
+                .pages
+                .iter()
+                .map(|page| (!page.deleted).then_some(page.obj))
+                .collect();
+            let mut positions = vec![0; self.pages.len()];
+            for (position, id) in self.page_order.iter().enumerate() {
+                positions[id.0] = position;
+            }
+            let parent_tree: Vec<(usize, &[usize])> = self
+                .pages
+                .iter()
+                .enumerate()
+                .filter(|(_, page)| !page.deleted && !page.mcids.is_empty())
+                .map(|(idx, page)| (idx, &page.mcids[..]))
+                .collect();
+            let objects = self
+                .structure
+                .objects(first_obj, &objs_by_id, &positions, &parent_tree);
+            for dict in objects {
+                self.add_object(dict.as_bytes());
+            }
+            catalog.push_str(&format!(
+                " /MarkInfo << /Marked true >> /StructTreeRoot {} 0 R",
+                first_obj
+            ));
+        }
+        if !self.layers.is_empty() {
+            for layer in self.layers.clone() {
+                self.write_object(layer.obj, layer::group_dict(&layer).as_bytes());
+            }
+            catalog.push_str(&format!(
+                " /OCProperties {}",
+                layer::oc_properties(&self.layers)
+            ));
+        }
+        if let Some(labels) = page::page_labels_dict(
+            self.page_order
+                .iter()
+                .map(|id| self.pages[id.0].options.label.as_ref()),
+        ) {
+            catalog.push_str(&format!(" /PageLabels {}", labels));
+        }
+        if self.conformance.is_pdfa() {
+            let profile = conformance::srgb_icc_profile();
+            let profile_obj = self.add_stream_object("/N 3", profile);
+            let intent_obj = self.add_object(conformance::output_intent(profile_obj).as_bytes());
+            catalog.push_str(&format!(" /OutputIntents [{} 0 R]", intent_obj));
+        }
+        catalog.push_str(" >>");
 
         // Catalog object
-        self.add_pre_object(b"<< /Type /Catalog /Pages 2 0 R >>", 0);
+        self.write_object(CATALOG_OBJ, catalog.as_bytes());
 
         // Pages object
-        let pages_kids: String = self
-            .pages
+        let pages_kids: String = pages
             .iter()
-            .map(|page| format!("{} 0 R ", page))
+            .map(|page| format!("{} 0 R ", page.obj))
             .collect::<String>()
             .trim()
             .to_string();
-        self.add_pre_object(
-            &format!(
+        self.write_object(
+            PAGES_OBJ,
+            format!(
                 "<< /Type /Pages /Kids [{}] /Count {} >>",
                 pages_kids,
-                self.pages.len()
+                pages.len()
             )
             .as_bytes(),
-            1,
         );
     }
 
     fn finalize_pdf(&mut self) {
-        // Xref table
-        let xref_start = self.pdf_pre.len() + self.pdf.len();
-        self.pdf.extend(b"xref\n");
-        self.pdf
-            .extend(format!("0 {}\n0000000000 65535 f \n", self.offsets.len() + 1).as_bytes());
-        for (i, offset) in self.offsets.iter().enumerate() {
-            self.pdf.extend(
+        if self.object_streams {
+            self.flush_object_stream();
+        }
+
+        // Document ID derived from the document body
+        let document_id = self.hasher.finish();
+        let info = self
+            .info_obj
+            .map(|obj| format!(" /Info {} 0 R", obj))
+            .unwrap_or_default();
+        let trailer = format!(
+            "/Root 1 0 R{} /ID [<{1:032x}> <{1:032x}>]",
+            info, document_id
+        );
+
+        let xref_start = self.written;
+        if self.object_streams {
+            // Cross-reference stream, listing itself
+            let obj = self.reserve_object();
+            self.offsets[obj - 1] = XrefEntry::Offset(xref_start);
+            let (widths, mut data) = xref::xref_stream_data(&self.offsets);
+            let flate_decode = self.encode_stream(&mut data);
+            let widths = widths.map(|width| width.to_string()).join(" ");
+            let mut content = format!(
+                "<< /Type /XRef /Size {} /W [{}] {} /Length {}{} >>\nstream\n",
+                self.offsets.len() + 1,
+                widths,
+                trailer,
+                data.len(),
+                if flate_decode {
+                    " /Filter /FlateDecode"
+                } else {
+                    ""
+                }
+            )
+            .into_bytes();
+            content.extend_from_slice(&data);
+            content.extend_from_slice(b"\nendstream");
+            self.emit_object(obj, &content);
+        } else {
+            let xref = xref::xref_table(&self.offsets);
+            self.emit(xref.as_bytes());
+            self.emit(
                 format!(
-                    "{:010} 00000 {} \n",
-                    offset
-                        + if i >= N_OBJ_RESERVED && (*offset > 0 || i == N_OBJ_RESERVED) {
-                            self.pre_offset
-                        } else {
-                            0
-                        },
-                    if *offset == 0 && i > N_OBJ_RESERVED {
-                        'f'
-                    } else {
-                        'n'
-                    }
+                    "trailer\n<< {} /Size {} >>\n",
+                    trailer,
+                    self.offsets.len() + 1
                 )
                 .as_bytes(),
             );
         }
+        self.emit(format!("startxref\n{}\n%%EOF\n", xref_start).as_bytes());
+    }
 
-        // Trailer
-        self.pdf.extend(b"trailer\n");
-        self.pdf.extend(
-            format!(
-                "<< /Root 1 0 R /Size {} >>\nstartxref\n{}\n%%EOF\n",
-                self.offsets.len() + 1,
-                xref_start
-            )
-            .as_bytes(),
-        );
+    /// Reserve an object number whose content is written later with `write_object`.
+    fn reserve_object(&mut self) -> usize {
+        self.offsets.push(XrefEntry::Free);
+        self.offsets.len()
     }
 
-    fn add_object(&mut self, content: &[u8]) {
-        self.offsets.push(self.pdf.len()); // Track offset
-        self.pdf
-            .extend_from_slice(format!("{} 0 obj\n", self.offsets.len()).as_bytes());
-        self.pdf.extend_from_slice(content);
-        self.pdf.extend_from_slice(b"\nendobj\n");
+    fn add_object(&mut self, content: &[u8]) -> usize {
+        let obj = self.reserve_object();
+        self.write_object(obj, content);
+        obj
     }
 
-    fn add_pre_object(&mut self, content: &[u8], n_obj: usize) {
-        self.offsets[n_obj] = self.pdf_pre.len(); // Track offset
-        self.pdf_pre
-            .extend_from_slice(format!("{} 0 obj\n", n_obj + 1).as_bytes());
-        self.pdf_pre.extend_from_slice(content);
-        self.pdf_pre.extend_from_slice(b"\nendobj\n");
-        self.pre_offset = self.pdf_pre.len();
+    /// Write an object, or queue it for the next object stream if enabled.
+    ///
+    /// Stream objects must be written with `emit_object`.
+    fn write_object(&mut self, obj: usize, content: &[u8]) {
+        if self.object_streams {
+            self.pending_objects.push((obj, content.to_vec()));
+        } else {
+            self.emit_object(obj, content);
+        }
     }
 
-    pub fn add_page(&mut self) {
-        self.add_page_with_size(
-            Pt(*DEFAULT_PAGE_WIDTH.lock().unwrap()),
-            Pt(*DEFAULT_PAGE_HEIGHT.lock().unwrap()),
-        );
+    /// Write an object directly to the file.
+    fn emit_object(&mut self, obj: usize, content: &[u8]) {
+        self.offsets[obj - 1] = XrefEntry::Offset(self.written); // Track offset
+        self.emit(format!("{} 0 obj\n", obj).as_bytes());
+        self.emit(content);
+        self.emit(b"\nendobj\n");
+    }
+
+    /// Write the queued objects as one compressed object stream.
+    fn flush_object_stream(&mut self) {
+        if self.pending_objects.is_empty() {
+            return;
+        }
+        let objects = std::mem::take(&mut self.pending_objects);
+        let (mut data, first) = xref::object_stream_data(&objects);
+        let stream = self.reserve_object();
+        for (index, (obj, _)) in objects.iter().enumerate() {
+            self.offsets[obj - 1] = XrefEntry::Compressed { stream, index };
+        }
+        let flate_decode = self.encode_stream(&mut data);
+        let dict = format!("/Type /ObjStm /N {} /First {}", objects.len(), first);
+        self.write_encoded_stream_object(stream, &dict, data, flate_decode);
+    }
+
+    pub fn add_page(&mut self) -> PageId {
+        let (width, height) = self.style.page_size;
+        self.add_page_with_size(Pt(width), Pt(height))
+    }
+
+    pub fn add_page_a4(&mut self) -> PageId {
+        self.add_page_with_size(Mm(210.0), Mm(297.0))
+    }
+
+    pub fn add_page_a4_landscape(&mut self) -> PageId {
+        self.add_page_with_size(Mm(297.0), Mm(210.0))
     }
 
-    pub fn add_page_a4(&mut self) {
-        self.add_page_with_size(Mm(210.0), Mm(297.0));
+    pub fn add_page_letter(&mut self) -> PageId {
+        self.add_page_with_size(Inch(8.5), Inch(11.0))
     }
 
-    pub fn add_page_a4_landscape(&mut self) {
-        self.add_page_with_size(Mm(297.0), Mm(210.0));
+    pub fn add_page_letter_landscape(&mut self) -> PageId {
+        self.add_page_with_size(Inch(11.0), Inch(8.5))
     }
 
-    pub fn add_page_letter(&mut self) {
-        self.add_page_with_size(Inch(8.5), Inch(11.0));
+    pub fn add_page_with_size<L: Length>(&mut self, width: L, height: L) -> PageId {
+        self.add_page_with_options(width, height, PageOptions::default())
     }
 
-    pub fn add_page_letter_landscape(&mut self) {
-        self.add_page_with_size(Inch(11.0), Inch(8.5));
+    /// Add a page whose media box is fitted to everything drawn on it, plus `margin`
+    /// on each side (e.g. for standalone figures).
+    ///
+    /// # Example
+    /// ```
+    /// use shapdf::*;
+    /// let mut generator = Generator::new("output/figure.pdf".into());
+    /// generator.add_page_fit(Mm(5.));
+    /// generator.circle(Mm(100.), Mm(100.), Mm(10.)).draw().unwrap();
+    /// let (width, height) = generator.get_page_size(0).unwrap();
+    /// assert!((width.0 - Mm(30.).to_points()).abs() < 1e-9);
+    /// assert!((height.0 - Mm(30.).to_points()).abs() < 1e-9);
+    /// ```
+    pub fn add_page_fit(&mut self, margin: impl Length) -> PageId {
+        let (width, height) = self.style.page_size;
+        self.add_page_with_options(Pt(width), Pt(height), PageOptions::new().with_fit(margin))
     }
 
-    pub fn add_page_with_size<L: Length>(&mut self, width: L, height: L) {
-        if self.pages.len() > 0 {
-            self.add_content(); // Add content for the previous page
+    /// Add a page with boxes, rotation or a page label (see [`PageOptions`]).
+    ///
+    /// A negative or non-finite size, or non-finite boxes, make finalizing fail with
+    /// [`Error::Negative`] or [`Error::NonFinite`].
+    pub fn add_page_with_options<L: Length>(
+        &mut self,
+        width: L,
+        height: L,
+        options: PageOptions,
+    ) -> PageId {
+        self.insert_page_with_options(self.page_order.len(), width, height, options)
+    }
+
+    /// Insert a page at the zero-based `index` (appending if `index` is past the end)
+    /// and make it the current page.
+    ///
+    /// # Example
+    /// ```
+    /// use shapdf::*;
+    /// let mut generator = Generator::new("output/toc.pdf".into());
+    /// for _ in 0..3 {
+    ///     generator.add_page_a4();
+    ///     generator.circle(Mm(20.), Mm(20.), Mm(5.)).draw().unwrap();
+    /// }
+    /// // Summary page in front, filled in once the detail pages are laid out
+    /// let summary = generator.insert_page_with_size(0, Mm(210.), Mm(297.));
+    /// generator.rectangle(Mm(20.), Mm(20.), Mm(10.), Mm(5. * 3.)).draw().unwrap();
+    /// assert_eq!(generator.page_index(summary), Some(0));
+    /// ```
+    pub fn insert_page_with_size<L: Length>(
+        &mut self,
+        index: usize,
+        width: L,
+        height: L,
+    ) -> PageId {
+        self.insert_page_with_options(index, width, height, PageOptions::default())
+    }
+
+    /// Insert a page with boxes, rotation or a page label at the zero-based `index`.
+    pub fn insert_page_with_options<L: Length>(
+        &mut self,
+        index: usize,
+        width: L,
+        height: L,
+        options: PageOptions,
+    ) -> PageId {
+        if self.reject_in_symbol() {
+            return self.detached_page();
         }
+        let size = (width.to_points(), height.to_points());
+        if let Err(err) = options.validate(size) {
+            self.error.get_or_insert(err);
+        }
+        // Page object is written once the page is finished
+        let obj = self.reserve_object();
+        let contents_obj = self.reserve_object();
+        let id = PageId(self.pages.len());
+        self.pages.push(PageState {
+            obj,
+            contents_obj,
+            size,
+            options,
+            ..Default::default()
+        });
+        self.page_order.insert(index.min(self.page_order.len()), id);
 
-        self.pages.push(self.offsets.len() + 1);
+        let first_page = self.current_page.is_none();
+        self.leave_page();
+        if !self.retain_pages {
+            self.write_open_pages(Some(id));
+        }
+        if first_page {
+            // Content drawn before the first page belongs to it
+            self.current_page = Some(id);
+            self.structure.page = id.0;
+            let state = &self.pages[id.0];
+            self.coordinates = state.options.coordinates.to_page(state.size);
+        } else {
+            self.enter_page(id);
+        }
+        id
+    }
 
-        // Page object
-        self.add_object(
-            &format!(
-                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Contents {} 0 R >>",
-                width.to_points(),
-                height.to_points(),
-                self.offsets.len() + 2
-            )
-            .as_bytes(),
-        );
+    /// Append pages built independently (e.g. on other threads), in order.
+    ///
+    /// The last page becomes the current page, and content drawn before the first page of the
+    /// document is placed under the content of the first one. Content streams are compressed
+    /// in parallel when the pages are written.
+    pub fn add_pages(&mut self, pages: impl IntoIterator<Item = PageBuilder>) -> Vec<PageId> {
+        if self.reject_in_symbol() {
+            return pages.into_iter().map(|_| self.detached_page()).collect();
+        }
+        let previous = self.current_page;
+        self.leave_page();
+        let mut ids = Vec::new();
+        for mut page in pages {
+            if let Err(err) = page.options.validate(page.size) {
+                self.error.get_or_insert(err);
+            }
+            if let Some(err) = page.error.take() {
+                self.error.get_or_insert(err);
+            }
+            let id = PageId(self.pages.len());
+            let obj = self.reserve_object();
+            let contents_obj = self.reserve_object();
+            self.pages.push(PageState {
+                obj,
+                contents_obj,
+                size: page.size,
+                options: page.options,
+                ..Default::default()
+            });
+            self.page_order.push(id);
+            if previous.is_none() && ids.is_empty() {
+                // Content drawn before the first page belongs to it
+                self.current_page = Some(id);
+                self.leave_page();
+            }
+            let state = &mut self.pages[id.0];
+            state.content.append(&page.content);
+            state.links.extend(page.links);
+            state.bounds = page
+                .bounds
+                .map(|rect| union_rect(state.bounds, rect))
+                .or(state.bounds);
+            ids.push(id);
+        }
+        let last = ids.last().copied().or(previous);
+        if !self.retain_pages {
+            self.write_open_pages(last);
+        }
+        if let Some(last) = last {
+            self.enter_page(last);
+        }
+        ids
+    }
+
+    /// Whether a symbol is being defined, in which case page operations are ignored
+    /// and [`Error::PageInSymbol`] is kept for finalization.
+    fn reject_in_symbol(&mut self) -> bool {
+        // Tags are only suspended while drawing a symbol
+        if self.structure.suspended {
+            self.error.get_or_insert(Error::PageInSymbol);
+        }
+        self.structure.suspended
+    }
+
+    /// Handle of a page that is not part of the document (e.g. added inside a symbol).
+    fn detached_page(&mut self) -> PageId {
+        self.pages.push(PageState {
+            deleted: true,
+            ..Default::default()
+        });
+        PageId(self.pages.len() - 1)
+    }
+
+    /// Make `page` the current page, so that drawing commands go to it.
+    ///
+    /// Returns `false` if the page was deleted or already written
+    /// (see [`to_writer`](Self::to_writer)).
+    pub fn select_page(&mut self, page: PageId) -> bool {
+        if self.reject_in_symbol() {
+            return false;
+        }
+        match self.pages.get(page.0) {
+            Some(state) if !state.deleted && !state.written => {}
+            _ => return false,
+        }
+        if self.current_page != Some(page) {
+            self.leave_page();
+            self.enter_page(page);
+        }
+        true
+    }
+
+    /// Move a page to the zero-based `index` (the last position if `index` is past the end).
+    ///
+    /// Returns `false` if the page was deleted.
+    pub fn move_page(&mut self, page: PageId, index: usize) -> bool {
+        if self.reject_in_symbol() {
+            return false;
+        }
+        let Some(from) = self.page_index(page) else {
+            return false;
+        };
+        self.page_order.remove(from);
+        self.page_order
+            .insert(index.min(self.page_order.len()), page);
+        true
+    }
+
+    /// Delete a page and its content.
+    ///
+    /// If it was the current page, the last page of the document becomes the current page.
+    /// Returns `false` if the page was already deleted or, when streaming, already written to
+    /// the sink.
+    pub fn delete_page(&mut self, page: PageId) -> bool {
+        if self.reject_in_symbol() || self.pages.get(page.0).is_some_and(|state| state.written) {
+            return false;
+        }
+        let Some(index) = self.page_index(page) else {
+            return false;
+        };
+        self.page_order.remove(index);
+        if self.current_page == Some(page) {
+            self.leave_page();
+        }
+        let state = &mut self.pages[page.0];
+        state.deleted = true;
+        state.content = ContentStream::new();
+        state.links = Vec::new();
+        state.mcids = Vec::new();
+        if self.current_page.is_none() {
+            if let Some(last) = self.page_order.last().copied() {
+                if !self.pages[last.0].written {
+                    self.enter_page(last);
+                }
+            }
+        }
+        true
+    }
+
+    /// Store the drawing state of the current page, closing the open layers and tagged groups.
+    fn leave_page(&mut self) {
+        let Some(id) = self.current_page.take() else {
+            return;
+        };
+        let ops = self.structure.leave_page();
+        self.content_stream.extend_from_slice(ops.as_bytes());
+        for _ in &self.open_layers {
+            self.content_stream.extend_from_slice(b"EMC\n");
+        }
+        let state = &mut self.pages[id.0];
+        state.content = std::mem::take(&mut self.content_stream);
+        state.links = std::mem::take(&mut self.page_links);
+        state.bounds = self.content_bounds.take();
+        state.symbols = std::mem::take(&mut self.content_symbols);
+        state.layers = std::mem::take(&mut self.content_layers);
+        state.mcids = std::mem::take(&mut self.structure.page_mcids);
+        self.coordinates = Transform::identity();
+    }
+
+    /// Restore the drawing state of `id`, continuing the open layers and tagged groups.
+    fn enter_page(&mut self, id: PageId) {
+        let state = &mut self.pages[id.0];
+        self.content_stream = std::mem::take(&mut state.content);
+        self.page_links = std::mem::take(&mut state.links);
+        self.content_bounds = state.bounds.take();
+        self.content_symbols = std::mem::take(&mut state.symbols);
+        self.content_layers = std::mem::take(&mut state.layers);
+        self.structure.page_mcids = std::mem::take(&mut state.mcids);
+        self.coordinates = state.options.coordinates.to_page(state.size);
+        self.current_page = Some(id);
+        // Reopen the layers and tagged groups in the order they were opened
+        let tag_ops = self.structure.enter_page(id.0);
+        let open_layers = self.open_layers.clone();
+        let mut layers = open_layers.iter().peekable();
+        for (depth, op) in tag_ops.iter().enumerate() {
+            while let Some((layer, _)) = layers.next_if(|(_, at)| *at == depth) {
+                self.begin_marked_layer(*layer);
+            }
+            self.content_stream.extend_from_slice(op.as_bytes());
+        }
+        for (layer, _) in layers {
+            self.begin_marked_layer(*layer);
+        }
+    }
+
+    /// Write the pages that are not written yet (except `keep`), in document order.
+    fn write_open_pages(&mut self, keep: Option<PageId>) {
+        let ids: Vec<PageId> = self
+            .page_order
+            .iter()
+            .copied()
+            .filter(|id| Some(*id) != keep && !self.pages[id.0].written)
+            .collect();
+        let mut streams: Vec<Vec<u8>> = ids
+            .iter()
+            .map(|id| self.pages[id.0].content.take_bytes())
+            .collect();
+        let flate_decode = self.encode_streams(&mut streams);
+        for ((id, stream), flate_decode) in ids.into_iter().zip(streams).zip(flate_decode) {
+            self.write_page(id, stream, flate_decode);
+        }
+    }
+
+    /// Compress a stream if enabled, returning whether it was compressed.
+    ///
+    /// Reproducible output is not compressed, as the compressed bytes
+    /// depend on the zlib implementation.
+    fn encode_stream(&mut self, stream: &mut Vec<u8>) -> bool {
+        self.encode_streams(std::slice::from_mut(stream))[0]
+    }
+
+    /// Compress streams if enabled, returning which ones were compressed.
+    ///
+    /// Streams that fail to compress are written uncompressed, and the first
+    /// error is kept for [`finish`](Self::finish).
+    fn encode_streams(&mut self, streams: &mut [Vec<u8>]) -> Vec<bool> {
+        #[cfg(feature = "compress")]
+        if !self.reproducible {
+            return Self::compress_streams(streams)
+                .into_iter()
+                .map(|result| match result {
+                    Ok(compressed) => compressed,
+                    Err(err) => {
+                        self.error.get_or_insert(err.into());
+                        false
+                    }
+                })
+                .collect();
+        }
+        vec![false; streams.len()]
     }
 
     /// Compress the content stream using the ZlibEncoder.
     #[cfg(feature = "compress")]
-    fn compress_stream(stream: &mut Vec<u8>) -> bool {
+    fn compress_stream(stream: &mut Vec<u8>) -> std::io::Result<bool> {
         let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
-        encoder.write_all(stream).unwrap();
-        let compressed = encoder.finish().unwrap();
+        encoder.write_all(stream)?;
+        let compressed = encoder.finish()?;
         if compressed.len() + 21 < stream.len() {
             // 21 is for the length of "/Filter /FlateDecode"
             stream.clear();
             stream.extend_from_slice(&compressed);
-            return true;
+            Ok(true)
         } else {
-            return false;
+            Ok(false)
         }
     }
 
-    fn add_content(&mut self) {
-        #[cfg(feature = "compress")]
-        let flate_decode = Self::compress_stream(&mut self.content_stream);
-        #[cfg(not(feature = "compress"))]
-        let flate_decode = false;
-        let len = self.content_stream.len();
+    /// Compress content streams on the available cores, returning which ones were compressed.
+    #[cfg(feature = "compress")]
+    fn compress_streams(streams: &mut [Vec<u8>]) -> Vec<std::io::Result<bool>> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if streams.len() > 1 {
+            let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+            let chunk_size = streams.len().div_ceil(threads);
+            return std::thread::scope(|scope| {
+                let handles: Vec<_> = streams
+                    .chunks_mut(chunk_size)
+                    .map(|chunk| {
+                        scope.spawn(move || {
+                            chunk
+                                .iter_mut()
+                                .map(|stream| Self::compress_stream(stream))
+                                .collect::<Vec<_>>()
+                        })
+                    })
+                    .collect();
+                handles
+                    .into_iter()
+                    .flat_map(|handle| handle.join().unwrap())
+                    .collect()
+            });
+        }
+        streams
+            .iter_mut()
+            .map(|stream| Self::compress_stream(stream))
+            .collect()
+    }
+
+    /// Write the (already encoded) content stream and page object of a page that is not current.
+    fn write_page(&mut self, id: PageId, stream: Vec<u8>, flate_decode: bool) {
+        let media_box = self.media_box(id);
+        let state = &mut self.pages[id.0];
+        state.written = true;
+        let (page_obj, contents_obj) = (state.obj, state.contents_obj);
+        let entries = state.options.page_entries(media_box, self.style.precision);
+        let media_box = media_box.map(|value| Num(value, self.style.precision));
+        let struct_parents = if state.mcids.is_empty() {
+            String::new()
+        } else {
+            format!(" /StructParents {}", id.0)
+        };
+        state.content = ContentStream::new();
+        let links = std::mem::take(&mut state.links);
+        let used_symbols = std::mem::take(&mut state.symbols);
+        let used_layers = std::mem::take(&mut state.layers);
+        self.write_encoded_stream_object(contents_obj, "", stream, flate_decode);
+        let resources = self.resources_dict(&used_symbols, &used_layers);
+
+        let mut annots = String::new();
+        for area in links {
+            let obj = self.reserve_object();
+            self.pending_links.push((obj, area));
+            annots.push_str(&format!("{} 0 R ", obj));
+        }
+        let annots = if annots.is_empty() {
+            String::new()
+        } else {
+            format!(" /Annots [{}]", annots.trim_end())
+        };
+
+        self.write_object(
+            page_obj,
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [{} {} {} {}]{} /Resources {} /Contents {} 0 R{}{} >>",
+                media_box[0],
+                media_box[1],
+                media_box[2],
+                media_box[3],
+                entries,
+                resources,
+                contents_obj,
+                annots,
+                struct_parents
+            )
+            .as_bytes(),
+        );
+        if self.pending_objects.len() >= OBJECTS_PER_STREAM {
+            self.flush_object_stream();
+        }
+        // Hand the finished page over to the sink
+        if self.error.is_none() {
+            if let Err(err) = self.out.flush() {
+                self.error = Some(err.into());
+            }
+        }
+    }
+
+    /// Resource dictionary for the symbols and layers used by a content stream.
+    fn resources_dict(&self, symbols: &BTreeSet<SymbolId>, layers: &BTreeSet<LayerId>) -> String {
+        let entries: Vec<String> = [
+            symbol::xobject_entry(symbols, &self.symbols),
+            layer::properties_entry(layers, &self.layers),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if entries.is_empty() {
+            "<< >>".to_string()
+        } else {
+            format!("<< {} >>", entries.join(" "))
+        }
+    }
+
+    /// Add a stream object with extra dictionary entries, compressing the data if possible.
+    fn add_stream_object(&mut self, dict: &str, mut stream: Vec<u8>) -> usize {
+        let flate_decode = self.encode_stream(&mut stream);
+        self.add_encoded_stream_object(dict, stream, flate_decode)
+    }
+
+    /// Add a stream object whose data is already compressed if `flate_decode` is set.
+    fn add_encoded_stream_object(
+        &mut self,
+        dict: &str,
+        stream: Vec<u8>,
+        flate_decode: bool,
+    ) -> usize {
+        let obj = self.reserve_object();
+        self.write_encoded_stream_object(obj, dict, stream, flate_decode);
+        obj
+    }
+
+    /// Write a reserved stream object directly to the file.
+    fn write_encoded_stream_object(
+        &mut self,
+        obj: usize,
+        dict: &str,
+        stream: Vec<u8>,
+        flate_decode: bool,
+    ) {
+        let len = stream.len();
         let mut content: Vec<u8> = format!(
-            "<< /Length {} {}>>\nstream\n",
+            "<< {}{}/Length {} {}>>\nstream\n",
+            dict,
+            if dict.is_empty() { "" } else { " " },
             len,
             if flate_decode {
                 "/Filter /FlateDecode "
@@ -251,10 +1300,9 @@ impl Generator {
         )
         .as_bytes()
         .to_vec();
-        content.extend_from_slice(&self.content_stream);
-        content.extend_from_slice(b"\nendstream\n");
-        self.add_object(&content);
-        self.content_stream.clear();
+        content.extend_from_slice(&stream);
+        content.extend_from_slice(b"\nendstream");
+        self.emit_object(obj, &content);
     }
 
     pub fn line(
@@ -266,6 +1314,11 @@ impl Generator {
     ) -> Shape<'_> {
         Shape {
             content_stream: Some(&mut self.content_stream),
+            links: Some(&mut self.page_links),
+            bounds: Some(&mut self.content_bounds),
+            structure: Some(&mut self.structure),
+            style: self.style,
+            coordinates: self.coordinates,
             enum_type: ShapeType::Line,
             x: vec![x1.to_points(), x2.to_points()],
             y: vec![y1.to_points(), y2.to_points()],
@@ -276,6 +1329,11 @@ impl Generator {
     pub fn circle(&mut self, x: impl Length, y: impl Length, radius: impl Length) -> Shape<'_> {
         Shape {
             content_stream: Some(&mut self.content_stream),
+            links: Some(&mut self.page_links),
+            bounds: Some(&mut self.content_bounds),
+            structure: Some(&mut self.structure),
+            style: self.style,
+            coordinates: self.coordinates,
             enum_type: ShapeType::Circle,
             x: vec![x.to_points()],
             y: vec![y.to_points()],
@@ -293,6 +1351,11 @@ impl Generator {
     ) -> Shape<'_> {
         Shape {
             content_stream: Some(&mut self.content_stream),
+            links: Some(&mut self.page_links),
+            bounds: Some(&mut self.content_bounds),
+            structure: Some(&mut self.structure),
+            style: self.style,
+            coordinates: self.coordinates,
             enum_type: ShapeType::Rectangle,
             x: vec![x.to_points(), width.to_points()],
             y: vec![y.to_points(), height.to_points()],
@@ -300,51 +1363,987 @@ impl Generator {
         }
     }
 
-    // pub fn add_polygon<L: Length>(&mut self, points: &[(L, L)]) {
-    //     if points.is_empty() {
-    //         return;
-    //     }
-    //     let mut iter = points.iter();
-    //     if let Some((x, y)) = iter.next() {
-    //         self.content_stream
-    //             .push_str(&format!("{} {} m\n", x.to_points(), y.to_points()));
-    //     }
-    //     for (x, y) in iter {
-    //         self.content_stream
-    //             .push_str(&format!("{} {} l\n", x.to_points(), y.to_points()));
-    //     }
-    //     self.content_stream.push_str("h f\n"); // Close the path and fill
-    // }
-
-    pub fn get_default_page_size() -> (Pt, Pt) {
-        (
-            Pt(*DEFAULT_PAGE_WIDTH.lock().unwrap()),
-            Pt(*DEFAULT_PAGE_HEIGHT.lock().unwrap()),
+    /// Filled polygon through the points `(x, y)`.
+    ///
+    /// # Example
+    /// ```
+    /// use shapdf::*;
+    /// let mut generator = Generator::new("output/polygon.pdf".into());
+    /// generator.add_page();
+    /// generator
+    ///     .polygon(&[(Mm(20.), Mm(20.)), (Mm(60.), Mm(20.)), (Mm(40.), Mm(50.))])
+    ///     .with_color(NamedColor("green"))
+    ///     .with_stroke(NamedColor("black"))
+    ///     .draw()
+    ///     .unwrap();
+    /// ```
+    pub fn polygon<L: Length>(&mut self, points: &[(L, L)]) -> Shape<'_> {
+        let mut shape = self.batch(ShapeType::Polygon, Vec::new());
+        shape.path = Path::polygon(points);
+        shape
+    }
+
+    /// Filled path, possibly made of several subpaths such as an outline with holes
+    /// (see [`Path`]).
+    pub fn path(&mut self, path: Path) -> Shape<'_> {
+        let mut shape = self.batch(ShapeType::Path, Vec::new());
+        shape.path = path;
+        shape
+    }
+
+    /// Draw many lines `(x1, y1, x2, y2)` sharing the same options.
+    ///
+    /// Batches write the color, width and cap style once, which keeps large plots small and fast.
+    pub fn lines<L: Length>(&mut self, lines: &[(L, L, L, L)]) -> Shape<'_> {
+        let batch = lines
+            .iter()
+            .map(|(x1, y1, x2, y2)| {
+                [
+                    x1.to_points(),
+                    y1.to_points(),
+                    x2.to_points(),
+                    y2.to_points(),
+                ]
+            })
+            .collect();
+        self.batch(ShapeType::Line, batch)
+    }
+
+    /// Draw many circles `(x, y, radius)` sharing the same options.
+    ///
+    /// # Example
+    /// ```
+    /// use shapdf::*;
+    /// let mut generator = Generator::new("output/scatter.pdf".into());
+    /// generator.add_page();
+    /// let points: Vec<(Pt, Pt, Pt)> = (0..1000)
+    ///     .map(|i| (Pt(50. + i as f64 * 0.5), Pt(400. + (i as f64 / 50.).sin() * 100.), Pt(1.)))
+    ///     .collect();
+    /// generator.circles(&points).with_color(NamedColor("blue")).draw().unwrap();
+    /// ```
+    pub fn circles<L: Length>(&mut self, circles: &[(L, L, L)]) -> Shape<'_> {
+        let batch = circles
+            .iter()
+            .map(|(x, y, radius)| [x.to_points(), y.to_points(), radius.to_points(), 0.0])
+            .collect();
+        self.batch(ShapeType::Circle, batch)
+    }
+
+    /// Draw many rectangles `(x, y, width, height)` sharing the same options.
+    pub fn rectangles<L: Length>(&mut self, rectangles: &[(L, L, L, L)]) -> Shape<'_> {
+        let batch = rectangles
+            .iter()
+            .map(|(x, y, width, height)| {
+                [
+                    x.to_points(),
+                    y.to_points(),
+                    width.to_points(),
+                    height.to_points(),
+                ]
+            })
+            .collect();
+        self.batch(ShapeType::Rectangle, batch)
+    }
+
+    fn batch(&mut self, enum_type: ShapeType, batch: Vec<[f64; 4]>) -> Shape<'_> {
+        Shape {
+            content_stream: Some(&mut self.content_stream),
+            links: Some(&mut self.page_links),
+            bounds: Some(&mut self.content_bounds),
+            structure: Some(&mut self.structure),
+            style: self.style,
+            coordinates: self.coordinates,
+            enum_type,
+            batch,
+            ..Default::default()
+        }
+    }
+
+    /// Add a clickable link area on the current page.
+    ///
+    /// A non-finite area or target position is ignored and makes finalizing fail with
+    /// [`Error::NonFinite`].
+    pub fn link(
+        &mut self,
+        x: impl Length,
+        y: impl Length,
+        width: impl Length,
+        height: impl Length,
+        link: Link,
+    ) {
+        let (x, y) = (x.to_points(), y.to_points());
+        let rect = [x, y, x + width.to_points(), y + height.to_points()];
+        let checked = rect
+            .iter()
+            .try_for_each(|value| error::finite("link area", *value).map(|_| ()))
+            .and_then(|_| link.validate());
+        if let Err(err) = checked {
+            self.error.get_or_insert(err);
+            return;
+        }
+        self.page_links.push(LinkArea {
+            rect: self.coordinates.apply_rect(rect),
+            link,
+        });
+    }
+
+    /// Define a reusable symbol by drawing into a fresh content stream.
+    ///
+    /// The shapes drawn by `draw` are written once as a Form XObject,
+    /// which can then be stamped many times with [`place`](Self::place).
+    /// Links and tags drawn inside a symbol are ignored. Page operations inside `draw`
+    /// (e.g. [`add_page`](Self::add_page)) are ignored and make finalizing fail with
+    /// [`Error::PageInSymbol`].
+    ///
+    /// # Example
+    /// ```
+    /// use shapdf::*;
+    /// let mut generator = Generator::new("output/symbols.pdf".into());
+    /// generator.add_page();
+    /// let chair = generator.define_symbol(|g| {
+    ///     g.rectangle(Mm(0.), Mm(0.), Mm(5.), Mm(5.)).draw().unwrap();
+    ///     g.circle(Mm(2.5), Mm(2.5), Mm(1.)).with_color(NamedColor("red")).draw().unwrap();
+    /// });
+    /// for i in 0..10 {
+    ///     generator.place(chair, Mm(10. * i as f64), Mm(20.), Transform::identity()).unwrap();
+    /// }
+    /// ```
+    pub fn define_symbol<F: FnOnce(&mut Generator<W>)>(&mut self, draw: F) -> SymbolId {
+        let stream = std::mem::take(&mut self.content_stream);
+        let links = std::mem::take(&mut self.page_links);
+        let bounds = self.content_bounds.take();
+        let used = std::mem::take(&mut self.content_symbols);
+        let used_layers = std::mem::take(&mut self.content_layers);
+        let suspended = std::mem::replace(&mut self.structure.suspended, true);
+        let coordinates = std::mem::take(&mut self.coordinates);
+
+        draw(self);
+
+        self.coordinates = coordinates;
+        self.structure.suspended = suspended;
+        let symbol_stream = std::mem::replace(&mut self.content_stream, stream);
+        self.page_links = links;
+        let bbox = std::mem::replace(&mut self.content_bounds, bounds).unwrap_or([0.0; 4]);
+        let symbol_used = std::mem::replace(&mut self.content_symbols, used);
+        let symbol_layers = std::mem::replace(&mut self.content_layers, used_layers);
+
+        let [x1, y1, x2, y2] = bbox.map(|value| Num(value, self.style.precision));
+        let dict = format!(
+            "/Type /XObject /Subtype /Form /BBox [{} {} {} {}] /Resources {}",
+            x1,
+            y1,
+            x2,
+            y2,
+            self.resources_dict(&symbol_used, &symbol_layers)
+        );
+        let obj = self.add_stream_object(&dict, symbol_stream.bytes);
+        self.symbols.push(Symbol { obj, bbox });
+        SymbolId(self.symbols.len() - 1)
+    }
+
+    /// Place a symbol with its origin at `(x, y)`, after applying `transform`.
+    ///
+    /// Fails if the symbol was not defined by this generator or the transform is not finite.
+    pub fn place(
+        &mut self,
+        symbol: SymbolId,
+        x: impl Length,
+        y: impl Length,
+        transform: Transform,
+    ) -> Result<(), Error> {
+        let Some(bbox) = self.symbols.get(symbol.0).map(|s| s.bbox) else {
+            return Err(Error::UnknownSymbol);
+        };
+        let (x, y) = self.coordinates.apply(x.to_points(), y.to_points());
+        let t = transform.then(Transform::translate(Pt(x), Pt(y)));
+        t.validate()?;
+        let marked = self.structure.begin_untagged();
+        if let Some(op) = &marked {
+            self.content_stream.extend_from_slice(op.as_bytes());
+        }
+        self.content_stream.save();
+        self.content_stream
+            .transform([t.a, t.b, t.c, t.d, t.e, t.f], self.style.precision);
+        self.content_stream
+            .extend_from_slice(format!("/{} Do\n", symbol.name()).as_bytes());
+        self.content_stream.restore();
+        if marked.is_some() {
+            self.content_stream.extend_from_slice(b"EMC\n");
+        }
+        self.content_symbols.insert(symbol);
+        self.content_bounds = Some(union_rect(self.content_bounds, t.apply_rect(bbox)));
+        Ok(())
+    }
+
+    /// Draw a [`Drawable`] with the current style.
+    ///
+    /// # Example
+    /// ```
+    /// use shapdf::*;
+    /// let mut generator = Generator::new("output/drawable.pdf".into());
+    /// generator.add_page();
+    /// let marker = ShapeNode::circle(Mm(0.), Mm(0.), Mm(2.)).with_color(NamedColor("red"));
+    /// generator.draw(&marker).unwrap();
+    /// generator
+    ///     .draw_transformed(&marker, Transform::translate(Mm(20.), Mm(20.)))
+    ///     .unwrap();
+    /// ```
+    pub fn draw(&mut self, drawable: &(impl Drawable + ?Sized)) -> Result<(), Error> {
+        drawable.draw(&mut self.canvas())
+    }
+
+    /// Draw a [`Drawable`] with `transform` applied.
+    pub fn draw_transformed(
+        &mut self,
+        drawable: &(impl Drawable + ?Sized),
+        transform: Transform,
+    ) -> Result<(), Error> {
+        self.canvas()
+            .transformed(transform, |canvas| drawable.draw(canvas))
+    }
+
+    fn canvas(&mut self) -> Canvas<'_> {
+        Canvas::new(
+            &mut self.content_stream,
+            &mut self.page_links,
+            &mut self.content_bounds,
+            Some(&mut self.structure),
+            self.style,
+            self.coordinates,
         )
     }
 
-    pub fn set_default_page_size(width: impl Length, height: impl Length) {
-        *DEFAULT_PAGE_WIDTH.lock().unwrap() = width.to_points();
-        *DEFAULT_PAGE_HEIGHT.lock().unwrap() = height.to_points();
+    /// Run `draw` with `t` applied (scoped with `q`/`Q`), mapping the bounds
+    /// and link areas of the drawn shapes to page space.
+    pub(crate) fn with_transform(
+        &mut self,
+        t: Transform,
+        draw: impl FnOnce(&mut Self) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let scope = TransformScope::begin(
+            t.in_coordinates(&self.coordinates)?,
+            &mut self.content_stream,
+            &self.page_links,
+            &mut self.content_bounds,
+            self.style.precision,
+        )?;
+        let result = draw(self);
+        scope.end(
+            &mut self.content_stream,
+            &mut self.page_links,
+            &mut self.content_bounds,
+        );
+        result
+    }
+
+    /// Draw on a layer (optional content group) that viewers can show or hide.
+    ///
+    /// Shapes drawn through the returned scope belong to the layer until the scope is dropped.
+    /// Layers are identified by name, so drawing on `"Grid"` again (even on another page)
+    /// adds to the same layer; `visible` sets whether it is shown when the document is opened.
+    /// Drawing on a layer again with a different `visible` makes finalizing fail with
+    /// [`Error::LayerVisibility`].
+    ///
+    /// # Example
+    /// ```
+    /// use shapdf::*;
+    /// let mut generator = Generator::new("output/layers.pdf".into());
+    /// generator.add_page();
+    /// {
+    ///     let mut grid = generator.layer("Grid", false);
+    ///     for i in 0..10 {
+    ///         grid.line(Mm(10. * i as f64), Mm(0.), Mm(10. * i as f64), Mm(100.)).draw().unwrap();
+    ///     }
+    /// }
+    /// generator.rectangle(Mm(20.), Mm(20.), Mm(30.), Mm(10.)).draw().unwrap();
+    /// ```
+    pub fn layer(&mut self, name: &str, visible: bool) -> LayerScope<'_, W> {
+        let id = match self.layers.iter().position(|layer| layer.name == name) {
+            Some(idx) => {
+                if self.layers[idx].visible != visible {
+                    self.error
+                        .get_or_insert(Error::LayerVisibility(name.to_string()));
+                }
+                LayerId(idx)
+            }
+            None => {
+                let obj = self.reserve_object();
+                self.layers.push(Layer {
+                    name: name.to_string(),
+                    visible,
+                    obj,
+                });
+                LayerId(self.layers.len() - 1)
+            }
+        };
+        self.open_layers.push((id, self.structure.depth()));
+        self.begin_marked_layer(id);
+        LayerScope { generator: self }
+    }
+
+    /// Draw a group of shapes tagged as a single element of the structure tree (tagged PDF).
+    ///
+    /// Shapes drawn through the returned scope form one element with the role and
+    /// alternate text of `tag` until the scope is dropped; tags of the shapes inside are ignored.
+    /// Decorations can be marked with [`Tag::artifact`] so that assistive technology skips them.
+    /// Once the document is tagged, untagged content drawn outside of tagged groups is marked
+    /// as an artifact; call [`set_tagged`](Self::set_tagged) first to include content drawn
+    /// before the first tag.
+    ///
+    /// # Example
+    /// ```
+    /// use shapdf::*;
+    /// let mut generator = Generator::new("output/tagged.pdf".into());
+    /// generator.add_page();
+    /// {
+    ///     let mut chart = generator.tag(Tag::figure("Bar chart of sales: 3, 5 and 4 units"));
+    ///     for (i, h) in [3., 5., 4.].iter().enumerate() {
+    ///         chart.rectangle(Mm(20. + 15. * i as f64), Mm(20.), Mm(10.), Mm(10. * h)).draw().unwrap();
+    ///     }
+    /// }
+    /// generator
+    ///     .line(Mm(15.), Mm(20.), Mm(70.), Mm(20.))
+    ///     .with_tag(Tag::artifact())
+    ///     .draw().unwrap();
+    /// ```
+    pub fn tag(&mut self, tag: Tag) -> TagScope<'_, W> {
+        if let Some(op) = self.structure.open(&tag) {
+            self.content_stream.extend_from_slice(op.as_bytes());
+        }
+        TagScope { generator: self }
+    }
+
+    /// Close the innermost tagged group (called when its [`TagScope`] is dropped).
+    pub(crate) fn end_tag(&mut self) {
+        if self.structure.close() {
+            self.content_stream.extend_from_slice(b"EMC\n");
+        }
     }
 
-    pub fn get_default_width() -> Pt {
-        Shape::get_default_width()
+    pub fn get_style(&self) -> Style {
+        self.style
     }
 
-    pub fn set_default_width(width: impl Length) {
-        Shape::set_default_width(width);
+    pub fn set_style(&mut self, style: Style) {
+        self.style = style;
     }
 
-    pub fn set_default_cap_type(cap_type: CapType) {
-        Shape::set_default_cap_type(cap_type);
+    /// Save the current style, to be restored by [`pop_style`](Self::pop_style).
+    ///
+    /// # Example
+    /// ```
+    /// use shapdf::*;
+    /// let mut generator = Generator::new("output/style.pdf".into());
+    /// generator.add_page();
+    /// generator.push_style();
+    /// generator.set_default_color(NamedColor("red"));
+    /// generator.circle(Mm(20.), Mm(20.), Mm(5.)).draw().unwrap(); // red
+    /// generator.pop_style();
+    /// generator.circle(Mm(40.), Mm(20.), Mm(5.)).draw().unwrap(); // black
+    /// ```
+    pub fn push_style(&mut self) {
+        self.style_stack.push(self.style);
+    }
+
+    /// Restore the style saved by the last [`push_style`](Self::push_style).
+    ///
+    /// Returns `false` (keeping the current style) if no style was saved.
+    pub fn pop_style(&mut self) -> bool {
+        match self.style_stack.pop() {
+            Some(style) => {
+                self.style = style;
+                true
+            }
+            None => false,
+        }
     }
 
-    pub fn set_default_color(color: impl Color) {
-        Shape::set_default_color(color);
+    pub fn get_default_page_size(&self) -> (Pt, Pt) {
+        let (width, height) = self.style.page_size;
+        (Pt(width), Pt(height))
     }
 
-    pub fn set_default_angle(angle: impl Angle) {
-        Shape::set_default_angle(angle);
+    pub fn set_default_page_size(&mut self, width: impl Length, height: impl Length) {
+        self.style.page_size = (width.to_points(), height.to_points());
+    }
+
+    pub fn get_default_width(&self) -> Pt {
+        Pt(self.style.width)
+    }
+
+    pub fn set_default_width(&mut self, width: impl Length) {
+        self.style.width = width.to_points();
+    }
+
+    pub fn get_default_cap_type(&self) -> CapType {
+        self.style.cap_type
+    }
+
+    pub fn set_default_cap_type(&mut self, cap_type: CapType) {
+        self.style.cap_type = cap_type;
+    }
+
+    pub fn get_default_color(&self) -> Rgb {
+        let (r, g, b) = self.style.color;
+        Rgb(r, g, b)
+    }
+
+    pub fn set_default_color(&mut self, color: impl Color) {
+        self.style.color = color.to_rgb();
+    }
+
+    pub fn get_default_angle(&self) -> Degree {
+        Degree(self.style.angle.to_degrees())
+    }
+
+    pub fn set_default_angle(&mut self, angle: impl Angle) {
+        self.style.angle = angle.to_radians();
+    }
+
+    pub fn get_default_anchor(&self) -> Anchor {
+        self.style.anchor
+    }
+
+    pub fn set_default_anchor(&mut self, anchor: Anchor) {
+        self.style.anchor = anchor;
+    }
+
+    pub fn get_default_fill_rule(&self) -> FillRule {
+        self.style.fill_rule
+    }
+
+    pub fn set_default_fill_rule(&mut self, fill_rule: FillRule) {
+        self.style.fill_rule = fill_rule;
+    }
+
+    pub fn get_precision(&self) -> u8 {
+        self.style.precision
+    }
+
+    /// Set the decimal places of coordinates and lengths written to the PDF, clamped to 9.
+    ///
+    /// Numbers are rounded and written without trailing zeros. Color components keep
+    /// 3 decimal places, and the scaling coefficients of transformations 3 more than
+    /// the coordinates.
+    ///
+    /// The precision is part of the style, so [`pop_style`](Self::pop_style) restores the
+    /// previous one. Document-level numbers (page boxes, links, bookmarks, symbol bounds)
+    /// use the precision in effect when they are written.
+    pub fn set_precision(&mut self, decimals: u8) {
+        self.style.precision = decimals.min(MAX_PRECISION);
+    }
+
+    pub fn get_layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    fn begin_marked_layer(&mut self, id: LayerId) {
+        self.content_stream
+            .extend_from_slice(format!("/OC /{} BDC\n", id.name()).as_bytes());
+        self.content_layers.insert(id);
+    }
+
+    /// Close the innermost open layer (called when its [`LayerScope`] is dropped).
+    pub(crate) fn end_layer(&mut self) {
+        if self.open_layers.pop().is_some() {
+            self.content_stream.extend_from_slice(b"EMC\n");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+
+    #[test]
+    fn symbols_are_written_once() {
+        let mut generator = Generator::new(path::PathBuf::new());
+        generator.add_page();
+        let icon = generator.define_symbol(|g| {
+            g.rectangle(Mm(0.), Mm(0.), Mm(4.), Mm(2.)).draw().unwrap();
+        });
+        for i in 0..50 {
+            generator
+                .place(icon, Mm(5. * i as f64), Mm(10.), Transform::scale(2., 2.))
+                .unwrap();
+        }
+        let bytes = generator.to_pdf_bytes().unwrap();
+        let text = String::from_utf8_lossy(&bytes);
+        assert_eq!(text.matches("/Subtype /Form").count(), 1);
+        assert!(text.contains("/Resources << /XObject << /S0 5 0 R >> >>"));
+    }
+
+    #[test]
+    fn symbols_keep_pages_and_square_caps() {
+        let mut generator = Generator::new(path::PathBuf::new());
+        let first = generator.add_page();
+        let symbol = generator.define_symbol(|g| {
+            g.line(Pt(0.), Pt(0.), Pt(10.), Pt(10.))
+                .with_width(Pt(2.))
+                .with_cap_type(CapType::Square)
+                .draw()
+                .unwrap();
+            let page = g.add_page();
+            assert_eq!(g.page_index(page), None);
+            assert!(!g.select_page(first));
+        });
+        // The corners of the square caps stick out diagonally beyond half the width
+        let [x1, y1, x2, y2] = generator.symbols[symbol.0].bbox;
+        let corner = 2f64.sqrt();
+        assert!((x1 + corner).abs() < 1e-9 && (y1 + corner).abs() < 1e-9);
+        assert!((x2 - 10. - corner).abs() < 1e-9 && (y2 - 10. - corner).abs() < 1e-9);
+        assert_eq!(generator.page_order, [first]);
+        assert!(generator.content_stream.is_empty());
+        assert!(matches!(generator.to_pdf_bytes(), Err(Error::PageInSymbol)));
+    }
+
+    #[test]
+    fn invalid_shapes_draw_nothing() {
+        let mut generator = Generator::new(path::PathBuf::new());
+        generator.add_page();
+        assert!(matches!(
+            generator.circle(Mm(10.), Mm(10.), Mm(-1.)).draw(),
+            Err(Error::Negative { name: "radius", .. })
+        ));
+        assert!(matches!(
+            generator.line(Pt(f64::NAN), Pt(0.), Pt(1.), Pt(1.)).draw(),
+            Err(Error::NonFinite { name: "x", .. })
+        ));
+        assert!(matches!(
+            generator
+                .rectangle(Mm(0.), Mm(0.), Mm(1.), Mm(1.))
+                .with_color(Rgb(1.5, 0., 0.))
+                .draw(),
+            Err(Error::ColorOutOfRange { .. })
+        ));
+        let points = [(Pt(0.), Pt(0.), Pt(1.)), (Pt(5.), Pt(5.), Pt(-1.))];
+        assert!(generator.circles(&points).draw().is_err());
+        assert!(generator.content_stream.is_empty());
+        let unknown = Generator::new(path::PathBuf::new()).define_symbol(|_| {});
+        assert!(matches!(
+            generator.place(unknown, Mm(0.), Mm(0.), Transform::identity()),
+            Err(Error::UnknownSymbol)
+        ));
+    }
+
+    #[test]
+    fn layers_continue_across_pages() {
+        let mut generator = Generator::new(path::PathBuf::new());
+        generator.add_page();
+        {
+            let mut grid = generator.layer("Grid", false);
+            grid.line(Mm(0.), Mm(0.), Mm(10.), Mm(10.)).draw().unwrap();
+            grid.add_page();
+            grid.line(Mm(0.), Mm(0.), Mm(10.), Mm(10.)).draw().unwrap();
+        }
+        generator
+            .layer("Grid", false)
+            .circle(Mm(5.), Mm(5.), Mm(1.))
+            .draw()
+            .unwrap();
+        assert_eq!(generator.get_layers().len(), 1);
+        let bytes = generator.to_pdf_bytes().unwrap();
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(text.contains("/Resources << /Properties << /OC0 5 0 R >> >>"));
+        assert!(text.contains(
+            "/OCProperties << /OCGs [5 0 R] /D << /Name (Layers) /Order [5 0 R] /OFF [5 0 R] >> >>"
+        ));
+
+        let mut generator = Generator::new(path::PathBuf::new());
+        generator.add_page();
+        generator.layer("Grid", false);
+        generator.layer("Grid", true);
+        assert!(matches!(
+            generator.to_pdf_bytes(),
+            Err(Error::LayerVisibility(name)) if name == "Grid"
+        ));
+    }
+
+    #[test]
+    fn layers_and_tags_reopen_in_nesting_order() {
+        let mut generator = Generator::new(path::PathBuf::new());
+        let first = generator.add_page();
+        let second = generator.add_page();
+        generator.select_page(first);
+        {
+            let mut figure = generator.tag(Tag::figure("Chart"));
+            let mut grid = figure.layer("Grid", true);
+            grid.select_page(second);
+            grid.line(Mm(0.), Mm(0.), Mm(10.), Mm(10.)).draw().unwrap();
+        }
+        let content = String::from_utf8_lossy(generator.content_stream.as_bytes());
+        assert!(content.starts_with("/Figure <</MCID 0>> BDC\n/OC /OC0 BDC\n"));
+        assert!(content.ends_with("EMC\nEMC\n"));
+    }
+
+    fn draw_pages<W: Write>(generator: &mut Generator<W>) {
+        for i in 0..3 {
+            generator.add_page();
+            generator
+                .circle(Mm(10.), Mm(10.), Mm(i as f64 + 1.))
+                .draw()
+                .unwrap();
+        }
+        generator.set_title("Streamed");
+    }
+
+    /// Sink sharing its contents, to inspect what was written before `finish`.
+    #[derive(Debug, Default, Clone)]
+    struct SharedSink(std::rc::Rc<std::cell::RefCell<Vec<u8>>>);
+
+    impl Write for SharedSink {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn streaming_writes_finished_pages() {
+        let sink = SharedSink::default();
+        let mut streamed = Generator::to_writer(sink.clone());
+        draw_pages(&mut streamed);
+        // The first two pages are written, the last one is still open
+        let text = String::from_utf8_lossy(&sink.0.borrow()).into_owned();
+        assert_eq!(text.matches("/Type /Page ").count(), 2);
+        let first = streamed.get_page_ids()[0];
+        assert!(!streamed.select_page(first));
+        assert!(!streamed.delete_page(first));
+        streamed.finish().unwrap();
+        let text = String::from_utf8_lossy(&sink.0.borrow()).into_owned();
+        assert_eq!(text.matches("/Type /Page ").count(), 3);
+    }
+
+    #[test]
+    fn streaming_matches_in_memory_output() {
+        let mut in_memory = Generator::new(path::PathBuf::new());
+        draw_pages(&mut in_memory);
+        let mut streamed = Generator::to_writer(Vec::new());
+        draw_pages(&mut streamed);
+        assert_eq!(
+            in_memory.to_pdf_bytes().unwrap(),
+            streamed.finish().unwrap()
+        );
+    }
+
+    #[cfg(feature = "compress")]
+    #[test]
+    fn object_streams_replace_xref_table() {
+        let render = |object_streams: bool| {
+            let mut generator = Generator::new(path::PathBuf::new());
+            generator.set_object_streams(object_streams);
+            for i in 0..50 {
+                generator.add_page();
+                generator.bookmark(format!("Page {}", i + 1), i, Mm(0.));
+            }
+            generator.to_pdf_bytes().unwrap()
+        };
+        let (classic, packed) = (render(false), render(true));
+        let text = String::from_utf8_lossy(&packed);
+        assert!(!text.contains("\nxref\n") && !text.contains("trailer"));
+        assert!(text.contains("/Type /XRef /Size 156 /W [1 2 2] /Root 1 0 R"));
+        assert!(text.contains("/Type /ObjStm"));
+        // Page and outline dictionaries are only found compressed
+        assert!(!text.contains("/Type /Page "));
+        assert!(packed.len() * 3 < classic.len());
+    }
+
+    #[test]
+    fn pages_built_in_parallel_match_sequential_drawing() {
+        let mut sequential = Generator::new(path::PathBuf::new());
+        for i in 0..8 {
+            sequential.add_page_a4();
+            sequential
+                .circle(Mm(10.), Mm(10.), Mm(i as f64 + 1.))
+                .draw()
+                .unwrap();
+        }
+        let pages: Vec<PageBuilder> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|i| {
+                    scope.spawn(move || {
+                        let mut page = PageBuilder::a4();
+                        page.circle(Mm(10.), Mm(10.), Mm(i as f64 + 1.))
+                            .draw()
+                            .unwrap();
+                        page
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        let mut parallel = Generator::new(path::PathBuf::new());
+        let ids = parallel.add_pages(pages);
+        assert_eq!(parallel.current_page(), ids.last().copied());
+        assert_eq!(
+            parallel.to_pdf_bytes().unwrap(),
+            sequential.to_pdf_bytes().unwrap()
+        );
+    }
+
+    #[test]
+    fn invalid_pages_and_destinations_fail_on_finish() {
+        let fails = |build: &dyn Fn(&mut Generator), name: &str| {
+            let mut generator = Generator::new(path::PathBuf::new());
+            generator.add_page();
+            build(&mut generator);
+            // The error is reported again by a retry instead of returning the truncated output
+            for _ in 0..2 {
+                match generator.to_pdf_bytes() {
+                    Err(
+                        Error::NonFinite { name: found, .. } | Error::Negative { name: found, .. },
+                    ) => assert_eq!(found, name),
+                    other => panic!("unexpected result {:?}", other.map(|_| ())),
+                }
+            }
+        };
+        fails(
+            &|g| {
+                g.add_page_with_size(Pt(f64::NAN), Pt(100.));
+            },
+            "page width",
+        );
+        fails(
+            &|g| {
+                g.add_page_with_size(Pt(100.), Pt(-1.));
+            },
+            "page height",
+        );
+        fails(
+            &|g| {
+                let options =
+                    PageOptions::new().with_trim_box(Pt(0.), Pt(0.), Pt(f64::INFINITY), Pt(1.));
+                g.add_page_with_options(Pt(100.), Pt(100.), options);
+            },
+            "page box",
+        );
+        fails(
+            &|g| {
+                g.bookmark("Start", 0, Pt(f64::NAN));
+            },
+            "bookmark y",
+        );
+        fails(
+            &|g| {
+                g.link(
+                    Pt(0.),
+                    Pt(0.),
+                    Pt(10.),
+                    Pt(10.),
+                    Link::page_at(0, Pt(f64::NAN)),
+                )
+            },
+            "link y",
+        );
+        fails(
+            &|g| {
+                let mut page = PageBuilder::a4();
+                page.link(Pt(f64::NAN), Pt(0.), Pt(10.), Pt(10.), Link::page(0));
+                g.add_pages([page]);
+            },
+            "link area",
+        );
+
+        let mut generator = Generator::new(path::PathBuf::new());
+        generator.add_page();
+        assert!(generator
+            .circle(Mm(5.), Mm(5.), Mm(1.))
+            .with_link(Link::page_at(0, Pt(f64::NAN)))
+            .draw()
+            .is_err());
+    }
+
+    #[test]
+    fn fitted_pages_clip_their_boxes() {
+        let mut generator = Generator::new(path::PathBuf::new());
+        let options = PageOptions::new()
+            .with_fit(Pt(10.))
+            .with_crop_box(Pt(0.), Pt(0.), Pt(500.), Pt(500.))
+            .with_trim_box(Pt(95.), Pt(85.), Pt(10.), Pt(400.));
+        generator.add_page_with_options(Pt(612.), Pt(792.), options);
+        generator
+            .rectangle(Pt(100.), Pt(100.), Pt(50.), Pt(20.))
+            .draw()
+            .unwrap();
+        let bytes = generator.to_pdf_bytes().unwrap();
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(text.contains(
+            "/MediaBox [90 90 160 130] /CropBox [90 90 160 130] /TrimBox [95 90 105 130]"
+        ));
+
+        let mut generator = Generator::new(path::PathBuf::new());
+        generator.add_page_fit(Pt(-10.));
+        assert!(matches!(
+            generator.to_pdf_bytes(),
+            Err(Error::Negative {
+                name: "fit margin",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn precision_is_clamped_and_restored_with_the_style() {
+        let mut generator = Generator::new(path::PathBuf::new());
+        generator.set_precision(12);
+        assert_eq!(generator.get_precision(), 9);
+        generator.push_style();
+        generator.set_precision(1);
+        assert!(generator.pop_style());
+        assert_eq!(generator.get_precision(), 9);
+    }
+
+    #[test]
+    fn content_before_added_pages_belongs_to_the_first() {
+        let mut page = PageBuilder::a4();
+        page.circle(Mm(10.), Mm(10.), Mm(1.)).draw().unwrap();
+        let mut generator = Generator::new(path::PathBuf::new());
+        generator.set_reproducible(true);
+        generator
+            .line(Mm(0.), Mm(0.), Mm(5.), Mm(5.))
+            .draw()
+            .unwrap();
+        generator.add_pages([page]);
+        let bytes = generator.to_pdf_bytes().unwrap();
+        let text = String::from_utf8_lossy(&bytes);
+        let content = text.split("stream\n").nth(1).unwrap();
+        let line = content.find("0 0 m\n14.173 14.173 l\n").unwrap();
+        let circle = content.find("28.346 28.346 m\n28.346 28.346 l\n").unwrap();
+        assert!(line < circle);
+    }
+
+    #[test]
+    fn added_pages_pass_on_their_graphics_state() {
+        let mut page = PageBuilder::a4();
+        page.line(Pt(0.), Pt(0.), Pt(1.), Pt(0.))
+            .with_color(Rgb(0., 0., 1.))
+            .draw()
+            .unwrap();
+        let mut generator = Generator::new(path::PathBuf::new());
+        generator.set_reproducible(true);
+        generator.set_default_color(Rgb(1., 0., 0.));
+        generator
+            .line(Pt(0.), Pt(0.), Pt(2.), Pt(0.))
+            .draw()
+            .unwrap();
+        generator.add_pages([page]);
+        generator
+            .line(Pt(0.), Pt(0.), Pt(3.), Pt(0.))
+            .draw()
+            .unwrap();
+        let bytes = generator.to_pdf_bytes().unwrap();
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(text.contains("0 0 1 RG\n1 w\n0 J\n0 0 m\n1 0 l\nS\n1 0 0 RG\n0 0 m\n3 0 l\n"));
+    }
+
+    #[test]
+    fn pages_can_be_revisited_inserted_moved_and_deleted() {
+        let mut generator = Generator::new(path::PathBuf::new());
+        let first = generator.add_page(); // objects 3 and 4 (contents)
+        let second = generator.add_page(); // objects 5 and 6
+        let third = generator.add_page(); // objects 7 and 8
+        assert!(generator.select_page(first));
+        generator
+            .circle(Mm(10.), Mm(10.), Mm(1.))
+            .with_link(Link::uri("https://example.com"))
+            .draw()
+            .unwrap();
+        let toc = generator.insert_page_with_size(0, Mm(100.), Mm(100.)); // objects 9 and 10
+        assert_eq!(generator.current_page(), Some(toc));
+        assert!(generator.move_page(third, 1));
+        assert!(generator.delete_page(second));
+        assert!(!generator.delete_page(second));
+        assert!(!generator.select_page(second));
+        assert_eq!(generator.get_page_ids(), &[toc, third, first]);
+        assert_eq!(generator.page_index(first), Some(2));
+
+        let bytes = generator.to_pdf_bytes().unwrap();
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(text.contains("/Kids [9 0 R 7 0 R 3 0 R] /Count 3"));
+        assert!(!text.contains("\n5 0 obj") && !text.contains("\n6 0 obj"));
+        // The linked circle was drawn on the first page only
+        let first_page = text.split("3 0 obj\n").nth(1).unwrap();
+        assert!(first_page.lines().next().unwrap().contains("/Annots"));
+        assert_eq!(text.matches("/Annots").count(), 1);
+    }
+
+    #[derive(Debug)]
+    struct FailingSink;
+
+    impl Write for FailingSink {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn sink_errors_are_reported_on_finish() {
+        let mut generator = Generator::to_writer(FailingSink);
+        draw_pages(&mut generator);
+        let err = generator.finish().unwrap_err();
+        assert!(err.to_string().contains("closed"));
+    }
+
+    #[test]
+    fn pdfa_checks_string_limits() {
+        let mut generator = Generator::new(path::PathBuf::new());
+        generator.set_conformance(Conformance::PdfA2b);
+        generator.add_page();
+        let uri = format!("https://example.com/{}", "a".repeat(40000));
+        generator.link(Mm(0.), Mm(0.), Mm(5.), Mm(5.), Link::uri(uri));
+        let err = generator.to_pdf_bytes().unwrap_err();
+        assert!(err.to_string().contains("link URI exceeds 32767 bytes"));
+    }
+
+    #[test]
+    fn links_and_bookmarks_need_existing_pages() {
+        let mut generator = Generator::new(path::PathBuf::new());
+        generator.add_page();
+        generator.link(Mm(0.), Mm(0.), Mm(5.), Mm(5.), Link::page(1));
+        assert!(matches!(
+            generator.to_pdf_bytes(),
+            Err(Error::MissingPage(1))
+        ));
+
+        let mut generator = Generator::new(path::PathBuf::new());
+        generator.add_page();
+        generator.bookmark("Appendix", 3, Mm(0.));
+        assert!(matches!(
+            generator.to_pdf_bytes(),
+            Err(Error::MissingPage(3))
+        ));
+    }
+
+    #[test]
+    fn bookmarks_need_known_parents() {
+        let mut other = Generator::new(path::PathBuf::new());
+        other.bookmark("Start", 0, Mm(0.));
+        let foreign = other.bookmark("End", 0, Mm(0.));
+
+        let mut generator = Generator::new(path::PathBuf::new());
+        generator.add_page();
+        generator.bookmark_child(foreign, "Orphan", 0, Mm(0.));
+        assert_eq!(generator.get_bookmarks()[0].parent, None);
+        assert!(matches!(
+            generator.to_pdf_bytes(),
+            Err(Error::UnknownBookmark)
+        ));
+
+        let mut generator = Generator::new(path::PathBuf::new());
+        generator.add_page();
+        generator.set_bookmark_open(foreign, false);
+        assert!(matches!(
+            generator.to_pdf_bytes(),
+            Err(Error::UnknownBookmark)
+        ));
     }
 }
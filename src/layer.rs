@@ -0,0 +1,123 @@
+use crate::generator::Generator;
+use crate::metadata::encode_text_string;
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::ops::{Deref, DerefMut};
+
+/// Handle to an optional content group (layer) created with [`Generator::layer`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LayerId(pub(crate) usize);
+
+impl LayerId {
+    /// Resource name of the layer in the `/Properties` dictionary.
+    pub(crate) fn name(&self) -> String {
+        format!("OC{}", self.0)
+    }
+}
+
+/// An optional content group that viewers can show or hide.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layer {
+    pub name: String,
+    /// Whether the layer is shown when the document is opened.
+    pub visible: bool,
+    pub(crate) obj: usize,
+}
+
+/// Drawing scope of a layer, returned by [`Generator::layer`].
+///
+/// Shapes drawn through the scope belong to the layer until it is dropped.
+#[derive(Debug)]
+pub struct LayerScope<'a, W: Write = Vec<u8>> {
+    pub(crate) generator: &'a mut Generator<W>,
+}
+
+impl<W: Write> Deref for LayerScope<'_, W> {
+    type Target = Generator<W>;
+
+    fn deref(&self) -> &Generator<W> {
+        self.generator
+    }
+}
+
+impl<W: Write> DerefMut for LayerScope<'_, W> {
+    fn deref_mut(&mut self) -> &mut Generator<W> {
+        self.generator
+    }
+}
+
+impl<W: Write> Drop for LayerScope<'_, W> {
+    fn drop(&mut self) {
+        self.generator.end_layer();
+    }
+}
+
+/// `/Properties` resource entry referencing the used layers, if any.
+pub(crate) fn properties_entry(used: &BTreeSet<LayerId>, layers: &[Layer]) -> Option<String> {
+    if used.is_empty() {
+        return None;
+    }
+    let properties: Vec<String> = used
+        .iter()
+        .map(|id| format!("/{} {} 0 R", id.name(), layers[id.0].obj))
+        .collect();
+    Some(format!("/Properties << {} >>", properties.join(" ")))
+}
+
+/// Optional content group dictionary of a layer.
+pub(crate) fn group_dict(layer: &Layer) -> String {
+    format!("<< /Type /OCG /Name {} >>", encode_text_string(&layer.name))
+}
+
+/// `/OCProperties` catalog entry with the default configuration of the layers.
+pub(crate) fn oc_properties(layers: &[Layer]) -> String {
+    let refs = |filter: &dyn Fn(&Layer) -> bool| -> String {
+        layers
+            .iter()
+            .filter(|layer| filter(layer))
+            .map(|layer| format!("{} 0 R", layer.obj))
+            .collect::<Vec<String>>()
+            .join(" ")
+    };
+    let all = refs(&|_| true);
+    let hidden = refs(&|layer| !layer.visible);
+    let off = if hidden.is_empty() {
+        String::new()
+    } else {
+        format!(" /OFF [{}]", hidden)
+    };
+    format!(
+        "<< /OCGs [{}] /D << /Name (Layers) /Order [{}]{} >> >>",
+        all, all, off
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hidden_layers_are_off_by_default() {
+        let layers = vec![
+            Layer {
+                name: "Grid".to_string(),
+                visible: false,
+                obj: 7,
+            },
+            Layer {
+                name: "Dimensions".to_string(),
+                visible: true,
+                obj: 9,
+            },
+        ];
+        assert_eq!(
+            oc_properties(&layers),
+            "<< /OCGs [7 0 R 9 0 R] /D << /Name (Layers) /Order [7 0 R 9 0 R] /OFF [7 0 R] >> >>"
+        );
+        let used = BTreeSet::from([LayerId(1)]);
+        assert_eq!(
+            properties_entry(&used, &layers).unwrap(),
+            "/Properties << /OC1 9 0 R >>"
+        );
+    }
+}
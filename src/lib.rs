@@ -15,12 +15,36 @@
 //!   - [x] Line
 //!   - [x] Circle (filled)
 //!   - [x] Rectangle (filled)
-//!   - [ ] Polygon
+//!   - [x] Polygon & Path (filled, optionally stroked; Bézier curves & multiple subpaths)
 //! - [ ] Text
 //! - [x] Color
 //! - [ ] Opacity
 //! - [x] Rotation & Anchor
+//! - [x] Even-Odd & Nonzero Fill Rules (`with_fill_rule(FillRule::EvenOdd)`, e.g. rings & polygons with holes)
+//! - [x] Path Boolean Operations (`Path::union`, `intersection`, `difference` & `xor`, e.g. merged outlines for stencils & laser cutting)
+//! - [x] Stroke-to-Outline Conversion & Path Offsetting (`Path::stroke_outline` with caps, joins & dashes, `Path::offset`, e.g. for cutting machines & engraving margins)
+//! - [x] Typed Errors (`shapdf::Error`) with Validation of Coordinates, Radii & Colors
+//! - [x] Reusable Symbols (Form XObjects)
+//! - [x] Custom Shapes via the `Drawable` Trait (`Generator::draw`, with inherited style & transforms)
+//! - [x] Bounding Boxes & Hit Testing (`Shape::bounds`, `Shape::contains`, `Generator::get_page_bounds`)
+//! - [x] Retained-Mode Document Model (`Document` → `Page` → `Node`: query, edit & reorder by z-index before export)
+//! - [x] Page Boxes, Rotation & Page Labels
+//! - [x] Pages Fitted to Their Content (`page fit margin=5mm`, `Generator::add_page_fit`)
+//! - [x] Top-Left Origin & Data Viewports (`Coordinates`, `page origin=top-left`, `page viewport=0,100,-1,1`)
+//! - [x] Layers (Optional Content Groups)
+//! - [x] Tagged PDF (Roles, Alternate Text & Structure Tree)
 //! - [x] PDF Stream Compression (feature `compress`)
+//! - [x] Object Streams & Cross-Reference Streams (feature `compress`, `Generator::set_object_streams`)
+//! - [x] Compact Number Output with Configurable Precision (`Generator::set_precision`)
+//! - [x] Batch Drawing (`Generator::circles`, `lines`, `rectangles`) without Redundant Graphics State Operators
+//! - [x] Reproducible Output (`Generator::set_reproducible`, uncompressed)
+//! - [x] Streaming Output to any `io::Write` Sink (`Generator::to_writer`)
+//! - [x] Random-Access Pages (`PageId`: draw on, insert, move & delete pages)
+//! - [x] Parallel Page Construction (`PageBuilder`) & Parallel Stream Compression
+//! - [x] PDF/A-2b Archival Conformance
+//! - [x] Document Metadata (Info dictionary & XMP)
+//! - [x] Bookmarks (Document Outline)
+//! - [x] Hyperlinks & Internal Links
 //! - [x] CLI for declarative scripts
 //! - [x] WebAssembly
 //! - [x] Python Bindings
@@ -39,23 +63,23 @@
 //!     generator
 //!         .circle(Mm(20.), Mm(20.), Mm(10.))
 //!         .with_color(NamedColor("blue"))
-//!         .draw();
+//!         .draw()?;
 //!     generator
 //!         .line(Pt(500.), Pt(600.), Pt(300.), Pt(400.))
 //!         .with_width(Mm(10.))
 //!         .with_cap_type(CapType::Round)
 //!         .with_color(NamedColor("red"))
-//!         .draw();
+//!         .draw()?;
 //!     generator.add_page_letter();
 //!     generator
 //!         .rectangle(Mm(80.), Mm(180.), Mm(50.), Mm(30.))
 //!         .with_anchor(Anchor::Center)
 //!         .with_angle(Degree(30.))
-//!         .draw();
+//!         .draw()?;
 //!     generator
 //!         .circle(Mm(80.), Mm(180.), Mm(1.))
 //!         .with_color(NamedColor("green"))
-//!         .draw();
+//!         .draw()?;
 //!     generator.add_page_a4();
 //!     generator.write_pdf()?;
 //!     println!("PDF generated successfully!");
@@ -80,17 +104,34 @@
 //!
 //! ### `.shapdf` Script Syntax
 //! - Lines are `command [args] [key=value ...]`; blank lines or those starting with `#`/`//` are ignored.
+//! - Values containing spaces can be wrapped in double quotes (e.g. `title="My Chart"`).
 //! - Supported commands:
 //!   - `page default|letter|letter-landscape|a4|a4-landscape`
 //!   - `page size <width> <height>` (accepts `mm`, `cm`, `in`, `pt`)
+//!   - `page fit [margin=<length>]` (the page is sized to everything drawn on it, plus the non-negative margin; e.g. for standalone figures)
 //!   - `set default_page_size <width> <height>`
 //!   - `set default_width <length>`
 //!   - `set default_color <color>` (named colors, `#RRGGBB`, `rgb(r,g,b)`, or `gray(v)`)
 //!   - `set default_cap butt|round|square`
 //!   - `set default_angle <value>` (`deg` default, or `rad`)
+//!   - `set default_fill_rule nonzero|evenodd`
+//!   - `set precision <n>` (decimal places of coordinates in the PDF, `0` to `9`, default `3`; restored by `pop_style` like the defaults)
+//!   - `push_style` / `pop_style` (save and restore the defaults set with `set default_*`; defaults only apply to the current document)
+//!   - `set conformance none|pdfa-2b` (PDF/A-2b adds XMP metadata, an sRGB output intent, and rejects non-conforming output)
+//!   - `set reproducible true|false` (output bytes only depend on the script: no `shapdf` version in the producer, and all compression is disabled, so large documents get bigger)
+//!   - `meta [title=...] [author=...] [subject=...] [keywords=...] [creator=...] [producer=...] [creation_date=YYYY-MM-DDTHH:MM:SSZ]`
 //!   - `line <x1> <y1> <x2> <y2> [width=...] [color=...] [cap=...]`
 //!   - `circle <x> <y> <radius> [color=...]`
 //! - `rectangle <x> <y> <width> <height> [color=...] [anchor=...] [angle=...]`
+//!   - `polygon <x>,<y> <x>,<y> <x>,<y> ... [color=...] [stroke=<color>] [width=...] [fill_rule=nonzero|evenodd] [anchor=...] [angle=...]`
+//!   - `path M <x>,<y> L <x>,<y> ... C <x1>,<y1> <x2>,<y2> <x>,<y> Z M ... [options]` (SVG-like segments with the options of `polygon`; each `M` starts a subpath, e.g. a hole with `fill_rule=evenodd`)
+//!   - `bookmark "<title>" [y=...] [level=<n>] [open=true|false]` (points at the current page; `y` defaults to the top, `level=2` nests under the previous level-1 bookmark)
+//! - `page` accepts `crop=`, `bleed=` and `trim=` boxes as `x,y,width,height`, `rotate=90|180|270`, and page labels with `label=decimal|roman|Roman|alpha|Alpha|none`, `label_prefix=...` and `label_start=<n>` (from 1).
+//! - `page` also accepts `origin=top-left` (y grows downwards) or `viewport=x1,x2,y1,y2`, which maps a data range onto the page or onto `viewport_box=x,y,width,height`; positions of all shapes on the page, and of bookmarks and links pointing to it, follow it, while widths and radii stay in lengths.
+//! - `layer "<name>" [visible=true|false]` starts a block of commands drawn on a layer that viewers can toggle; close it with `end`. Layers can be nested, and reusing a name adds to the same layer (keeping its visibility unless `visible=` is given; a conflicting value is an error).
+//! - `tag figure|formula|artifact [alt="..."]` starts a block of commands tagged as one element for accessibility (tagged PDF); close it with `end`.
+//! - Shapes accept `role=figure|formula|artifact` and `alt="..."` to tag them individually (alternate text alone implies a figure). When a script tags anything, untagged shapes are marked as artifacts.
+//! - Shapes accept `link=<uri>` (e.g. `link=https://example.com`) or `link=page:<n>` (one-based page number) to make their bounds clickable.
 //! - The first drawing command automatically inserts a default page if none was added.
 //!
 //! ### WebAssembly & Web Editor
@@ -132,10 +173,29 @@
 //!
 //! © 2025 [Teddy van Jerry](https://github.com/Teddy-van-Jerry) ([Wuqiong Zhao](https://wqzhao.org))
 
+mod boolean;
+mod conformance;
+mod content;
+mod document;
+mod drawable;
+mod error;
 mod generator;
+mod layer;
+mod link;
+mod metadata;
+mod number;
+mod outline;
+mod page;
+mod path;
 mod script;
 mod shapes;
+mod stroke;
+mod style;
+mod symbol;
+mod tag;
+mod transform;
 mod units;
+mod xref;
 
 #[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
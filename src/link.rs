@@ -0,0 +1,147 @@
+use crate::error::{self, Error};
+use crate::number::Num;
+use crate::transform::Transform;
+use crate::units::Length;
+
+/// Target of a link annotation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Link {
+    /// External URI (e.g. `https://example.com`).
+    Uri(String),
+    /// Page in the same document (zero-based index in the final page order) and optional
+    /// vertical position in points, in the coordinates of that page; finalizing fails with
+    /// [`Error::MissingPage`](crate::Error::MissingPage) if the page does not exist.
+    Page { page: usize, y: Option<f64> },
+}
+
+impl Link {
+    pub fn uri(uri: impl Into<String>) -> Self {
+        Link::Uri(uri.into())
+    }
+
+    /// Link to the zero-based `page`, keeping the current zoom and position.
+    pub fn page(page: usize) -> Self {
+        Link::Page { page, y: None }
+    }
+
+    /// Link to the zero-based `page`, scrolled to the vertical position `y` in the
+    /// [`Coordinates`](crate::Coordinates) of that page.
+    pub fn page_at(page: usize, y: impl Length) -> Self {
+        Link::Page {
+            page,
+            y: Some(y.to_points()),
+        }
+    }
+
+    /// Check that the target position is finite.
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        if let Link::Page { y: Some(y), .. } = self {
+            error::finite("link y", *y)?;
+        }
+        Ok(())
+    }
+}
+
+/// Clickable area of a page and its link target.
+///
+/// This struct is mostly internal; link areas are created by
+/// [`Shape::with_link`](crate::Shape::with_link) and [`Generator::link`](crate::Generator::link).
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkArea {
+    /// Lower-left and upper-right corners `[x1, y1, x2, y2]` in points.
+    pub rect: [f64; 4],
+    pub link: Link,
+}
+
+impl LinkArea {
+    /// Link annotation dictionary, resolving internal targets with the pages in document order.
+    pub(crate) fn annotation_dict(&self, pages: &[PageTarget], decimals: u8) -> String {
+        let [x1, y1, x2, y2] = self.rect.map(|value| Num(value, decimals));
+        let action = match &self.link {
+            Link::Uri(uri) => format!(" /A << /S /URI /URI {} >>", encode_uri(uri)),
+            Link::Page { page, y } => match pages.get(*page) {
+                Some(target) => format!(" /Dest {}", target.destination(*y, decimals)),
+                None => String::new(),
+            },
+        };
+        format!(
+            "<< /Type /Annot /Subtype /Link /Rect [{} {} {} {}] /Border [0 0 0] /F 4{} >>",
+            x1, y1, x2, y2, action
+        )
+    }
+}
+
+/// Object number and coordinate system of a page that links and bookmarks point to.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct PageTarget {
+    pub obj: usize,
+    /// Map from the coordinates of the page to PDF points.
+    pub coordinates: Transform,
+}
+
+impl PageTarget {
+    /// Destination array showing the page scrolled to `y` (in the coordinates of the page),
+    /// or at the current position.
+    pub(crate) fn destination(&self, y: Option<f64>, decimals: u8) -> String {
+        match y {
+            Some(y) => format!(
+                "[{} 0 R /XYZ null {} null]",
+                self.obj,
+                Num(self.coordinates.apply(0.0, y).1, decimals)
+            ),
+            None => format!("[{} 0 R /XYZ null null null]", self.obj),
+        }
+    }
+}
+
+/// Encode a URI as a PDF string, percent-encoding bytes outside printable ASCII.
+fn encode_uri(uri: &str) -> String {
+    let mut encoded = String::from("(");
+    for byte in uri.bytes() {
+        match byte {
+            b'(' | b')' | b'\\' => {
+                encoded.push('\\');
+                encoded.push(byte as char);
+            }
+            0x21..=0x7e => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded.push(')');
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn annotation_targets() {
+        let uri = LinkArea {
+            rect: [0.0, 0.0, 10.0, 20.0],
+            link: Link::uri("https://example.com/a b"),
+        };
+        assert!(uri
+            .annotation_dict(&[], 3)
+            .contains("/A << /S /URI /URI (https://example.com/a%20b) >>"));
+        let page = LinkArea {
+            rect: [0.0, 0.0, 10.0, 20.0],
+            link: Link::page_at(1, crate::Pt(50.0)),
+        };
+        let target = |obj| PageTarget {
+            obj,
+            coordinates: Transform::identity(),
+        };
+        assert!(page
+            .annotation_dict(&[target(3), target(5)], 3)
+            .contains("/Dest [5 0 R /XYZ null 50 null]"));
+        // Positions are mapped from the coordinates of the target page
+        let top_left = PageTarget {
+            obj: 5,
+            coordinates: crate::Coordinates::TopLeft.to_page((612.0, 792.0)),
+        };
+        assert!(page
+            .annotation_dict(&[target(3), top_left], 3)
+            .contains("/Dest [5 0 R /XYZ null 742 null]"));
+    }
+}
@@ -0,0 +1,401 @@
+use crate::conformance::{self, Conformance, ConformanceError};
+use std::{fmt, str::FromStr};
+
+/// Date and time used in document metadata.
+///
+/// # Example
+/// ```
+/// use shapdf::DateTime;
+/// let date: DateTime = "2025-03-14T15:09:26+08:00".parse().unwrap();
+/// assert_eq!(date.to_string(), "2025-03-14T15:09:26+08:00");
+/// ```
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    /// Offset from UTC in minutes.
+    pub offset_minutes: i16,
+}
+
+impl DateTime {
+    /// Create a UTC date time.
+    pub fn utc(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> Self {
+        Self {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            offset_minutes: 0,
+        }
+    }
+
+    /// Create a UTC date time from seconds since the Unix epoch.
+    pub fn from_unix_timestamp(secs: u64) -> Self {
+        let days = (secs / 86400) as i64;
+        let rem = secs % 86400;
+        // Civil from days (Howard Hinnant's algorithm)
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z - era * 146097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+        let year = (yoe + era * 400 + if month <= 2 { 1 } else { 0 }) as u16;
+        Self::utc(
+            year,
+            month,
+            day,
+            (rem / 3600) as u8,
+            (rem % 3600 / 60) as u8,
+            (rem % 60) as u8,
+        )
+    }
+
+    /// Current UTC date time from the system clock.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn now() -> Self {
+        let secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self::from_unix_timestamp(secs)
+    }
+
+    /// Format as a PDF date string, e.g. `D:20250314150926+08'00'`.
+    pub fn to_pdf_date(&self) -> String {
+        let offset = if self.offset_minutes == 0 {
+            "Z".to_string()
+        } else {
+            format!(
+                "{}{:02}'{:02}'",
+                if self.offset_minutes < 0 { '-' } else { '+' },
+                self.offset_minutes.unsigned_abs() / 60,
+                self.offset_minutes.unsigned_abs() % 60
+            )
+        };
+        format!(
+            "D:{:04}{:02}{:02}{:02}{:02}{:02}{}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second, offset
+        )
+    }
+}
+
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )?;
+        if self.offset_minutes == 0 {
+            write!(f, "Z")
+        } else {
+            write!(
+                f,
+                "{}{:02}:{:02}",
+                if self.offset_minutes < 0 { '-' } else { '+' },
+                self.offset_minutes.unsigned_abs() / 60,
+                self.offset_minutes.unsigned_abs() % 60
+            )
+        }
+    }
+}
+
+impl FromStr for DateTime {
+    type Err = String;
+
+    /// Parse an ISO 8601 date (`YYYY-MM-DD`) or date time (`YYYY-MM-DDTHH:MM[:SS][Z|±HH:MM]`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid date '{s}', expected YYYY-MM-DD[THH:MM[:SS][Z|+HH:MM]]");
+        let num = |part: &str| part.parse::<u16>().map_err(|_| invalid());
+        let small = |part: &str| u8::try_from(num(part)?).map_err(|_| invalid());
+        let (date, time) = match s.split_once(['T', ' ']) {
+            Some((date, time)) => (date, Some(time)),
+            None => (s, None),
+        };
+        let mut parts = date.split('-');
+        let (Some(year), Some(month), Some(day), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+        let mut result = Self::utc(num(year)?, small(month)?, small(day)?, 0, 0, 0);
+        if let Some(time) = time {
+            let (clock, offset) = if let Some(clock) = time.strip_suffix('Z') {
+                (clock, 0)
+            } else if let Some(idx) = time.rfind(['+', '-']) {
+                let sign = if time[idx..].starts_with('-') { -1 } else { 1 };
+                let (h, m) = time[idx + 1..]
+                    .split_once(':')
+                    .unwrap_or((&time[idx + 1..], "0"));
+                let (h, m) = (small(h)?, small(m)?);
+                if h > 23 || m > 59 {
+                    return Err(invalid());
+                }
+                let minutes = sign * (i32::from(h) * 60 + i32::from(m));
+                (&time[..idx], i16::try_from(minutes).map_err(|_| invalid())?)
+            } else {
+                (time, 0)
+            };
+            let mut fields = clock.split(':');
+            result.hour = small(fields.next().ok_or_else(invalid)?)?;
+            result.minute = small(fields.next().ok_or_else(invalid)?)?;
+            result.second = fields.next().map(small).transpose()?.unwrap_or(0);
+            result.offset_minutes = offset;
+        }
+        let valid = (1..=12).contains(&result.month)
+            && (1..=days_in_month(result.year, result.month)).contains(&result.day)
+            && result.hour < 24
+            && result.minute < 60
+            && result.second < 60;
+        if valid {
+            Ok(result)
+        } else {
+            Err(invalid())
+        }
+    }
+}
+
+/// Number of days of `month` (1 to 12) in the Gregorian calendar.
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Document information written to the `/Info` dictionary and the XMP metadata stream.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Metadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub keywords: Option<String>,
+    pub creator: Option<String>,
+    pub producer: Option<String>,
+    pub creation_date: Option<DateTime>,
+}
+
+impl Metadata {
+    /// Whether no metadata entry was set.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn producer(&self) -> String {
+        self.producer
+            .clone()
+            .unwrap_or_else(|| format!("shapdf {}", env!("CARGO_PKG_VERSION")))
+    }
+
+    fn text_entries(&self) -> [(&'static str, Option<&String>); 5] {
+        [
+            ("Title", self.title.as_ref()),
+            ("Author", self.author.as_ref()),
+            ("Subject", self.subject.as_ref()),
+            ("Keywords", self.keywords.as_ref()),
+            ("Creator", self.creator.as_ref()),
+        ]
+    }
+
+    /// Check the metadata against the string limits required by PDF/A.
+    pub(crate) fn check_conformance(
+        &self,
+        conformance: Conformance,
+    ) -> Result<(), ConformanceError> {
+        for (key, value) in self.text_entries() {
+            if let Some(value) = value {
+                conformance::check_string(conformance, &format!("/{key}"), value)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Document information dictionary.
+    pub(crate) fn info_dict(&self) -> String {
+        let mut dict = String::from("<<");
+        for (key, value) in self.text_entries() {
+            if let Some(value) = value {
+                dict.push_str(&format!(" /{} {}", key, encode_text_string(value)));
+            }
+        }
+        dict.push_str(&format!(
+            " /Producer {}",
+            encode_text_string(&self.producer())
+        ));
+        if let Some(date) = self.creation_date {
+            dict.push_str(&format!(" /CreationDate ({})", date.to_pdf_date()));
+        }
+        dict.push_str(" >>");
+        dict
+    }
+
+    /// XMP metadata packet mirroring the information dictionary.
+    pub(crate) fn xmp_packet(&self, conformance: Conformance) -> String {
+        let mut xmp = String::new();
+        xmp.push_str("<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n");
+        xmp.push_str("<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n");
+        xmp.push_str("<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n");
+
+        xmp.push_str(
+            "<rdf:Description rdf:about=\"\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n",
+        );
+        xmp.push_str("<dc:format>application/pdf</dc:format>\n");
+        if let Some(title) = &self.title {
+            xmp.push_str(&format!(
+                "<dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:title>\n",
+                escape_xml(title)
+            ));
+        }
+        if let Some(author) = &self.author {
+            xmp.push_str(&format!(
+                "<dc:creator><rdf:Seq><rdf:li>{}</rdf:li></rdf:Seq></dc:creator>\n",
+                escape_xml(author)
+            ));
+        }
+        if let Some(subject) = &self.subject {
+            xmp.push_str(&format!(
+                "<dc:description><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:description>\n",
+                escape_xml(subject)
+            ));
+        }
+        xmp.push_str("</rdf:Description>\n");
+
+        xmp.push_str(
+            "<rdf:Description rdf:about=\"\" xmlns:pdf=\"http://ns.adobe.com/pdf/1.3/\">\n",
+        );
+        if let Some(keywords) = &self.keywords {
+            xmp.push_str(&format!(
+                "<pdf:Keywords>{}</pdf:Keywords>\n",
+                escape_xml(keywords)
+            ));
+        }
+        xmp.push_str(&format!(
+            "<pdf:Producer>{}</pdf:Producer>\n",
+            escape_xml(&self.producer())
+        ));
+        xmp.push_str("</rdf:Description>\n");
+
+        if self.creator.is_some() || self.creation_date.is_some() {
+            xmp.push_str(
+                "<rdf:Description rdf:about=\"\" xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\">\n",
+            );
+            if let Some(creator) = &self.creator {
+                xmp.push_str(&format!(
+                    "<xmp:CreatorTool>{}</xmp:CreatorTool>\n",
+                    escape_xml(creator)
+                ));
+            }
+            if let Some(date) = self.creation_date {
+                xmp.push_str(&format!("<xmp:CreateDate>{}</xmp:CreateDate>\n", date));
+            }
+            xmp.push_str("</rdf:Description>\n");
+        }
+
+        if let Some((part, level)) = conformance.pdfa_part_and_level() {
+            xmp.push_str(
+                "<rdf:Description rdf:about=\"\" xmlns:pdfaid=\"http://www.aiim.org/pdfa/ns/id/\">\n",
+            );
+            xmp.push_str(&format!("<pdfaid:part>{}</pdfaid:part>\n", part));
+            xmp.push_str(&format!(
+                "<pdfaid:conformance>{}</pdfaid:conformance>\n",
+                level
+            ));
+            xmp.push_str("</rdf:Description>\n");
+        }
+
+        xmp.push_str("</rdf:RDF>\n");
+        xmp.push_str("</x:xmpmeta>\n");
+        xmp.push_str("<?xpacket end=\"w\"?>");
+        xmp
+    }
+}
+
+/// Encode a text string as a PDF literal string, or as UTF-16BE hex when it is not ASCII.
+pub(crate) fn encode_text_string(text: &str) -> String {
+    if text.is_ascii() {
+        let mut encoded = String::with_capacity(text.len() + 2);
+        encoded.push('(');
+        for ch in text.chars() {
+            match ch {
+                '(' | ')' | '\\' => {
+                    encoded.push('\\');
+                    encoded.push(ch);
+                }
+                '\n' => encoded.push_str("\\n"),
+                '\r' => encoded.push_str("\\r"),
+                _ => encoded.push(ch),
+            }
+        }
+        encoded.push(')');
+        encoded
+    } else {
+        let mut encoded = String::from("<FEFF");
+        for unit in text.encode_utf16() {
+            encoded.push_str(&format!("{:04X}", unit));
+        }
+        encoded.push('>');
+        encoded
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn date_time_formats() {
+        let date: DateTime = "2025-03-14T15:09:26-05:30".parse().unwrap();
+        assert_eq!(date.to_pdf_date(), "D:20250314150926-05'30'");
+        assert_eq!(
+            DateTime::from_unix_timestamp(0).to_string(),
+            "1970-01-01T00:00:00Z"
+        );
+        assert_eq!(
+            DateTime::from_unix_timestamp(1_700_000_000),
+            DateTime::utc(2023, 11, 14, 22, 13, 20)
+        );
+        assert!("2025-13-01".parse::<DateTime>().is_err());
+        assert!("2024-02-29".parse::<DateTime>().is_ok());
+        assert!("2000-02-29".parse::<DateTime>().is_ok());
+        // Out-of-range fields are rejected instead of wrapping or overflowing
+        for invalid in [
+            "2025-268-01",
+            "2025-02-29",
+            "2025-02-31",
+            "2025-04-31",
+            "1900-02-29",
+            "2025-01-01T256:00Z",
+            "2025-01-01T10:00+1100:00",
+            "2025-01-01T10:00+24:00",
+            "2025-01-01T10:00-05:60",
+        ] {
+            assert!(invalid.parse::<DateTime>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn text_string_encoding() {
+        assert_eq!(encode_text_string("a (b)"), "(a \\(b\\))");
+        assert_eq!(encode_text_string("é"), "<FEFF00E9>");
+    }
+}
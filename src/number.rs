@@ -0,0 +1,136 @@
+use std::fmt;
+
+/// Default number of decimal places of lengths written to the PDF.
+pub(crate) const DEFAULT_PRECISION: u8 = 3;
+/// Decimal places of color components, enough to distinguish 8-bit channels.
+pub(crate) const COLOR_PRECISION: u8 = 3;
+/// Extra decimal places of matrix coefficients, which scale the coordinates.
+const MATRIX_EXTRA_PRECISION: u8 = 3;
+pub(crate) const MAX_PRECISION: u8 = 9;
+const POWERS_OF_TEN: [u64; 10] = [
+    1,
+    10,
+    100,
+    1_000,
+    10_000,
+    100_000,
+    1_000_000,
+    10_000_000,
+    100_000_000,
+    1_000_000_000,
+];
+
+/// Append `value` rounded to `decimals` places, without trailing zeros
+/// (e.g. `28.346`, `-0.5` or `3`).
+///
+/// Values are validated before drawing; a non-finite value that slips through is
+/// written as `0`, as PDF has no syntax for it.
+pub(crate) fn write_number(out: &mut Vec<u8>, value: f64, decimals: u8) {
+    if !value.is_finite() {
+        out.push(b'0');
+        return;
+    }
+    let decimals = decimals.min(MAX_PRECISION) as usize;
+    let scale = POWERS_OF_TEN[decimals];
+    let scaled = (value.abs() * scale as f64).round();
+    if scaled >= u64::MAX as f64 {
+        // Out of the range of PDF numbers anyway
+        out.extend_from_slice(format!("{}", value.round()).as_bytes());
+        return;
+    }
+    let scaled = scaled as u64;
+    if scaled == 0 {
+        out.push(b'0');
+        return;
+    }
+    if value < 0.0 {
+        out.push(b'-');
+    }
+    write_integer(out, scaled / scale);
+    let (mut fraction, mut digits) = (scaled % scale, decimals);
+    if fraction == 0 {
+        return;
+    }
+    while fraction % 10 == 0 {
+        fraction /= 10;
+        digits -= 1;
+    }
+    out.push(b'.');
+    let start = out.len();
+    out.resize(start + digits, b'0');
+    for slot in out[start..].iter_mut().rev() {
+        *slot = b'0' + (fraction % 10) as u8;
+        fraction /= 10;
+    }
+}
+
+fn write_integer(out: &mut Vec<u8>, mut value: u64) {
+    let mut buf = [0u8; 20];
+    let mut pos = buf.len();
+    loop {
+        pos -= 1;
+        buf[pos] = b'0' + (value % 10) as u8;
+        value /= 10;
+        if value == 0 {
+            break;
+        }
+    }
+    out.extend_from_slice(&buf[pos..]);
+}
+
+/// Append a content stream operator with its operands, e.g. `10 20 m`.
+pub(crate) fn write_op(out: &mut Vec<u8>, operands: &[f64], operator: &str, decimals: u8) {
+    for operand in operands {
+        write_number(out, *operand, decimals);
+        out.push(b' ');
+    }
+    out.extend_from_slice(operator.as_bytes());
+    out.push(b'\n');
+}
+
+/// Append a `cm` operator, keeping more decimal places for the scaling coefficients.
+pub(crate) fn write_matrix(out: &mut Vec<u8>, matrix: [f64; 6], decimals: u8) {
+    for coefficient in &matrix[..4] {
+        write_number(
+            out,
+            *coefficient,
+            decimals.saturating_add(MATRIX_EXTRA_PRECISION),
+        );
+        out.push(b' ');
+    }
+    write_op(out, &matrix[4..], "cm", decimals);
+}
+
+/// Number displayed with at most the given decimal places, for dictionaries.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Num(pub f64, pub u8);
+
+impl fmt::Display for Num {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = Vec::with_capacity(24);
+        write_number(&mut out, self.0, self.1);
+        // Only ASCII digits, '-' and '.' are written
+        f.write_str(std::str::from_utf8(&out).map_err(|_| fmt::Error)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_are_rounded_without_trailing_zeros() {
+        let format = |value: f64, decimals: u8| Num(value, decimals).to_string();
+        assert_eq!(format(28.346456692913385, 3), "28.346");
+        assert_eq!(format(0.1 + 0.2, 4), "0.3");
+        assert_eq!(format(-0.5, 3), "-0.5");
+        assert_eq!(format(-0.0001, 3), "0");
+        assert_eq!(format(612.0, 3), "612");
+        assert_eq!(format(0.0625, 3), "0.063");
+        assert_eq!(format(1234.5, 0), "1235");
+        assert_eq!(format(0.05, 2), "0.05");
+        assert_eq!(format(f64::NAN, 3), "0");
+        assert_eq!(format(f64::NEG_INFINITY, 3), "0");
+        assert_eq!(format(1e20, 3), "100000000000000000000");
+    }
+}
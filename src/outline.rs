@@ -0,0 +1,145 @@
+use crate::link::PageTarget;
+use crate::metadata::encode_text_string;
+
+/// Handle to a bookmark in the document outline.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct BookmarkId(pub(crate) usize);
+
+/// An entry of the document outline (bookmark).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bookmark {
+    pub title: String,
+    /// Zero-based index of the destination page.
+    pub page: usize,
+    /// Vertical position (in points, in the coordinates of the destination page) scrolled to.
+    pub y: f64,
+    pub parent: Option<BookmarkId>,
+    /// Whether the children are expanded in the viewer.
+    pub open: bool,
+}
+
+/// Build the outline dictionaries, numbered consecutively from `first_obj`.
+///
+/// The first returned dictionary is the outline root, followed by one dictionary per bookmark.
+pub(crate) fn outline_objects(
+    bookmarks: &[Bookmark],
+    first_obj: usize,
+    pages: &[PageTarget],
+    decimals: u8,
+) -> Vec<String> {
+    let obj = |idx: usize| first_obj + 1 + idx;
+    // Children of each bookmark, then the top-level bookmarks, and positions among siblings
+    let roots = bookmarks.len();
+    let mut children = vec![Vec::new(); bookmarks.len() + 1];
+    let mut positions = vec![0; bookmarks.len()];
+    for (idx, bookmark) in bookmarks.iter().enumerate() {
+        let parent = bookmark.parent.map_or(roots, |p| p.0);
+        positions[idx] = children[parent].len();
+        children[parent].push(idx);
+    }
+
+    // Number of descendants visible when the item itself is shown (bottom-up).
+    let mut visible = vec![0usize; bookmarks.len()];
+    let mut total = vec![0usize; bookmarks.len()];
+    for idx in (0..bookmarks.len()).rev() {
+        for &child in &children[idx] {
+            total[idx] += 1 + total[child];
+            visible[idx] += 1 + if bookmarks[child].open {
+                visible[child]
+            } else {
+                0
+            };
+        }
+    }
+
+    let first_last = |kids: &[usize]| match (kids.first(), kids.last()) {
+        (Some(first), Some(last)) => {
+            format!(" /First {} 0 R /Last {} 0 R", obj(*first), obj(*last))
+        }
+        _ => String::new(),
+    };
+
+    let root_count: usize = children[roots]
+        .iter()
+        .map(|&idx| 1 + if bookmarks[idx].open { visible[idx] } else { 0 })
+        .sum();
+    let mut objects = vec![format!(
+        "<< /Type /Outlines{} /Count {} >>",
+        first_last(&children[roots]),
+        root_count
+    )];
+
+    for (idx, bookmark) in bookmarks.iter().enumerate() {
+        let siblings = &children[bookmark.parent.map_or(roots, |p| p.0)];
+        let pos = positions[idx];
+        let parent = bookmark.parent.map(|p| obj(p.0)).unwrap_or(first_obj);
+        let mut dict = format!(
+            "<< /Title {} /Parent {} 0 R",
+            encode_text_string(&bookmark.title),
+            parent
+        );
+        if pos > 0 {
+            dict.push_str(&format!(" /Prev {} 0 R", obj(siblings[pos - 1])));
+        }
+        if let Some(next) = siblings.get(pos + 1) {
+            dict.push_str(&format!(" /Next {} 0 R", obj(*next)));
+        }
+        let kids = &children[idx];
+        if !kids.is_empty() {
+            dict.push_str(&first_last(kids));
+            if bookmark.open {
+                dict.push_str(&format!(" /Count {}", visible[idx]));
+            } else {
+                dict.push_str(&format!(" /Count -{}", total[idx]));
+            }
+        }
+        if let Some(target) = pages.get(bookmark.page) {
+            dict.push_str(&format!(
+                " /Dest {}",
+                target.destination(Some(bookmark.y), decimals)
+            ));
+        }
+        dict.push_str(" >>");
+        objects.push(dict);
+    }
+    objects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bookmark(title: &str, parent: Option<usize>, open: bool) -> Bookmark {
+        Bookmark {
+            title: title.to_string(),
+            page: 0,
+            y: 100.0,
+            parent: parent.map(BookmarkId),
+            open,
+        }
+    }
+
+    #[test]
+    fn nested_outline_counts() {
+        let bookmarks = vec![
+            bookmark("Chapter 1", None, true),
+            bookmark("Section 1.1", Some(0), false),
+            bookmark("Detail", Some(1), true),
+            bookmark("Chapter 2", None, true),
+        ];
+        let page = PageTarget {
+            obj: 3,
+            coordinates: crate::Transform::identity(),
+        };
+        let objects = outline_objects(&bookmarks, 10, &[page], 3);
+        assert_eq!(objects.len(), 5);
+        assert_eq!(
+            objects[0],
+            "<< /Type /Outlines /First 11 0 R /Last 14 0 R /Count 3 >>"
+        );
+        assert!(objects[1].contains("/Next 14 0 R /First 12 0 R /Last 12 0 R /Count 1"));
+        assert!(objects[2].contains("/Parent 11 0 R /First 13 0 R /Last 13 0 R /Count -1"));
+        assert!(objects[4].contains("/Prev 11 0 R"));
+        assert!(objects[4].contains("/Dest [3 0 R /XYZ null 100 null]"));
+    }
+}
@@ -0,0 +1,826 @@
+use crate::content::ContentStream;
+use crate::drawable::{Canvas, Drawable};
+use crate::error::{self, Error};
+use crate::layer::LayerId;
+use crate::link::{Link, LinkArea};
+use crate::metadata::encode_text_string;
+use crate::number::Num;
+use crate::path::Path;
+use crate::shapes::{Shape, ShapeType};
+use crate::style::Style;
+use crate::symbol::SymbolId;
+use crate::transform::Transform;
+use crate::units::{Inch, Length, Mm};
+use std::collections::BTreeSet;
+use std::num::NonZeroU32;
+
+/// Handle to a page, returned when the page is added.
+///
+/// The handle stays valid when pages are inserted, moved or deleted.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PageId(pub(crate) usize);
+
+/// A page and the content drawn on it until it is written.
+#[derive(Debug, Default)]
+pub(crate) struct PageState {
+    pub obj: usize,
+    /// Object of the content stream, reserved with the page so that streamed and
+    /// in-memory output are numbered alike.
+    pub contents_obj: usize,
+    /// Width and height in points.
+    pub size: (f64, f64),
+    pub options: PageOptions,
+    pub content: ContentStream,
+    pub links: Vec<LinkArea>,
+    pub bounds: Option<[f64; 4]>,
+    pub symbols: BTreeSet<SymbolId>,
+    pub layers: BTreeSet<LayerId>,
+    /// Structure element of each MCID of the page.
+    pub mcids: Vec<usize>,
+    pub written: bool,
+    pub deleted: bool,
+}
+
+/// A page drawn independently of a [`Generator`](crate::Generator), e.g. on another thread.
+///
+/// Pages built in parallel are added to a document in order with
+/// [`Generator::add_pages`](crate::Generator::add_pages).
+/// Shapes are drawn with the builder's own [`Style`]; tags, symbols and layers
+/// are only available when drawing through the generator.
+///
+/// # Example
+/// ```
+/// use shapdf::*;
+/// let pages: Vec<PageBuilder> = std::thread::scope(|scope| {
+///     let handles: Vec<_> = (0..4)
+///         .map(|i| {
+///             scope.spawn(move || {
+///                 let mut page = PageBuilder::a4();
+///                 page.circle(Mm(20.), Mm(20.), Mm(i as f64 + 1.)).draw().unwrap();
+///                 page
+///             })
+///         })
+///         .collect();
+///     handles.into_iter().map(|h| h.join().unwrap()).collect()
+/// });
+/// let mut generator = Generator::new("output/parallel.pdf".into());
+/// generator.add_pages(pages);
+/// assert_eq!(generator.page_count(), 4);
+/// ```
+#[derive(Debug)]
+pub struct PageBuilder {
+    /// Width and height in points.
+    pub(crate) size: (f64, f64),
+    pub(crate) options: PageOptions,
+    pub(crate) style: Style,
+    pub(crate) content: ContentStream,
+    pub(crate) links: Vec<LinkArea>,
+    pub(crate) bounds: Option<[f64; 4]>,
+    /// First invalid link, reported when the page is added to a generator.
+    pub(crate) error: Option<Error>,
+}
+
+impl Clone for PageBuilder {
+    fn clone(&self) -> Self {
+        Self {
+            size: self.size,
+            options: self.options.clone(),
+            style: self.style,
+            content: self.content.clone(),
+            links: self.links.clone(),
+            bounds: self.bounds,
+            error: self.error.as_ref().map(Error::duplicate),
+        }
+    }
+}
+
+impl PageBuilder {
+    pub fn new<L: Length>(width: L, height: L) -> Self {
+        Self {
+            size: (width.to_points(), height.to_points()),
+            options: PageOptions::default(),
+            style: Style::default(),
+            content: ContentStream::new(),
+            links: Vec::new(),
+            bounds: None,
+            error: None,
+        }
+    }
+
+    pub fn a4() -> Self {
+        Self::new(Mm(210.0), Mm(297.0))
+    }
+
+    pub fn letter() -> Self {
+        Self::new(Inch(8.5), Inch(11.0))
+    }
+
+    /// Set the page boxes, rotation or page label.
+    pub fn with_options(mut self, options: PageOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Set the style used for options not set on a shape (e.g. [`Generator::get_style`](crate::Generator::get_style)).
+    pub fn with_style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn get_style(&self) -> Style {
+        self.style
+    }
+
+    pub fn set_style(&mut self, style: Style) {
+        self.style = style;
+    }
+
+    /// Bounding box `[x1, y1, x2, y2]` in PDF points of everything drawn on the page,
+    /// whatever its [`Coordinates`].
+    pub fn get_bounds(&self) -> Option<[f64; 4]> {
+        self.bounds
+    }
+
+    /// Map from the coordinates of the page to PDF points.
+    fn coordinates(&self) -> Transform {
+        self.options.coordinates.to_page(self.size)
+    }
+
+    fn shape(&mut self, enum_type: ShapeType, x: Vec<f64>, y: Vec<f64>) -> Shape<'_> {
+        let coordinates = self.coordinates();
+        Shape {
+            content_stream: Some(&mut self.content),
+            links: Some(&mut self.links),
+            bounds: Some(&mut self.bounds),
+            style: self.style,
+            coordinates,
+            enum_type,
+            x,
+            y,
+            ..Default::default()
+        }
+    }
+
+    pub fn line(
+        &mut self,
+        x1: impl Length,
+        y1: impl Length,
+        x2: impl Length,
+        y2: impl Length,
+    ) -> Shape<'_> {
+        self.shape(
+            ShapeType::Line,
+            vec![x1.to_points(), x2.to_points()],
+            vec![y1.to_points(), y2.to_points()],
+        )
+    }
+
+    pub fn circle(&mut self, x: impl Length, y: impl Length, radius: impl Length) -> Shape<'_> {
+        let mut shape = self.shape(ShapeType::Circle, vec![x.to_points()], vec![y.to_points()]);
+        shape.radius = Some(radius.to_points());
+        shape
+    }
+
+    pub fn rectangle(
+        &mut self,
+        x: impl Length,
+        y: impl Length,
+        width: impl Length,
+        height: impl Length,
+    ) -> Shape<'_> {
+        self.shape(
+            ShapeType::Rectangle,
+            vec![x.to_points(), width.to_points()],
+            vec![y.to_points(), height.to_points()],
+        )
+    }
+
+    /// Filled polygon through the points `(x, y)`.
+    pub fn polygon<L: Length>(&mut self, points: &[(L, L)]) -> Shape<'_> {
+        let mut shape = self.shape(ShapeType::Polygon, Vec::new(), Vec::new());
+        shape.path = Path::polygon(points);
+        shape
+    }
+
+    /// Filled path, possibly made of several subpaths (see [`Path`]).
+    pub fn path(&mut self, path: Path) -> Shape<'_> {
+        let mut shape = self.shape(ShapeType::Path, Vec::new(), Vec::new());
+        shape.path = path;
+        shape
+    }
+
+    /// Draw many lines `(x1, y1, x2, y2)` sharing the same options.
+    pub fn lines<L: Length>(&mut self, lines: &[(L, L, L, L)]) -> Shape<'_> {
+        let mut shape = self.shape(ShapeType::Line, Vec::new(), Vec::new());
+        shape.batch = lines
+            .iter()
+            .map(|(x1, y1, x2, y2)| {
+                [
+                    x1.to_points(),
+                    y1.to_points(),
+                    x2.to_points(),
+                    y2.to_points(),
+                ]
+            })
+            .collect();
+        shape
+    }
+
+    /// Draw many circles `(x, y, radius)` sharing the same options.
+    pub fn circles<L: Length>(&mut self, circles: &[(L, L, L)]) -> Shape<'_> {
+        let mut shape = self.shape(ShapeType::Circle, Vec::new(), Vec::new());
+        shape.batch = circles
+            .iter()
+            .map(|(x, y, radius)| [x.to_points(), y.to_points(), radius.to_points(), 0.0])
+            .collect();
+        shape
+    }
+
+    /// Draw many rectangles `(x, y, width, height)` sharing the same options.
+    pub fn rectangles<L: Length>(&mut self, rectangles: &[(L, L, L, L)]) -> Shape<'_> {
+        let mut shape = self.shape(ShapeType::Rectangle, Vec::new(), Vec::new());
+        shape.batch = rectangles
+            .iter()
+            .map(|(x, y, width, height)| {
+                [
+                    x.to_points(),
+                    y.to_points(),
+                    width.to_points(),
+                    height.to_points(),
+                ]
+            })
+            .collect();
+        shape
+    }
+
+    /// Draw a [`Drawable`] with the style of the page.
+    pub fn draw(&mut self, drawable: &(impl Drawable + ?Sized)) -> Result<(), Error> {
+        drawable.draw(&mut self.canvas())
+    }
+
+    /// Draw a [`Drawable`] with `transform` applied.
+    pub fn draw_transformed(
+        &mut self,
+        drawable: &(impl Drawable + ?Sized),
+        transform: Transform,
+    ) -> Result<(), Error> {
+        self.canvas()
+            .transformed(transform, |canvas| drawable.draw(canvas))
+    }
+
+    fn canvas(&mut self) -> Canvas<'_> {
+        let coordinates = self.coordinates();
+        Canvas::new(
+            &mut self.content,
+            &mut self.links,
+            &mut self.bounds,
+            None,
+            self.style,
+            coordinates,
+        )
+    }
+
+    /// Add a clickable link area on the page.
+    ///
+    /// An invalid area or link is reported by the generator the page is added to, as with
+    /// [`Generator::link`](crate::Generator::link).
+    pub fn link(
+        &mut self,
+        x: impl Length,
+        y: impl Length,
+        width: impl Length,
+        height: impl Length,
+        link: Link,
+    ) {
+        let (x, y) = (x.to_points(), y.to_points());
+        let rect = [x, y, x + width.to_points(), y + height.to_points()];
+        let checked = rect
+            .iter()
+            .try_for_each(|value| error::finite("link area", *value).map(|_| ()))
+            .and_then(|_| link.validate());
+        if let Err(err) = checked {
+            self.error.get_or_insert(err);
+            return;
+        }
+        self.links.push(LinkArea {
+            rect: self.coordinates().apply_rect(rect),
+            link,
+        });
+    }
+}
+
+/// Clockwise rotation of a page when displayed or printed (`/Rotate`).
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum PageRotation {
+    #[default]
+    None,
+    Clockwise90,
+    Rotate180,
+    Clockwise270,
+}
+
+impl PageRotation {
+    pub fn to_degrees(&self) -> u16 {
+        match self {
+            PageRotation::None => 0,
+            PageRotation::Clockwise90 => 90,
+            PageRotation::Rotate180 => 180,
+            PageRotation::Clockwise270 => 270,
+        }
+    }
+
+    /// Rotation from a multiple of 90 degrees (negative values rotate counterclockwise).
+    pub fn from_degrees(degrees: i32) -> Option<Self> {
+        match degrees.rem_euclid(360) {
+            0 => Some(PageRotation::None),
+            90 => Some(PageRotation::Clockwise90),
+            180 => Some(PageRotation::Rotate180),
+            270 => Some(PageRotation::Clockwise270),
+            _ => None,
+        }
+    }
+}
+
+/// Numbering style of a page label range.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum PageLabelStyle {
+    /// Arabic numerals (1, 2, 3, ...).
+    #[default]
+    Decimal,
+    /// Uppercase roman numerals (I, II, III, ...).
+    UpperRoman,
+    /// Lowercase roman numerals (i, ii, iii, ...).
+    LowerRoman,
+    /// Uppercase letters (A to Z, then AA to ZZ, ...).
+    UpperLetters,
+    /// Lowercase letters (a to z, then aa to zz, ...).
+    LowerLetters,
+    /// Prefix only, without a number.
+    None,
+}
+
+/// Label of the page range starting at a page (e.g. `iv` or `A-1`).
+///
+/// # Example
+/// ```
+/// use shapdf::{PageLabel, PageLabelStyle};
+/// let appendix = PageLabel::new(PageLabelStyle::Decimal).with_prefix("A-");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageLabel {
+    pub style: PageLabelStyle,
+    pub prefix: Option<String>,
+    /// Number of the first page in the range (PDF requires it to be at least 1).
+    pub start: NonZeroU32,
+}
+
+impl PageLabel {
+    pub fn new(style: PageLabelStyle) -> Self {
+        Self {
+            style,
+            prefix: None,
+            start: NonZeroU32::MIN,
+        }
+    }
+
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    pub fn with_start(mut self, start: NonZeroU32) -> Self {
+        self.start = start;
+        self
+    }
+
+    pub(crate) fn to_dict(&self) -> String {
+        let mut dict = String::from("<<");
+        match self.style {
+            PageLabelStyle::Decimal => dict.push_str(" /S /D"),
+            PageLabelStyle::UpperRoman => dict.push_str(" /S /R"),
+            PageLabelStyle::LowerRoman => dict.push_str(" /S /r"),
+            PageLabelStyle::UpperLetters => dict.push_str(" /S /A"),
+            PageLabelStyle::LowerLetters => dict.push_str(" /S /a"),
+            PageLabelStyle::None => {}
+        }
+        if let Some(prefix) = &self.prefix {
+            dict.push_str(&format!(" /P {}", encode_text_string(prefix)));
+        }
+        if self.start != NonZeroU32::MIN {
+            dict.push_str(&format!(" /St {}", self.start));
+        }
+        dict.push_str(" >>");
+        dict
+    }
+}
+
+/// Coordinate system of the shapes drawn on a page.
+///
+/// Coordinates set the positions of shapes (line ends, circle centers, rectangle corners,
+/// links and symbol origins), while line widths and radii stay in points. Anchors refer
+/// to the axes of the coordinate system (e.g. [`Anchor::South`](crate::Anchor::South)
+/// is towards decreasing `y`) and rotations turn from `+x` towards `+y`, so that with
+/// [`TopLeft`](Self::TopLeft) shapes are laid out as in SVG.
+///
+/// # Example
+/// ```
+/// use shapdf::*;
+/// let mut generator = Generator::new("output/plot.pdf".into());
+/// let plot = Coordinates::viewport((0., 100.), (-1., 1.))
+///     .with_rect(Mm(20.), Mm(20.), Mm(170.), Mm(100.));
+/// generator.add_page_with_options(Mm(210.), Mm(140.), PageOptions::new().with_coordinates(plot));
+/// let points: Vec<(Pt, Pt, Pt)> = (0..=100)
+///     .map(|x| (Pt(x as f64), Pt((x as f64 / 10.).sin()), Pt(1.)))
+///     .collect();
+/// generator.circles(&points).draw().unwrap();
+/// ```
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub enum Coordinates {
+    /// PDF default: origin at the bottom-left corner, `y` pointing up, in points.
+    #[default]
+    BottomLeft,
+    /// Origin at the top-left corner, `y` pointing down, in points (as in SVG).
+    TopLeft,
+    /// Data ranges `x = (x1, x2)` and `y = (y1, y2)` mapped onto the rectangle
+    /// `[x1, y1, x2, y2]` of the page in points (the whole page if `None`).
+    /// Empty or non-finite ranges make finalizing fail with [`Error::EmptyRange`]
+    /// or [`Error::NonFinite`].
+    Viewport {
+        x: (f64, f64),
+        y: (f64, f64),
+        rect: Option<[f64; 4]>,
+    },
+}
+
+impl Coordinates {
+    /// Map the data ranges `x` and `y` onto the whole page.
+    pub fn viewport(x: (f64, f64), y: (f64, f64)) -> Self {
+        Coordinates::Viewport { x, y, rect: None }
+    }
+
+    /// Map a viewport onto a rectangle of the page instead of the whole page.
+    pub fn with_rect(
+        self,
+        x: impl Length,
+        y: impl Length,
+        width: impl Length,
+        height: impl Length,
+    ) -> Self {
+        match self {
+            Coordinates::Viewport {
+                x: range_x,
+                y: range_y,
+                ..
+            } => Coordinates::Viewport {
+                x: range_x,
+                y: range_y,
+                rect: Some(to_rect(x, y, width, height)),
+            },
+            other => other,
+        }
+    }
+
+    /// Map from these coordinates to PDF points on a page of the given size.
+    pub(crate) fn to_page(self, (width, height): (f64, f64)) -> Transform {
+        match self {
+            Coordinates::BottomLeft => Transform::identity(),
+            Coordinates::TopLeft => Transform {
+                d: -1.0,
+                f: height,
+                ..Transform::identity()
+            },
+            Coordinates::Viewport { x, y, rect } => {
+                let [x1, y1, x2, y2] = rect.unwrap_or([0.0, 0.0, width, height]);
+                let (sx, sy) = ((x2 - x1) / (x.1 - x.0), (y2 - y1) / (y.1 - y.0));
+                Transform {
+                    a: sx,
+                    d: sy,
+                    e: x1 - x.0 * sx,
+                    f: y1 - y.0 * sy,
+                    ..Transform::identity()
+                }
+            }
+        }
+    }
+}
+
+/// Page boxes, rotation and label of a page.
+///
+/// Boxes are `[x1, y1, x2, y2]` rectangles in points, and default to the media box.
+///
+/// # Example
+/// ```
+/// use shapdf::*;
+/// let mut generator = Generator::new("output/print.pdf".into());
+/// let options = PageOptions::new()
+///     .with_bleed_box(Mm(0.), Mm(0.), Mm(216.), Mm(303.))
+///     .with_trim_box(Mm(3.), Mm(3.), Mm(210.), Mm(297.))
+///     .with_label(PageLabel::new(PageLabelStyle::LowerRoman));
+/// generator.add_page_with_options(Mm(216.), Mm(303.), options);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PageOptions {
+    pub crop_box: Option<[f64; 4]>,
+    pub bleed_box: Option<[f64; 4]>,
+    pub trim_box: Option<[f64; 4]>,
+    pub rotation: PageRotation,
+    /// Label starting a new page label range at this page.
+    pub label: Option<PageLabel>,
+    /// Margin in points around the content when the media box is fitted to it.
+    pub fit: Option<f64>,
+    /// Coordinate system of the shapes drawn on the page.
+    pub coordinates: Coordinates,
+}
+
+fn to_rect(x: impl Length, y: impl Length, width: impl Length, height: impl Length) -> [f64; 4] {
+    let (x, y) = (x.to_points(), y.to_points());
+    [x, y, x + width.to_points(), y + height.to_points()]
+}
+
+impl PageOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_crop_box(
+        mut self,
+        x: impl Length,
+        y: impl Length,
+        width: impl Length,
+        height: impl Length,
+    ) -> Self {
+        self.crop_box = Some(to_rect(x, y, width, height));
+        self
+    }
+
+    pub fn with_bleed_box(
+        mut self,
+        x: impl Length,
+        y: impl Length,
+        width: impl Length,
+        height: impl Length,
+    ) -> Self {
+        self.bleed_box = Some(to_rect(x, y, width, height));
+        self
+    }
+
+    pub fn with_trim_box(
+        mut self,
+        x: impl Length,
+        y: impl Length,
+        width: impl Length,
+        height: impl Length,
+    ) -> Self {
+        self.trim_box = Some(to_rect(x, y, width, height));
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: PageRotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_label(mut self, label: PageLabel) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    /// Set the coordinate system of the shapes drawn on the page.
+    pub fn with_coordinates(mut self, coordinates: Coordinates) -> Self {
+        self.coordinates = coordinates;
+        self
+    }
+
+    /// Fit the media box to everything drawn on the page, plus `margin` on each side.
+    ///
+    /// The box is computed when the page is written; pages without content keep their size.
+    /// The crop, bleed and trim boxes are clipped to the fitted box, and a negative or
+    /// non-finite margin makes finalizing fail with [`Error::Negative`] or
+    /// [`Error::NonFinite`].
+    pub fn with_fit(mut self, margin: impl Length) -> Self {
+        self.fit = Some(margin.to_points());
+        self
+    }
+
+    /// Media box `[x1, y1, x2, y2]` of a page of the given size with content `bounds`.
+    pub(crate) fn media_box(
+        &self,
+        (width, height): (f64, f64),
+        bounds: Option<[f64; 4]>,
+    ) -> [f64; 4] {
+        match (self.fit, bounds) {
+            (Some(margin), Some([x1, y1, x2, y2])) => {
+                [x1 - margin, y1 - margin, x2 + margin, y2 + margin]
+            }
+            _ => [0.0, 0.0, width, height],
+        }
+    }
+
+    /// Check that the page size and fit margin are finite and not negative, and the boxes
+    /// are finite.
+    pub(crate) fn validate(&self, (width, height): (f64, f64)) -> Result<(), Error> {
+        error::non_negative("page width", width)?;
+        error::non_negative("page height", height)?;
+        if let Some(margin) = self.fit {
+            error::non_negative("fit margin", margin)?;
+        }
+        for rect in [self.crop_box, self.bleed_box, self.trim_box]
+            .into_iter()
+            .flatten()
+        {
+            for value in rect {
+                error::finite("page box", value)?;
+            }
+        }
+        if let Coordinates::Viewport { x, y, rect } = self.coordinates {
+            for (name, (start, end)) in [("viewport x range", x), ("viewport y range", y)] {
+                if error::finite(name, start)? == error::finite(name, end)? {
+                    return Err(Error::EmptyRange(name));
+                }
+            }
+            for value in rect.into_iter().flatten() {
+                error::finite("viewport rect", value)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Page dictionary entries for the boxes, clipped to `media_box`, and rotation.
+    pub(crate) fn page_entries(&self, media_box: [f64; 4], decimals: u8) -> String {
+        let mut entries = String::new();
+        for (key, rect) in [
+            ("CropBox", self.crop_box),
+            ("BleedBox", self.bleed_box),
+            ("TrimBox", self.trim_box),
+        ] {
+            if let Some([x1, y1, x2, y2]) = rect {
+                let clip = |a: f64, b: f64, min: f64, max: f64| {
+                    let low = a.min(b).max(min).min(max);
+                    (low, a.max(b).min(max).max(low))
+                };
+                let (x1, x2) = clip(x1, x2, media_box[0], media_box[2]);
+                let (y1, y2) = clip(y1, y2, media_box[1], media_box[3]);
+                let [x1, y1, x2, y2] = [x1, y1, x2, y2].map(|value| Num(value, decimals));
+                entries.push_str(&format!(" /{} [{} {} {} {}]", key, x1, y1, x2, y2));
+            }
+        }
+        if self.rotation != PageRotation::None {
+            entries.push_str(&format!(" /Rotate {}", self.rotation.to_degrees()));
+        }
+        entries
+    }
+}
+
+/// Page labels number tree for the catalog, or `None` if no page has a label.
+pub(crate) fn page_labels_dict<'a>(
+    labels: impl Iterator<Item = Option<&'a PageLabel>>,
+) -> Option<String> {
+    let ranges: Vec<(usize, &PageLabel)> = labels
+        .enumerate()
+        .filter_map(|(idx, label)| label.map(|label| (idx, label)))
+        .collect();
+    if ranges.is_empty() {
+        return None;
+    }
+    let mut nums = String::new();
+    if ranges[0].0 != 0 {
+        // The first range must start at the first page.
+        nums.push_str("0 << /S /D >> ");
+    }
+    for (idx, label) in ranges {
+        nums.push_str(&format!("{} {} ", idx, label.to_dict()));
+    }
+    Some(format!("<< /Nums [{}] >>", nums.trim_end()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::{Degree, Pt, Rgb};
+
+    struct Sensor;
+
+    impl Drawable for Sensor {
+        fn draw(&self, canvas: &mut crate::drawable::Canvas<'_>) -> Result<(), Error> {
+            canvas.line(Pt(0.), Pt(0.), Pt(5.), Pt(5.)).draw()
+        }
+    }
+
+    #[test]
+    fn page_labels_number_tree() {
+        let roman = PageLabel::new(PageLabelStyle::LowerRoman);
+        let appendix = PageLabel::new(PageLabelStyle::Decimal).with_prefix("A-");
+        let labels = [None, Some(&roman), None, Some(&appendix)];
+        assert_eq!(
+            page_labels_dict(labels.into_iter()).unwrap(),
+            "<< /Nums [0 << /S /D >> 1 << /S /r >> 3 << /S /D /P (A-) >>] >>"
+        );
+        assert_eq!(page_labels_dict([None, None].into_iter()), None);
+        assert_eq!(
+            PageRotation::from_degrees(-90),
+            Some(PageRotation::Clockwise270)
+        );
+        assert_eq!(PageRotation::from_degrees(45), None);
+    }
+
+    #[test]
+    fn content_numbers_use_the_style_precision() {
+        let mut page = PageBuilder::a4();
+        page.line(Mm(10.), Mm(10.), Inch(1.), Inch(1.))
+            .with_color(Rgb(0.1 + 0.2, 0.5, 1.))
+            .draw()
+            .unwrap();
+        let style = Style {
+            precision: 1,
+            ..Style::default()
+        };
+        page.set_style(style);
+        page.circle(Mm(10.), Mm(10.), Mm(1.)).draw().unwrap();
+        assert_eq!(
+            String::from_utf8(page.content.take_bytes()).unwrap(),
+            "0.3 0.5 1 RG\n1 w\n0 J\n28.346 28.346 m\n72 72 l\nS\n\
+             0 0 0 RG\n5.7 w\n1 J\n28.3 28.3 m\n28.3 28.3 l\nS\n"
+        );
+    }
+
+    #[test]
+    fn batches_share_the_graphics_state() {
+        let mut page = PageBuilder::letter();
+        page.circles(&[(Pt(1.), Pt(1.), Pt(1.)), (Pt(2.), Pt(3.), Pt(1.))])
+            .draw()
+            .unwrap();
+        page.rectangles(&[(Pt(0.), Pt(0.), Pt(4.), Pt(2.)); 2])
+            .with_link(Link::uri("https://example.com"))
+            .draw()
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(page.content.take_bytes()).unwrap(),
+            "0 0 0 RG\n2 w\n1 J\n1 1 m\n1 1 l\nS\n2 3 m\n2 3 l\nS\n\
+             0 0 0 rg\n0 0 4 2 re f\n0 0 4 2 re f\n"
+        );
+        assert_eq!(page.links.len(), 2);
+        assert_eq!(page.bounds, Some([0., 0., 4., 4.]));
+    }
+
+    #[test]
+    fn top_left_pages_flip_shapes_and_transforms() {
+        let options = PageOptions::new().with_coordinates(Coordinates::TopLeft);
+        let mut page = PageBuilder::new(Pt(100.), Pt(100.)).with_options(options);
+        page.rectangle(Pt(10.), Pt(10.), Pt(20.), Pt(5.))
+            .with_angle(Degree(90.))
+            .draw()
+            .unwrap();
+        page.draw_transformed(
+            &Sensor,
+            crate::transform::Transform::translate(Pt(10.), Pt(20.)),
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(page.content.take_bytes()).unwrap(),
+            "0 0 0 rg\nq\n0 -1 1 0 -80 100 cm\n10 90 20 -5 re f\nQ\n\
+             q\n1 0 0 1 10 -20 cm\n0 0 0 RG\n1 w\n0 J\n0 100 m\n5 95 l\nS\nQ\n"
+        );
+        // The rotated rectangle spans y = 70..90 and the caps of the line stick out past x = 15
+        let [x1, y1, x2, y2] = page.get_bounds().unwrap();
+        assert_eq!([x1, y1, y2], [5., 70., 90.]);
+        assert!((x2 - (15. + 0.5 / 2f64.sqrt())).abs() < 1e-9);
+    }
+
+    #[test]
+    fn viewports_need_finite_non_empty_ranges() {
+        let size = (100., 100.);
+        let options = |coordinates| PageOptions::new().with_coordinates(coordinates);
+        assert!(options(Coordinates::viewport((0., 10.), (-1., 1.)))
+            .validate(size)
+            .is_ok());
+        assert!(matches!(
+            options(Coordinates::viewport((0., 10.), (1., 1.))).validate(size),
+            Err(Error::EmptyRange("viewport y range"))
+        ));
+        assert!(matches!(
+            options(Coordinates::viewport((0., f64::NAN), (0., 1.))).validate(size),
+            Err(Error::NonFinite {
+                name: "viewport x range",
+                ..
+            })
+        ));
+        let rect = Coordinates::viewport((0., 1.), (0., 1.)).with_rect(
+            Pt(0.),
+            Pt(0.),
+            Pt(f64::INFINITY),
+            Pt(1.),
+        );
+        assert!(options(rect).validate(size).is_err());
+
+        let mut generator = crate::Generator::new(std::path::PathBuf::new());
+        generator.add_page_with_options(
+            Pt(100.),
+            Pt(100.),
+            options(Coordinates::viewport((5., 5.), (0., 1.))),
+        );
+        assert!(matches!(
+            generator.to_pdf_bytes(),
+            Err(Error::EmptyRange("viewport x range"))
+        ));
+    }
+}
@@ -0,0 +1,422 @@
+use crate::content::ContentStream;
+use crate::error::{self, Error};
+use crate::transform::Transform;
+use crate::units::{Length, Pt};
+
+/// Largest distance in points between a curve and the lines approximating it.
+pub(crate) const FLATTEN_TOLERANCE: f64 = 0.01;
+
+/// Magic number to approximate a quarter circle with a cubic Bézier curve.
+const KAPPA: f64 = 0.552_284_749_830_793_4;
+
+/// Rule deciding which of the areas enclosed by a [`Path`] are filled.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum FillRule {
+    /// Fill the areas the outline winds around a nonzero number of times, e.g. holes
+    /// are only left empty if they run in the opposite direction (PDF `f`).
+    #[default]
+    NonZero,
+    /// Fill the areas enclosed by an odd number of subpaths, e.g. holes are left empty
+    /// whatever their direction (PDF `f*`).
+    EvenOdd,
+}
+
+impl FillRule {
+    /// Whether a point around which the outline winds `winding` times is filled.
+    pub(crate) fn is_inside(&self, winding: i32) -> bool {
+        match self {
+            FillRule::NonZero => winding != 0,
+            FillRule::EvenOdd => winding % 2 != 0,
+        }
+    }
+}
+
+/// Segment of a [`Path`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Segment {
+    /// Start a new subpath.
+    MoveTo(f64, f64),
+    LineTo(f64, f64),
+    /// Cubic Bézier curve with two control points, then the end point.
+    CurveTo(f64, f64, f64, f64, f64, f64),
+    /// Close the current subpath with a straight line to its start.
+    Close,
+}
+
+/// Outline made of straight lines and cubic Bézier curves, in the coordinates of the page.
+///
+/// A path can hold several subpaths, e.g. a ring made of two circles or a map polygon
+/// with lakes; the [`FillRule`] decides which of the enclosed areas are filled.
+///
+/// # Example
+/// ```
+/// use shapdf::*;
+/// let mut generator = Generator::new("output/ring.pdf".into());
+/// generator.add_page();
+/// let ring = Path::circle(Mm(50.), Mm(50.), Mm(20.))
+///     .append(Path::circle(Mm(50.), Mm(50.), Mm(10.)));
+/// generator
+///     .path(ring)
+///     .with_fill_rule(FillRule::EvenOdd)
+///     .draw()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Path {
+    segments: Vec<Segment>,
+}
+
+/// Subpath flattened to a polyline.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Polyline {
+    pub(crate) points: Vec<(f64, f64)>,
+    pub(crate) closed: bool,
+}
+
+impl Path {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Closed polygon through the points.
+    pub fn polygon<L: Length>(points: &[(L, L)]) -> Self {
+        let mut path = Self::new();
+        for (i, (x, y)) in points.iter().enumerate() {
+            path = match i {
+                0 => path.move_to(*x, *y),
+                _ => path.line_to(*x, *y),
+            };
+        }
+        if points.is_empty() {
+            path
+        } else {
+            path.close()
+        }
+    }
+
+    /// Rectangle with its south west corner at `(x, y)`, running counterclockwise.
+    pub fn rectangle(
+        x: impl Length,
+        y: impl Length,
+        width: impl Length,
+        height: impl Length,
+    ) -> Self {
+        let (x, y) = (x.to_points(), y.to_points());
+        let (x2, y2) = (x + width.to_points(), y + height.to_points());
+        Self::polygon(&[
+            (Pt(x), Pt(y)),
+            (Pt(x2), Pt(y)),
+            (Pt(x2), Pt(y2)),
+            (Pt(x), Pt(y2)),
+        ])
+    }
+
+    /// Circle made of four Bézier curves, running counterclockwise.
+    pub fn circle(x: impl Length, y: impl Length, radius: impl Length) -> Self {
+        let (x, y, r) = (x.to_points(), y.to_points(), radius.to_points());
+        let k = KAPPA * r;
+        let segments = vec![
+            Segment::MoveTo(x + r, y),
+            Segment::CurveTo(x + r, y + k, x + k, y + r, x, y + r),
+            Segment::CurveTo(x - k, y + r, x - r, y + k, x - r, y),
+            Segment::CurveTo(x - r, y - k, x - k, y - r, x, y - r),
+            Segment::CurveTo(x + k, y - r, x + r, y - k, x + r, y),
+            Segment::Close,
+        ];
+        Self { segments }
+    }
+
+    /// Start a new subpath at `(x, y)`.
+    pub fn move_to(mut self, x: impl Length, y: impl Length) -> Self {
+        self.segments
+            .push(Segment::MoveTo(x.to_points(), y.to_points()));
+        self
+    }
+
+    /// Add a straight line to `(x, y)`, starting the path there if it is empty.
+    pub fn line_to(mut self, x: impl Length, y: impl Length) -> Self {
+        let (x, y) = (x.to_points(), y.to_points());
+        self.ensure_current_point(x, y);
+        self.segments.push(Segment::LineTo(x, y));
+        self
+    }
+
+    /// Add a cubic Bézier curve through the control points `(x1, y1)` and `(x2, y2)`
+    /// to `(x, y)`, starting the path at `(x1, y1)` if it is empty.
+    pub fn curve_to(
+        mut self,
+        x1: impl Length,
+        y1: impl Length,
+        x2: impl Length,
+        y2: impl Length,
+        x: impl Length,
+        y: impl Length,
+    ) -> Self {
+        let (x1, y1) = (x1.to_points(), y1.to_points());
+        self.ensure_current_point(x1, y1);
+        self.segments.push(Segment::CurveTo(
+            x1,
+            y1,
+            x2.to_points(),
+            y2.to_points(),
+            x.to_points(),
+            y.to_points(),
+        ));
+        self
+    }
+
+    /// Close the current subpath with a straight line to its start.
+    pub fn close(mut self) -> Self {
+        if !matches!(self.segments.last(), None | Some(Segment::Close)) {
+            self.segments.push(Segment::Close);
+        }
+        self
+    }
+
+    /// Add the subpaths of `other`, e.g. the holes of a shape.
+    pub fn append(mut self, other: Path) -> Self {
+        self.segments.extend(other.segments);
+        self
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// The same path with `transform` applied to all of its points.
+    pub fn transformed(&self, transform: &Transform) -> Path {
+        let t = transform;
+        let segments = self
+            .segments
+            .iter()
+            .map(|segment| match *segment {
+                Segment::MoveTo(x, y) => {
+                    let (x, y) = t.apply(x, y);
+                    Segment::MoveTo(x, y)
+                }
+                Segment::LineTo(x, y) => {
+                    let (x, y) = t.apply(x, y);
+                    Segment::LineTo(x, y)
+                }
+                Segment::CurveTo(x1, y1, x2, y2, x, y) => {
+                    let (x1, y1) = t.apply(x1, y1);
+                    let (x2, y2) = t.apply(x2, y2);
+                    let (x, y) = t.apply(x, y);
+                    Segment::CurveTo(x1, y1, x2, y2, x, y)
+                }
+                Segment::Close => Segment::Close,
+            })
+            .collect();
+        Path { segments }
+    }
+
+    /// Start a subpath if a new segment has no current point: at `(x, y)` for an
+    /// empty path, or at the start of the subpath closed last.
+    fn ensure_current_point(&mut self, x: f64, y: f64) {
+        match self.segments.last() {
+            None => self.segments.push(Segment::MoveTo(x, y)),
+            Some(Segment::Close) => {
+                let start = self
+                    .segments
+                    .iter()
+                    .rev()
+                    .find_map(|segment| match segment {
+                        Segment::MoveTo(x, y) => Some((*x, *y)),
+                        _ => None,
+                    });
+                if let Some((x, y)) = start {
+                    self.segments.push(Segment::MoveTo(x, y));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Check that the path has segments and that its coordinates are finite.
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        if self.segments.is_empty() {
+            return Err(Error::MissingGeometry("path"));
+        }
+        for segment in &self.segments {
+            let points: &[f64] = match segment {
+                Segment::MoveTo(x, y) | Segment::LineTo(x, y) => &[*x, *y],
+                Segment::CurveTo(x1, y1, x2, y2, x, y) => &[*x1, *y1, *x2, *y2, *x, *y],
+                Segment::Close => &[],
+            };
+            for pair in points.chunks(2) {
+                error::finite("x", pair[0])?;
+                error::finite("y", pair[1])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Append the path construction operators (`m`, `l`, `c` and `h`).
+    pub(crate) fn write(&self, content: &mut ContentStream, decimals: u8) {
+        for segment in &self.segments {
+            match *segment {
+                Segment::MoveTo(x, y) => content.op(&[x, y], "m", decimals),
+                Segment::LineTo(x, y) => content.op(&[x, y], "l", decimals),
+                Segment::CurveTo(x1, y1, x2, y2, x, y) => {
+                    content.op(&[x1, y1, x2, y2, x, y], "c", decimals)
+                }
+                Segment::Close => content.op(&[], "h", decimals),
+            }
+        }
+    }
+
+    /// Subpaths approximated by polylines within `tolerance` points.
+    pub(crate) fn flatten(&self, tolerance: f64) -> Vec<Polyline> {
+        let mut polylines = Vec::new();
+        let mut current = Polyline {
+            points: Vec::new(),
+            closed: false,
+        };
+        for segment in &self.segments {
+            match *segment {
+                Segment::MoveTo(x, y) => {
+                    let done = std::mem::replace(
+                        &mut current,
+                        Polyline {
+                            points: vec![(x, y)],
+                            closed: false,
+                        },
+                    );
+                    if !done.points.is_empty() {
+                        polylines.push(done);
+                    }
+                }
+                Segment::LineTo(x, y) => current.points.push((x, y)),
+                Segment::CurveTo(x1, y1, x2, y2, x, y) => {
+                    let p0 = current.points.last().copied().unwrap_or((x1, y1));
+                    flatten_curve(
+                        p0,
+                        (x1, y1),
+                        (x2, y2),
+                        (x, y),
+                        tolerance,
+                        &mut current.points,
+                    );
+                }
+                Segment::Close => current.closed = true,
+            }
+        }
+        if !current.points.is_empty() {
+            polylines.push(current);
+        }
+        polylines
+    }
+}
+
+/// Append the points approximating a cubic Bézier curve from `p0` (excluded).
+fn flatten_curve(
+    p0: (f64, f64),
+    p1: (f64, f64),
+    p2: (f64, f64),
+    p3: (f64, f64),
+    tolerance: f64,
+    points: &mut Vec<(f64, f64)>,
+) {
+    // Wang's formula: number of lines keeping the approximation within the tolerance
+    let dd = |a: (f64, f64), b: (f64, f64), c: (f64, f64)| {
+        (a.0 - 2.0 * b.0 + c.0).hypot(a.1 - 2.0 * b.1 + c.1)
+    };
+    let m = dd(p0, p1, p2).max(dd(p1, p2, p3));
+    let n = ((0.75 * m / tolerance).sqrt().ceil() as usize).clamp(1, 1000);
+    for i in 1..=n {
+        let t = i as f64 / n as f64;
+        let u = 1.0 - t;
+        let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+        points.push((
+            a * p0.0 + b * p1.0 + c * p2.0 + d * p3.0,
+            a * p0.1 + b * p1.1 + c * p2.1 + d * p3.1,
+        ));
+    }
+}
+
+/// Number of times the polylines, each closed implicitly, wind counterclockwise around `(px, py)`.
+pub(crate) fn winding(polylines: &[Polyline], (px, py): (f64, f64)) -> i32 {
+    let mut winding = 0;
+    for polyline in polylines {
+        let points = &polyline.points;
+        for (i, &(x1, y1)) in points.iter().enumerate() {
+            let (x2, y2) = points[(i + 1) % points.len()];
+            // Side of the point relative to the edge
+            let side = (x2 - x1) * (py - y1) - (px - x1) * (y2 - y1);
+            if y1 <= py && y2 > py && side > 0.0 {
+                winding += 1;
+            } else if y1 > py && y2 <= py && side < 0.0 {
+                winding -= 1;
+            }
+        }
+    }
+    winding
+}
+
+/// Area enclosed by a path, counting holes negatively.
+#[cfg(test)]
+pub(crate) fn area(path: &Path) -> f64 {
+    path.flatten(FLATTEN_TOLERANCE)
+        .iter()
+        .map(|polyline| {
+            let points = &polyline.points;
+            (0..points.len())
+                .map(|i| {
+                    let ((x1, y1), (x2, y2)) = (points[i], points[(i + 1) % points.len()]);
+                    x1 * y2 - x2 * y1
+                })
+                .sum::<f64>()
+                / 2.0
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_rules_decide_the_holes() {
+        let outer = Path::rectangle(Pt(0.), Pt(0.), Pt(10.), Pt(10.));
+        let hole = Path::rectangle(Pt(3.), Pt(3.), Pt(4.), Pt(4.));
+        let ring = outer.append(hole).flatten(FLATTEN_TOLERANCE);
+        assert_eq!(winding(&ring, (5., 5.)), 2);
+        assert_eq!(winding(&ring, (1., 5.)), 1);
+        assert_eq!(winding(&ring, (11., 5.)), 0);
+        assert!(FillRule::NonZero.is_inside(2));
+        assert!(!FillRule::EvenOdd.is_inside(2));
+        assert!(FillRule::EvenOdd.is_inside(-1));
+
+        let circle = Path::circle(Pt(0.), Pt(0.), Pt(10.)).flatten(FLATTEN_TOLERANCE);
+        assert_eq!(circle.len(), 1);
+        assert!(circle[0].closed);
+        assert!(circle[0]
+            .points
+            .iter()
+            .all(|(x, y)| (x.hypot(*y) - 10.).abs() < 0.03));
+    }
+
+    #[test]
+    fn segments_without_a_current_point_start_a_subpath() {
+        let path = Path::new()
+            .line_to(Pt(1.), Pt(2.))
+            .close()
+            .line_to(Pt(3.), Pt(4.));
+        assert_eq!(
+            path.segments(),
+            [
+                Segment::MoveTo(1., 2.),
+                Segment::LineTo(1., 2.),
+                Segment::Close,
+                Segment::MoveTo(1., 2.),
+                Segment::LineTo(3., 4.),
+            ]
+        );
+        assert!(Path::new().validate().is_err());
+        assert!(Path::polygon(&[(Pt(0.), Pt(f64::NAN))]).validate().is_err());
+    }
+}
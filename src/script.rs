@@ -1,9 +1,16 @@
 use crate::{
+    conformance::Conformance,
     generator::Generator,
+    link::Link,
+    metadata::DateTime,
+    outline::BookmarkId,
+    page::{Coordinates, PageLabel, PageLabelStyle, PageOptions, PageRotation},
+    path::{FillRule, Path},
     shapes::{Anchor, CapType, Shape},
-    units::{Degree, Gray, Length, Mm, Pt, Radian, Rgb, RGB},
+    tag::{Role, Tag},
+    units::{Color, Degree, Gray, Inch, Length, Mm, Pt, Radian, Rgb, RGB},
 };
-use std::{error::Error, fmt, path::PathBuf};
+use std::{error::Error, fmt, io::Write, num::NonZeroU32, path::PathBuf};
 
 #[derive(Debug, Clone)]
 pub struct Instruction {
@@ -13,10 +20,11 @@ pub struct Instruction {
 
 #[derive(Debug, Clone)]
 pub enum InstructionKind {
-    AddPage(PageKind),
+    AddPage(PageKind, PageOptions),
     DrawLine(LineSpec),
     DrawCircle(CircleSpec),
     DrawRectangle(RectSpec),
+    DrawPath(PathSpec),
     SetDefaultPageSize {
         width: LengthValue,
         height: LengthValue,
@@ -25,6 +33,16 @@ pub enum InstructionKind {
     SetDefaultColor(ColorValue),
     SetDefaultCapType(CapType),
     SetDefaultAngle(AngleValue),
+    SetDefaultFillRule(FillRule),
+    SetPrecision(u8),
+    PushStyle,
+    PopStyle,
+    SetConformance(Conformance),
+    SetReproducible(bool),
+    SetMetadata(MetaSpec),
+    AddBookmark(BookmarkSpec),
+    Layer(LayerSpec),
+    Tag(TagSpec),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -34,6 +52,8 @@ pub enum PageKind {
     LetterLandscape,
     A4,
     A4Landscape,
+    /// Default size, with the media box fitted to the content.
+    Fit,
     Custom {
         width: LengthValue,
         height: LengthValue,
@@ -49,6 +69,8 @@ pub struct LineSpec {
     pub width: Option<LengthValue>,
     pub color: Option<ColorValue>,
     pub cap: Option<CapType>,
+    pub link: Option<Link>,
+    pub tag: Option<Tag>,
 }
 
 #[derive(Debug, Clone)]
@@ -57,6 +79,8 @@ pub struct CircleSpec {
     pub y: LengthValue,
     pub radius: LengthValue,
     pub color: Option<ColorValue>,
+    pub link: Option<Link>,
+    pub tag: Option<Tag>,
 }
 
 #[derive(Debug, Clone)]
@@ -68,6 +92,59 @@ pub struct RectSpec {
     pub color: Option<ColorValue>,
     pub anchor: Option<Anchor>,
     pub angle: Option<AngleValue>,
+    pub link: Option<Link>,
+    pub tag: Option<Tag>,
+}
+
+/// Polygon or path, in points.
+#[derive(Debug, Clone)]
+pub struct PathSpec {
+    pub path: Path,
+    pub width: Option<LengthValue>,
+    pub color: Option<ColorValue>,
+    pub stroke: Option<ColorValue>,
+    pub cap: Option<CapType>,
+    pub fill_rule: Option<FillRule>,
+    pub anchor: Option<Anchor>,
+    pub angle: Option<AngleValue>,
+    pub link: Option<Link>,
+    pub tag: Option<Tag>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MetaSpec {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub keywords: Option<String>,
+    pub creator: Option<String>,
+    pub producer: Option<String>,
+    pub creation_date: Option<DateTime>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BookmarkSpec {
+    pub title: String,
+    pub y: Option<LengthValue>,
+    /// Nesting level, starting at 1 for top-level bookmarks.
+    pub level: usize,
+    pub open: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct LayerSpec {
+    pub name: String,
+    /// Visibility, or that of the existing layer with the same name (visible if new).
+    pub visible: Option<bool>,
+    /// Instructions between `layer` and the matching `end`.
+    pub body: Vec<Instruction>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TagSpec {
+    pub tag: Tag,
+    /// Instructions between `tag` and the matching `end`.
+    pub body: Vec<Instruction>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -184,6 +261,8 @@ impl Error for ExecutionError {}
 
 pub fn parse_script(input: &str) -> Result<Vec<Instruction>, ParseError> {
     let mut instructions = Vec::new();
+    // Blocks being parsed, with the instructions preceding each block
+    let mut blocks: Vec<(usize, InstructionKind, Vec<Instruction>)> = Vec::new();
 
     for (idx, raw_line) in input.lines().enumerate() {
         let line_no = idx + 1;
@@ -195,20 +274,47 @@ pub fn parse_script(input: &str) -> Result<Vec<Instruction>, ParseError> {
             continue;
         }
 
+        if trimmed.eq_ignore_ascii_case("end") {
+            let (line, mut kind, outer) = blocks.pop().ok_or_else(|| {
+                ParseError::new(line_no, "'end' without a matching 'layer' or 'tag'")
+            })?;
+            let body = std::mem::replace(&mut instructions, outer);
+            match &mut kind {
+                InstructionKind::Layer(spec) => spec.body = body,
+                InstructionKind::Tag(spec) => spec.body = body,
+                _ => unreachable!(),
+            }
+            instructions.push(Instruction { line, kind });
+            continue;
+        }
+
         let command = parse_line(trimmed, line_no)?;
-        if let Some(kind) = command {
-            instructions.push(Instruction {
+        match command {
+            Some(kind @ (InstructionKind::Layer(_) | InstructionKind::Tag(_))) => {
+                blocks.push((line_no, kind, std::mem::take(&mut instructions)));
+            }
+            Some(kind) => instructions.push(Instruction {
                 line: line_no,
                 kind,
-            });
+            }),
+            None => {}
         }
     }
 
+    if let Some((line, kind, _)) = blocks.pop() {
+        let block = match kind {
+            InstructionKind::Layer(spec) => format!("Layer '{}'", spec.name),
+            _ => "Tag block".to_string(),
+        };
+        return Err(ParseError::new(line, format!("{block} is missing 'end'")));
+    }
+
     Ok(instructions)
 }
 
 fn parse_line(line: &str, line_no: usize) -> Result<Option<InstructionKind>, ParseError> {
-    let tokens: Vec<&str> = line.split_whitespace().collect();
+    let owned_tokens = tokenize(line).map_err(|msg| ParseError::new(line_no, msg))?;
+    let tokens: Vec<&str> = owned_tokens.iter().map(String::as_str).collect();
     if tokens.is_empty() {
         return Ok(None);
     }
@@ -219,7 +325,15 @@ fn parse_line(line: &str, line_no: usize) -> Result<Option<InstructionKind>, Par
         "line" => parse_line_command(&tokens, line_no),
         "circle" => parse_circle_command(&tokens, line_no),
         "rectangle" => parse_rectangle_command(&tokens, line_no),
+        "polygon" => parse_polygon_command(&tokens, line_no),
+        "path" => parse_path_command(&tokens, line_no),
         "set" => parse_set_command(&tokens, line_no),
+        "meta" => parse_meta_command(&tokens, line_no),
+        "bookmark" => parse_bookmark_command(&tokens, line_no),
+        "layer" => parse_layer_command(&tokens, line_no),
+        "push_style" => Ok(Some(InstructionKind::PushStyle)),
+        "pop_style" => Ok(Some(InstructionKind::PopStyle)),
+        "tag" => parse_tag_command(&tokens, line_no),
         _ => Err(ParseError::new(
             line_no,
             format!("Unknown command '{}'", tokens[0]),
@@ -227,17 +341,59 @@ fn parse_line(line: &str, line_no: usize) -> Result<Option<InstructionKind>, Par
     }
 }
 
+/// Split a line on whitespace, keeping double-quoted sections (with `\"` escapes) together.
+fn tokenize(line: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut chars = line.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' => {
+                in_token = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped) => current.push(escaped),
+                            None => return Err("Unterminated string".to_string()),
+                        },
+                        Some(other) => current.push(other),
+                        None => return Err("Unterminated string".to_string()),
+                    }
+                }
+            }
+            ch if ch.is_whitespace() => {
+                if in_token {
+                    tokens.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+            }
+            _ => {
+                in_token = true;
+                current.push(ch);
+            }
+        }
+    }
+    if in_token {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
 fn parse_page_command(tokens: &[&str], line: usize) -> Result<Option<InstructionKind>, ParseError> {
     let spec = tokens
         .get(1)
         .ok_or_else(|| ParseError::new(line, "Missing page specification"))?
         .to_ascii_lowercase();
+    let mut options_from = 2;
     let kind = match spec.as_str() {
         "default" => PageKind::Default,
         "letter" => PageKind::Letter,
         "letter-landscape" => PageKind::LetterLandscape,
         "a4" => PageKind::A4,
         "a4-landscape" => PageKind::A4Landscape,
+        "fit" => PageKind::Fit,
         "size" => {
             let width = tokens
                 .get(2)
@@ -245,6 +401,7 @@ fn parse_page_command(tokens: &[&str], line: usize) -> Result<Option<Instruction
             let height = tokens
                 .get(3)
                 .ok_or_else(|| ParseError::new(line, "Missing page height"))?;
+            options_from = 4;
             PageKind::Custom {
                 width: parse_length(width).map_err(|msg| ParseError::new(line, msg))?,
                 height: parse_length(height).map_err(|msg| ParseError::new(line, msg))?,
@@ -257,7 +414,158 @@ fn parse_page_command(tokens: &[&str], line: usize) -> Result<Option<Instruction
             ));
         }
     };
-    Ok(Some(InstructionKind::AddPage(kind)))
+
+    let mut options = PageOptions::new();
+    let mut label_style = None;
+    let mut label_prefix = None;
+    let mut label_start = None;
+    let mut margin = None;
+    let mut origin = None;
+    let mut viewport = None;
+    let mut viewport_box = None;
+    for token in tokens.iter().skip(options_from) {
+        let (key, value) = split_key_value(token, line)?;
+        match key.as_str() {
+            "crop" => options.crop_box = Some(parse_box(value, line)?),
+            "bleed" => options.bleed_box = Some(parse_box(value, line)?),
+            "trim" => options.trim_box = Some(parse_box(value, line)?),
+            "rotate" => {
+                options.rotation = value
+                    .parse()
+                    .ok()
+                    .and_then(PageRotation::from_degrees)
+                    .ok_or_else(|| {
+                        ParseError::new(
+                            line,
+                            format!("Page rotation must be a multiple of 90, found '{value}'"),
+                        )
+                    })?;
+            }
+            "label" => {
+                label_style = Some(match value {
+                    "decimal" => PageLabelStyle::Decimal,
+                    "Roman" => PageLabelStyle::UpperRoman,
+                    "roman" => PageLabelStyle::LowerRoman,
+                    "Alpha" => PageLabelStyle::UpperLetters,
+                    "alpha" => PageLabelStyle::LowerLetters,
+                    "none" => PageLabelStyle::None,
+                    other => {
+                        return Err(ParseError::new(
+                            line,
+                            format!("Unknown page label style '{other}'"),
+                        ));
+                    }
+                });
+            }
+            "origin" => {
+                origin = Some(match value {
+                    "bottom-left" => Coordinates::BottomLeft,
+                    "top-left" => Coordinates::TopLeft,
+                    other => {
+                        return Err(ParseError::new(
+                            line,
+                            format!("Unknown page origin '{other}'"),
+                        ));
+                    }
+                });
+            }
+            "viewport" => viewport = Some(parse_viewport(value, line)?),
+            "viewport_box" => viewport_box = Some(parse_box(value, line)?),
+            "margin" => {
+                if !matches!(kind, PageKind::Fit) {
+                    return Err(ParseError::new(line, "'margin' only applies to 'page fit'"));
+                }
+                let length = parse_length(value).map_err(|msg| ParseError::new(line, msg))?;
+                if !length.0.is_finite() || length.0 < 0.0 {
+                    return Err(ParseError::new(line, format!("Invalid margin '{value}'")));
+                }
+                margin = Some(length);
+            }
+            "label_prefix" => label_prefix = Some(value.to_string()),
+            "label_start" => {
+                label_start = Some(value.parse::<NonZeroU32>().map_err(|_| {
+                    ParseError::new(line, format!("Invalid page label start '{value}'"))
+                })?);
+            }
+            other => {
+                return Err(ParseError::new(
+                    line,
+                    format!("Unknown page option '{other}'"),
+                ));
+            }
+        }
+    }
+    if label_style.is_some() || label_prefix.is_some() || label_start.is_some() {
+        let mut label = PageLabel::new(label_style.unwrap_or_default());
+        label.prefix = label_prefix;
+        if let Some(start) = label_start {
+            label.start = start;
+        }
+        options.label = Some(label);
+    }
+    options.coordinates = match (origin, viewport, viewport_box) {
+        (Some(_), Some(_), _) => {
+            return Err(ParseError::new(
+                line,
+                "'origin' and 'viewport' cannot be combined",
+            ));
+        }
+        (_, None, Some(_)) => {
+            return Err(ParseError::new(
+                line,
+                "'viewport_box' requires a 'viewport'",
+            ));
+        }
+        (_, Some([x1, x2, y1, y2]), rect) => Coordinates::Viewport {
+            x: (x1, x2),
+            y: (y1, y2),
+            rect,
+        },
+        (origin, None, None) => origin.unwrap_or_default(),
+    };
+    if matches!(kind, PageKind::Fit) {
+        options = options.with_fit(margin.map_or(Pt(0.0), |margin| margin.as_pt()));
+    }
+
+    Ok(Some(InstructionKind::AddPage(kind, options)))
+}
+
+/// Parse the data ranges of a viewport given as `x1,x2,y1,y2`.
+fn parse_viewport(value: &str, line: usize) -> Result<[f64; 4], ParseError> {
+    let parts: Vec<f64> = value
+        .split(',')
+        .map(|part| part.trim().parse::<f64>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| ParseError::new(line, format!("Invalid viewport '{value}'")))?;
+    match parts[..] {
+        [x1, x2, y1, y2] if parts.iter().all(|v| v.is_finite()) && x1 != x2 && y1 != y2 => {
+            Ok([x1, x2, y1, y2])
+        }
+        _ => Err(ParseError::new(
+            line,
+            format!("Viewport must be 'x1,x2,y1,y2' with non-empty ranges, found '{value}'"),
+        )),
+    }
+}
+
+/// Parse a page box given as `x,y,width,height`.
+fn parse_box(value: &str, line: usize) -> Result<[f64; 4], ParseError> {
+    let parts: Vec<&str> = value.split(',').collect();
+    if parts.len() != 4 {
+        return Err(ParseError::new(
+            line,
+            format!("Page box must be 'x,y,width,height', found '{value}'"),
+        ));
+    }
+    let mut lengths = [0.0; 4];
+    for (length, part) in lengths.iter_mut().zip(parts) {
+        *length = parse_length(part)
+            .map_err(|msg| ParseError::new(line, msg))?
+            .as_pt()
+            .to_points();
+    }
+    let [x, y, width, height] = lengths;
+    Ok([x, y, x + width, y + height])
 }
 
 fn parse_line_command(tokens: &[&str], line: usize) -> Result<Option<InstructionKind>, ParseError> {
@@ -276,6 +584,9 @@ fn parse_line_command(tokens: &[&str], line: usize) -> Result<Option<Instruction
     let mut width = None;
     let mut color = None;
     let mut cap = None;
+    let mut link = None;
+    let mut role = None;
+    let mut alt = None;
 
     for token in tokens.iter().skip(5) {
         let (key, value) = split_key_value(token, line)?;
@@ -290,6 +601,9 @@ fn parse_line_command(tokens: &[&str], line: usize) -> Result<Option<Instruction
                     return Err(ParseError::new(line, format!("Unknown cap type '{other}'")));
                 }
             },
+            "link" => link = Some(parse_link(value).map_err(|msg| ParseError::new(line, msg))?),
+            "role" => role = Some(parse_role(value).map_err(|msg| ParseError::new(line, msg))?),
+            "alt" => alt = Some(value.to_string()),
             other => {
                 return Err(ParseError::new(
                     line,
@@ -307,6 +621,8 @@ fn parse_line_command(tokens: &[&str], line: usize) -> Result<Option<Instruction
         width,
         color,
         cap,
+        link,
+        tag: shape_tag(role, alt),
     })))
 }
 
@@ -326,10 +642,16 @@ fn parse_circle_command(
     let radius = parse_length(tokens[3]).map_err(|msg| ParseError::new(line, msg))?;
 
     let mut color = None;
+    let mut link = None;
+    let mut role = None;
+    let mut alt = None;
     for token in tokens.iter().skip(4) {
         let (key, value) = split_key_value(token, line)?;
         match key.as_str() {
             "color" => color = Some(parse_color(value).map_err(|msg| ParseError::new(line, msg))?),
+            "link" => link = Some(parse_link(value).map_err(|msg| ParseError::new(line, msg))?),
+            "role" => role = Some(parse_role(value).map_err(|msg| ParseError::new(line, msg))?),
+            "alt" => alt = Some(value.to_string()),
             other => {
                 return Err(ParseError::new(
                     line,
@@ -344,6 +666,8 @@ fn parse_circle_command(
         y,
         radius,
         color,
+        link,
+        tag: shape_tag(role, alt),
     })))
 }
 
@@ -366,6 +690,9 @@ fn parse_rectangle_command(
     let mut color = None;
     let mut anchor = None;
     let mut angle = None;
+    let mut link = None;
+    let mut role = None;
+    let mut alt = None;
 
     for token in tokens.iter().skip(5) {
         let (key, value) = split_key_value(token, line)?;
@@ -378,6 +705,9 @@ fn parse_rectangle_command(
                     })?)
             }
             "angle" => angle = Some(parse_angle(value).map_err(|msg| ParseError::new(line, msg))?),
+            "link" => link = Some(parse_link(value).map_err(|msg| ParseError::new(line, msg))?),
+            "role" => role = Some(parse_role(value).map_err(|msg| ParseError::new(line, msg))?),
+            "alt" => alt = Some(value.to_string()),
             other => {
                 return Err(ParseError::new(
                     line,
@@ -395,9 +725,189 @@ fn parse_rectangle_command(
         color,
         anchor,
         angle,
+        link,
+        tag: shape_tag(role, alt),
     })))
 }
 
+fn parse_polygon_command(
+    tokens: &[&str],
+    line: usize,
+) -> Result<Option<InstructionKind>, ParseError> {
+    let points = tokens
+        .iter()
+        .skip(1)
+        .take_while(|token| !token.contains('='))
+        .map(|token| parse_point(token, line))
+        .collect::<Result<Vec<_>, _>>()?;
+    if points.len() < 3 {
+        return Err(ParseError::new(
+            line,
+            "Polygon requires at least three points 'x,y'",
+        ));
+    }
+    let spec = parse_path_options(
+        &tokens[1 + points.len()..],
+        line,
+        "polygon",
+        Path::polygon(&points),
+    )?;
+    Ok(Some(InstructionKind::DrawPath(spec)))
+}
+
+/// Parse `path M x,y L x,y ... C x1,y1 x2,y2 x,y ... Z ...`, where the points after `M`
+/// continue the subpath with lines (as in SVG).
+fn parse_path_command(tokens: &[&str], line: usize) -> Result<Option<InstructionKind>, ParseError> {
+    let mut path = Path::new();
+    let mut command = None;
+    let mut controls = Vec::new();
+    let mut options_from = tokens.len();
+    for (i, token) in tokens.iter().enumerate().skip(1) {
+        if token.contains('=') {
+            options_from = i;
+            break;
+        }
+        if !controls.is_empty() && token.len() == 1 {
+            return Err(ParseError::new(line, "Curve requires three points 'x,y'"));
+        }
+        match token.to_ascii_uppercase().as_str() {
+            letter @ ("M" | "L" | "C") => {
+                command = letter.chars().next();
+                continue;
+            }
+            "Z" => {
+                path = path.close();
+                command = None;
+                continue;
+            }
+            _ => {}
+        }
+        let (x, y) = parse_point(token, line)?;
+        match command {
+            Some('M') => {
+                path = path.move_to(x, y);
+                command = Some('L');
+            }
+            Some('L') => path = path.line_to(x, y),
+            Some(_) => {
+                controls.push((x, y));
+                if let [(x1, y1), (x2, y2), (x, y)] = controls[..] {
+                    path = path.curve_to(x1, y1, x2, y2, x, y);
+                    controls.clear();
+                }
+            }
+            None => {
+                return Err(ParseError::new(
+                    line,
+                    format!("Point '{token}' must follow 'M', 'L' or 'C'"),
+                ));
+            }
+        }
+    }
+    if !controls.is_empty() {
+        return Err(ParseError::new(line, "Curve requires three points 'x,y'"));
+    }
+    if path.is_empty() {
+        return Err(ParseError::new(
+            line,
+            "Path requires segments such as 'M x,y L x,y'",
+        ));
+    }
+    let spec = parse_path_options(&tokens[options_from..], line, "path", path)?;
+    Ok(Some(InstructionKind::DrawPath(spec)))
+}
+
+/// Parse a point given as `x,y`.
+fn parse_point(token: &str, line: usize) -> Result<(Pt, Pt), ParseError> {
+    let (x, y) = token
+        .split_once(',')
+        .ok_or_else(|| ParseError::new(line, format!("Expected a point 'x,y', found '{token}'")))?;
+    let x = parse_length(x).map_err(|msg| ParseError::new(line, msg))?;
+    let y = parse_length(y).map_err(|msg| ParseError::new(line, msg))?;
+    Ok((x.as_pt(), y.as_pt()))
+}
+
+fn parse_fill_rule(token: &str) -> Option<FillRule> {
+    match token.to_ascii_lowercase().as_str() {
+        "nonzero" => Some(FillRule::NonZero),
+        "evenodd" => Some(FillRule::EvenOdd),
+        _ => None,
+    }
+}
+
+/// Parse the options of a polygon or path.
+fn parse_path_options(
+    tokens: &[&str],
+    line: usize,
+    command: &str,
+    path: Path,
+) -> Result<PathSpec, ParseError> {
+    let mut spec = PathSpec {
+        path,
+        width: None,
+        color: None,
+        stroke: None,
+        cap: None,
+        fill_rule: None,
+        anchor: None,
+        angle: None,
+        link: None,
+        tag: None,
+    };
+    let mut role = None;
+    let mut alt = None;
+    for token in tokens {
+        let (key, value) = split_key_value(token, line)?;
+        match key.as_str() {
+            "width" => {
+                spec.width = Some(parse_length(value).map_err(|msg| ParseError::new(line, msg))?)
+            }
+            "color" => {
+                spec.color = Some(parse_color(value).map_err(|msg| ParseError::new(line, msg))?)
+            }
+            "stroke" => {
+                spec.stroke = Some(parse_color(value).map_err(|msg| ParseError::new(line, msg))?)
+            }
+            "cap" => match value {
+                "butt" => spec.cap = Some(CapType::Butt),
+                "round" => spec.cap = Some(CapType::Round),
+                "square" => spec.cap = Some(CapType::Square),
+                other => {
+                    return Err(ParseError::new(line, format!("Unknown cap type '{other}'")));
+                }
+            },
+            "fill_rule" => {
+                spec.fill_rule =
+                    Some(parse_fill_rule(value).ok_or_else(|| {
+                        ParseError::new(line, format!("Unknown fill rule '{value}'"))
+                    })?)
+            }
+            "anchor" => {
+                spec.anchor =
+                    Some(parse_anchor(value).ok_or_else(|| {
+                        ParseError::new(line, format!("Unknown anchor '{value}'"))
+                    })?)
+            }
+            "angle" => {
+                spec.angle = Some(parse_angle(value).map_err(|msg| ParseError::new(line, msg))?)
+            }
+            "link" => {
+                spec.link = Some(parse_link(value).map_err(|msg| ParseError::new(line, msg))?)
+            }
+            "role" => role = Some(parse_role(value).map_err(|msg| ParseError::new(line, msg))?),
+            "alt" => alt = Some(value.to_string()),
+            other => {
+                return Err(ParseError::new(
+                    line,
+                    format!("Unknown {command} option '{other}'"),
+                ));
+            }
+        }
+    }
+    spec.tag = shape_tag(role, alt);
+    Ok(spec)
+}
+
 fn parse_set_command(tokens: &[&str], line: usize) -> Result<Option<InstructionKind>, ParseError> {
     let target = tokens
         .get(1)
@@ -454,10 +964,191 @@ fn parse_set_command(tokens: &[&str], line: usize) -> Result<Option<InstructionK
                 parse_angle(angle).map_err(|msg| ParseError::new(line, msg))?,
             )))
         }
+        "default_fill_rule" => {
+            let rule = tokens
+                .get(2)
+                .ok_or_else(|| ParseError::new(line, "Missing fill rule"))?;
+            let fill_rule = parse_fill_rule(rule)
+                .ok_or_else(|| ParseError::new(line, format!("Unknown fill rule '{rule}'")))?;
+            Ok(Some(InstructionKind::SetDefaultFillRule(fill_rule)))
+        }
+        "precision" => {
+            let decimals = tokens
+                .get(2)
+                .ok_or_else(|| ParseError::new(line, "Missing number of decimal places"))?;
+            match decimals.parse::<u8>() {
+                Ok(decimals) if decimals <= 9 => Ok(Some(InstructionKind::SetPrecision(decimals))),
+                _ => Err(ParseError::new(
+                    line,
+                    format!("Invalid precision '{decimals}' (expected 0 to 9 decimal places)"),
+                )),
+            }
+        }
+        "conformance" => {
+            let level = tokens
+                .get(2)
+                .ok_or_else(|| ParseError::new(line, "Missing conformance level"))?;
+            let conformance = match level.to_ascii_lowercase().as_str() {
+                "none" => Conformance::None,
+                "pdfa-2b" | "pdf/a-2b" => Conformance::PdfA2b,
+                other => {
+                    return Err(ParseError::new(
+                        line,
+                        format!("Unknown conformance level '{other}'"),
+                    ));
+                }
+            };
+            Ok(Some(InstructionKind::SetConformance(conformance)))
+        }
+        "reproducible" => {
+            let value = tokens
+                .get(2)
+                .ok_or_else(|| ParseError::new(line, "Missing reproducible value"))?;
+            let reproducible = parse_bool(value).ok_or_else(|| {
+                ParseError::new(line, format!("Invalid reproducible value '{value}'"))
+            })?;
+            Ok(Some(InstructionKind::SetReproducible(reproducible)))
+        }
         other => Err(ParseError::new(line, format!("Unknown property '{other}'"))),
     }
 }
 
+fn parse_meta_command(tokens: &[&str], line: usize) -> Result<Option<InstructionKind>, ParseError> {
+    if tokens.len() < 2 {
+        return Err(ParseError::new(
+            line,
+            "Meta requires at least one key=value entry",
+        ));
+    }
+
+    let mut spec = MetaSpec::default();
+    for token in tokens.iter().skip(1) {
+        let (key, value) = split_key_value(token, line)?;
+        let value = value.to_string();
+        match key.as_str() {
+            "title" => spec.title = Some(value),
+            "author" => spec.author = Some(value),
+            "subject" => spec.subject = Some(value),
+            "keywords" => spec.keywords = Some(value),
+            "creator" => spec.creator = Some(value),
+            "producer" => spec.producer = Some(value),
+            "creation_date" => {
+                spec.creation_date = Some(value.parse().map_err(|msg| ParseError::new(line, msg))?)
+            }
+            other => {
+                return Err(ParseError::new(line, format!("Unknown meta key '{other}'")));
+            }
+        }
+    }
+
+    Ok(Some(InstructionKind::SetMetadata(spec)))
+}
+
+fn parse_bookmark_command(
+    tokens: &[&str],
+    line: usize,
+) -> Result<Option<InstructionKind>, ParseError> {
+    let title = tokens
+        .get(1)
+        .ok_or_else(|| ParseError::new(line, "Missing bookmark title"))?
+        .to_string();
+
+    let mut spec = BookmarkSpec {
+        title,
+        y: None,
+        level: 1,
+        open: true,
+    };
+    for token in tokens.iter().skip(2) {
+        let (key, value) = split_key_value(token, line)?;
+        match key.as_str() {
+            "y" => spec.y = Some(parse_length(value).map_err(|msg| ParseError::new(line, msg))?),
+            "level" => {
+                spec.level = value
+                    .parse()
+                    .ok()
+                    .filter(|level| *level >= 1)
+                    .ok_or_else(|| ParseError::new(line, format!("Invalid level '{value}'")))?
+            }
+            "open" => {
+                spec.open = parse_bool(value).ok_or_else(|| {
+                    ParseError::new(line, format!("Expected true or false, found '{value}'"))
+                })?
+            }
+            other => {
+                return Err(ParseError::new(
+                    line,
+                    format!("Unknown bookmark option '{other}'"),
+                ));
+            }
+        }
+    }
+
+    Ok(Some(InstructionKind::AddBookmark(spec)))
+}
+
+fn parse_layer_command(
+    tokens: &[&str],
+    line: usize,
+) -> Result<Option<InstructionKind>, ParseError> {
+    let name = tokens
+        .get(1)
+        .ok_or_else(|| ParseError::new(line, "Missing layer name"))?;
+    let mut spec = LayerSpec {
+        name: name.to_string(),
+        visible: None,
+        body: Vec::new(),
+    };
+    for token in tokens.iter().skip(2) {
+        let (key, value) = split_key_value(token, line)?;
+        match key.as_str() {
+            "visible" => {
+                spec.visible = Some(parse_bool(value).ok_or_else(|| {
+                    ParseError::new(line, format!("Expected true or false, found '{value}'"))
+                })?);
+            }
+            other => {
+                return Err(ParseError::new(
+                    line,
+                    format!("Unknown layer option '{other}'"),
+                ));
+            }
+        }
+    }
+    Ok(Some(InstructionKind::Layer(spec)))
+}
+
+fn parse_tag_command(tokens: &[&str], line: usize) -> Result<Option<InstructionKind>, ParseError> {
+    let role = tokens
+        .get(1)
+        .ok_or_else(|| ParseError::new(line, "Missing tag role"))?;
+    let mut tag = Tag::new(parse_role(role).map_err(|msg| ParseError::new(line, msg))?);
+    for token in tokens.iter().skip(2) {
+        let (key, value) = split_key_value(token, line)?;
+        match key.as_str() {
+            "alt" => tag.alt = Some(value.to_string()),
+            other => {
+                return Err(ParseError::new(
+                    line,
+                    format!("Unknown tag option '{other}'"),
+                ));
+            }
+        }
+    }
+    Ok(Some(InstructionKind::Tag(TagSpec {
+        tag,
+        body: Vec::new(),
+    })))
+}
+
+fn parse_bool(token: &str) -> Option<bool> {
+    match token.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" => Some(true),
+        "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
 fn split_key_value(token: &str, line: usize) -> Result<(String, &str), ParseError> {
     let (key, value) = token.split_once('=').ok_or_else(|| {
         ParseError::new(line, format!("Expected key=value option, found '{token}'"))
@@ -561,6 +1252,42 @@ fn parse_color(token: &str) -> Result<ColorValue, String> {
     Err(format!("Unsupported color specification '{token}'"))
 }
 
+fn parse_role(token: &str) -> Result<Role, String> {
+    match token.to_ascii_lowercase().as_str() {
+        "figure" => Ok(Role::Figure),
+        "formula" => Ok(Role::Formula),
+        "artifact" => Ok(Role::Artifact),
+        other => Err(format!("Unknown role '{other}'")),
+    }
+}
+
+/// Tag of a shape from its `role=` and `alt=` options (alternate text implies a figure).
+fn shape_tag(role: Option<Role>, alt: Option<String>) -> Option<Tag> {
+    match (role, alt) {
+        (None, None) => None,
+        (role, alt) => Some(Tag {
+            role: role.unwrap_or(Role::Figure),
+            alt,
+        }),
+    }
+}
+
+/// Parse a link target: `page:<n>` (one-based) for internal links, anything else is a URI.
+fn parse_link(token: &str) -> Result<Link, String> {
+    if let Some(page) = token.strip_prefix("page:") {
+        let page: usize = page
+            .parse()
+            .ok()
+            .filter(|page| *page >= 1)
+            .ok_or_else(|| format!("Invalid page number in link '{token}'"))?;
+        return Ok(Link::page(page - 1));
+    }
+    if token.is_empty() {
+        return Err("Empty link target".to_string());
+    }
+    Ok(Link::uri(token))
+}
+
 fn parse_anchor(token: &str) -> Option<Anchor> {
     match token.to_ascii_lowercase().as_str() {
         "center" => Some(Anchor::Center),
@@ -594,43 +1321,144 @@ fn split_number_unit(token: &str) -> Result<(&str, &str), String> {
     Ok((number, unit))
 }
 
-pub fn execute_instructions(
-    generator: &mut Generator,
+/// State carried across nested instruction blocks.
+#[derive(Default)]
+struct ExecutionState {
+    has_page: bool,
+    bookmark_stack: Vec<BookmarkId>,
+}
+
+pub fn execute_instructions<W: Write>(
+    generator: &mut Generator<W>,
     instructions: &[Instruction],
 ) -> Result<(), ExecutionError> {
-    let mut has_page = false;
+    let mut state = ExecutionState::default();
+    // Untagged content before the first tag is marked as an artifact as well
+    if uses_tags(instructions) {
+        generator.set_tagged(true);
+    }
+    execute_block(generator, instructions, &mut state)?;
 
+    if !state.has_page {
+        return Err(ExecutionError::new(
+            0,
+            "Script did not add any page. Add at least one 'page' command.",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Whether any instruction tags content, including in nested blocks.
+fn uses_tags(instructions: &[Instruction]) -> bool {
+    instructions
+        .iter()
+        .any(|instruction| match &instruction.kind {
+            InstructionKind::DrawLine(spec) => spec.tag.is_some(),
+            InstructionKind::DrawCircle(spec) => spec.tag.is_some(),
+            InstructionKind::DrawRectangle(spec) => spec.tag.is_some(),
+            InstructionKind::DrawPath(spec) => spec.tag.is_some(),
+            InstructionKind::Layer(spec) => uses_tags(&spec.body),
+            InstructionKind::Tag(_) => true,
+            _ => false,
+        })
+}
+
+fn execute_block<W: Write>(
+    generator: &mut Generator<W>,
+    instructions: &[Instruction],
+    state: &mut ExecutionState,
+) -> Result<(), ExecutionError> {
     for instruction in instructions {
         match &instruction.kind {
-            InstructionKind::AddPage(kind) => {
-                apply_page(generator, *kind);
-                has_page = true;
+            InstructionKind::AddPage(kind, options) => {
+                apply_page(generator, *kind, options.clone());
+                state.has_page = true;
             }
             InstructionKind::SetDefaultPageSize { width, height } => {
-                Generator::set_default_page_size(width.as_pt(), height.as_pt());
+                generator.set_default_page_size(width.as_pt(), height.as_pt());
             }
             InstructionKind::SetDefaultWidth(width) => {
-                Generator::set_default_width(width.as_pt());
+                generator.set_default_width(width.as_pt());
             }
             InstructionKind::SetDefaultColor(color) => {
-                apply_default_color(color.clone());
+                apply_default_color(generator, color.clone());
             }
             InstructionKind::SetDefaultCapType(cap) => {
-                Generator::set_default_cap_type(*cap);
+                generator.set_default_cap_type(*cap);
             }
             InstructionKind::SetDefaultAngle(angle) => {
-                Generator::set_default_angle(angle.as_degree());
+                generator.set_default_angle(angle.as_degree());
+            }
+            InstructionKind::SetDefaultFillRule(fill_rule) => {
+                generator.set_default_fill_rule(*fill_rule);
+            }
+            InstructionKind::SetPrecision(decimals) => generator.set_precision(*decimals),
+            InstructionKind::PushStyle => generator.push_style(),
+            InstructionKind::PopStyle => {
+                if !generator.pop_style() {
+                    return Err(ExecutionError::new(
+                        instruction.line,
+                        "'pop_style' without a matching 'push_style'",
+                    ));
+                }
+            }
+            InstructionKind::SetConformance(conformance) => {
+                generator.set_conformance(*conformance);
+            }
+            InstructionKind::SetReproducible(reproducible) => {
+                generator.set_reproducible(*reproducible);
             }
+            InstructionKind::SetMetadata(spec) => apply_metadata(generator, spec.clone()),
             other => {
-                if !has_page {
-                    apply_page(generator, PageKind::Default);
-                    has_page = true;
+                if !state.has_page {
+                    apply_page(generator, PageKind::Default, PageOptions::default());
+                    state.has_page = true;
                 }
+                let draw_error =
+                    |err: crate::Error| ExecutionError::new(instruction.line, err.to_string());
                 match other {
-                    InstructionKind::DrawLine(spec) => apply_line(generator, spec.clone()),
-                    InstructionKind::DrawCircle(spec) => apply_circle(generator, spec.clone()),
+                    InstructionKind::AddBookmark(spec) => {
+                        if spec.level > state.bookmark_stack.len() + 1 {
+                            return Err(ExecutionError::new(
+                                instruction.line,
+                                format!(
+                                    "Bookmark level {} has no parent at level {}",
+                                    spec.level,
+                                    spec.level - 1
+                                ),
+                            ));
+                        }
+                        state.bookmark_stack.truncate(spec.level - 1);
+                        let id =
+                            apply_bookmark(generator, spec.clone(), state.bookmark_stack.last());
+                        state.bookmark_stack.push(id);
+                    }
+                    InstructionKind::DrawLine(spec) => {
+                        apply_line(generator, spec.clone()).map_err(draw_error)?
+                    }
+                    InstructionKind::DrawCircle(spec) => {
+                        apply_circle(generator, spec.clone()).map_err(draw_error)?
+                    }
                     InstructionKind::DrawRectangle(spec) => {
-                        apply_rectangle(generator, spec.clone())
+                        apply_rectangle(generator, spec.clone()).map_err(draw_error)?
+                    }
+                    InstructionKind::DrawPath(spec) => {
+                        apply_path(generator, spec.clone()).map_err(draw_error)?
+                    }
+                    InstructionKind::Layer(spec) => {
+                        let existing = generator
+                            .get_layers()
+                            .iter()
+                            .find(|layer| layer.name == spec.name)
+                            .map(|layer| layer.visible);
+                        let visible = spec.visible.or(existing).unwrap_or(true);
+                        let mut scope = generator.layer(&spec.name, visible);
+                        execute_block(&mut scope, &spec.body, state)?;
+                    }
+                    InstructionKind::Tag(spec) => {
+                        let mut scope = generator.tag(spec.tag.clone());
+                        execute_block(&mut scope, &spec.body, state)?;
                     }
                     _ => unreachable!(),
                 }
@@ -638,13 +1466,6 @@ pub fn execute_instructions(
         }
     }
 
-    if !has_page {
-        return Err(ExecutionError::new(
-            0,
-            "Script did not add any page. Add at least one 'page' command.",
-        ));
-    }
-
     Ok(())
 }
 
@@ -666,40 +1487,81 @@ pub fn render_script_to_bytes(script: &str) -> Result<Vec<u8>, Box<dyn Error>> {
     let instructions = parse_script(script)?;
     let mut generator = Generator::new(PathBuf::new());
     execute_instructions(&mut generator, &instructions)?;
-    Ok(generator.to_pdf_bytes())
+    Ok(generator.to_pdf_bytes()?)
 }
 
-fn apply_page(generator: &mut Generator, kind: PageKind) {
-    match kind {
-        PageKind::Default => generator.add_page(),
-        PageKind::Letter => generator.add_page_letter(),
-        PageKind::LetterLandscape => generator.add_page_letter_landscape(),
-        PageKind::A4 => generator.add_page_a4(),
-        PageKind::A4Landscape => generator.add_page_a4_landscape(),
-        PageKind::Custom { width, height } => {
-            generator.add_page_with_size(width.as_pt(), height.as_pt())
-        }
+fn apply_page<W: Write>(generator: &mut Generator<W>, kind: PageKind, options: PageOptions) {
+    let (width, height) = match kind {
+        PageKind::Default | PageKind::Fit => generator.get_default_page_size(),
+        PageKind::Letter => (Inch(8.5).as_pt(), Inch(11.0).as_pt()),
+        PageKind::LetterLandscape => (Inch(11.0).as_pt(), Inch(8.5).as_pt()),
+        PageKind::A4 => (Mm(210.0).as_pt(), Mm(297.0).as_pt()),
+        PageKind::A4Landscape => (Mm(297.0).as_pt(), Mm(210.0).as_pt()),
+        PageKind::Custom { width, height } => (width.as_pt(), height.as_pt()),
+    };
+    generator.add_page_with_options(width, height, options);
+}
+
+fn apply_metadata<W: Write>(generator: &mut Generator<W>, spec: MetaSpec) {
+    if let Some(title) = spec.title {
+        generator.set_title(title);
+    }
+    if let Some(author) = spec.author {
+        generator.set_author(author);
+    }
+    if let Some(subject) = spec.subject {
+        generator.set_subject(subject);
+    }
+    if let Some(keywords) = spec.keywords {
+        generator.set_keywords(keywords);
+    }
+    if let Some(creator) = spec.creator {
+        generator.set_creator(creator);
+    }
+    if let Some(producer) = spec.producer {
+        generator.set_producer(producer);
     }
+    if let Some(date) = spec.creation_date {
+        generator.set_creation_date(date);
+    }
+}
+
+fn apply_bookmark<W: Write>(
+    generator: &mut Generator<W>,
+    spec: BookmarkSpec,
+    parent: Option<&BookmarkId>,
+) -> BookmarkId {
+    let page = generator.page_count() - 1;
+    let y = match spec.y {
+        Some(y) => y.as_pt(),
+        None => Pt(generator.page_top(page).unwrap_or(0.0)),
+    };
+    let id = match parent {
+        Some(parent) => generator.bookmark_child(*parent, spec.title, page, y),
+        None => generator.bookmark(spec.title, page, y),
+    };
+    generator.set_bookmark_open(id, spec.open);
+    id
 }
 
-fn apply_default_color(color: ColorValue) {
+fn apply_default_color<W: Write>(generator: &mut Generator<W>, color: ColorValue) {
     match color {
         ColorValue::Named(name) => match name {
-            NamedColorValue::Black => Generator::set_default_color(Rgb(0.0, 0.0, 0.0)),
-            NamedColorValue::White => Generator::set_default_color(Rgb(1.0, 1.0, 1.0)),
-            NamedColorValue::Gray => Generator::set_default_color(Rgb(0.5, 0.5, 0.5)),
-            NamedColorValue::Red => Generator::set_default_color(Rgb(1.0, 0.0, 0.0)),
-            NamedColorValue::Green => Generator::set_default_color(Rgb(0.0, 1.0, 0.0)),
-            NamedColorValue::Blue => Generator::set_default_color(Rgb(0.0, 0.0, 1.0)),
-            NamedColorValue::Yellow => Generator::set_default_color(Rgb(1.0, 1.0, 0.0)),
+            NamedColorValue::Black => generator.set_default_color(Rgb(0.0, 0.0, 0.0)),
+            NamedColorValue::White => generator.set_default_color(Rgb(1.0, 1.0, 1.0)),
+            NamedColorValue::Gray => generator.set_default_color(Rgb(0.5, 0.5, 0.5)),
+            NamedColorValue::Red => generator.set_default_color(Rgb(1.0, 0.0, 0.0)),
+            NamedColorValue::Green => generator.set_default_color(Rgb(0.0, 1.0, 0.0)),
+            NamedColorValue::Blue => generator.set_default_color(Rgb(0.0, 0.0, 1.0)),
+            NamedColorValue::Yellow => generator.set_default_color(Rgb(1.0, 1.0, 0.0)),
         },
-        ColorValue::Gray(value) => Generator::set_default_color(Gray(value)),
-        ColorValue::RgbFloat { r, g, b } => Generator::set_default_color(Rgb(r, g, b)),
-        ColorValue::Rgb { r, g, b } => Generator::set_default_color(RGB(r, g, b)),
+        ColorValue::Gray(value) => generator.set_default_color(Gray(value)),
+        ColorValue::RgbFloat { r, g, b } => generator.set_default_color(Rgb(r, g, b)),
+        ColorValue::Rgb { r, g, b } => generator.set_default_color(RGB(r, g, b)),
     }
 }
 
-fn apply_line(generator: &mut Generator, spec: LineSpec) {
+fn apply_line<W: Write>(generator: &mut Generator<W>, spec: LineSpec) -> Result<(), crate::Error> {
     let mut shape = generator.line(
         spec.x1.as_pt(),
         spec.y1.as_pt(),
@@ -712,12 +1574,68 @@ fn apply_line(generator: &mut Generator, spec: LineSpec) {
     if let Some(cap) = spec.cap {
         shape.with_cap_type(cap);
     }
+    if let Some(link) = spec.link {
+        shape.with_link(link);
+    }
+    if let Some(tag) = spec.tag {
+        shape.with_tag(tag);
+    }
 
     if let Some(color) = spec.color.clone() {
         with_color(&mut shape, color);
-    } else {
-        shape.draw();
     }
+    shape.draw()
+}
+
+fn apply_path<W: Write>(generator: &mut Generator<W>, spec: PathSpec) -> Result<(), crate::Error> {
+    let mut shape = generator.path(spec.path);
+    if let Some(width) = spec.width {
+        shape.with_width(width.as_pt());
+    }
+    if let Some(cap) = spec.cap {
+        shape.with_cap_type(cap);
+    }
+    if let Some(fill_rule) = spec.fill_rule {
+        shape.with_fill_rule(fill_rule);
+    }
+    if let Some(angle) = spec.angle {
+        shape.with_angle(angle.as_degree());
+    }
+    if let Some(anchor) = spec.anchor {
+        shape.with_anchor(anchor);
+    }
+    if let Some(link) = spec.link {
+        shape.with_link(link);
+    }
+    if let Some(tag) = spec.tag {
+        shape.with_tag(tag);
+    }
+    if let Some(stroke) = spec.stroke {
+        shape.with_stroke(color_rgb(stroke));
+    }
+    if let Some(color) = spec.color {
+        with_color(&mut shape, color);
+    }
+    shape.draw()
+}
+
+/// RGB value of a color given in a script.
+fn color_rgb(color: ColorValue) -> Rgb {
+    let (r, g, b) = match color {
+        ColorValue::Named(name) => match name {
+            NamedColorValue::Black => (0.0, 0.0, 0.0),
+            NamedColorValue::White => (1.0, 1.0, 1.0),
+            NamedColorValue::Gray => (0.5, 0.5, 0.5),
+            NamedColorValue::Red => (1.0, 0.0, 0.0),
+            NamedColorValue::Green => (0.0, 1.0, 0.0),
+            NamedColorValue::Blue => (0.0, 0.0, 1.0),
+            NamedColorValue::Yellow => (1.0, 1.0, 0.0),
+        },
+        ColorValue::Gray(value) => Gray(value).to_rgb(),
+        ColorValue::RgbFloat { r, g, b } => (r, g, b),
+        ColorValue::Rgb { r, g, b } => RGB(r, g, b).to_rgb(),
+    };
+    Rgb(r, g, b)
 }
 
 fn with_color(shape: &mut Shape<'_>, color: ColorValue) {
@@ -743,19 +1661,29 @@ fn with_color(shape: &mut Shape<'_>, color: ColorValue) {
             shape.with_color(RGB(r, g, b));
         }
     }
-    shape.draw();
 }
 
-fn apply_circle(generator: &mut Generator, spec: CircleSpec) {
+fn apply_circle<W: Write>(
+    generator: &mut Generator<W>,
+    spec: CircleSpec,
+) -> Result<(), crate::Error> {
     let mut shape = generator.circle(spec.x.as_pt(), spec.y.as_pt(), spec.radius.as_pt());
+    if let Some(link) = spec.link {
+        shape.with_link(link);
+    }
+    if let Some(tag) = spec.tag {
+        shape.with_tag(tag);
+    }
     if let Some(color) = spec.color.clone() {
         with_color(&mut shape, color);
-    } else {
-        shape.draw();
     }
+    shape.draw()
 }
 
-fn apply_rectangle(generator: &mut Generator, spec: RectSpec) {
+fn apply_rectangle<W: Write>(
+    generator: &mut Generator<W>,
+    spec: RectSpec,
+) -> Result<(), crate::Error> {
     let mut shape = generator.rectangle(
         spec.x.as_pt(),
         spec.y.as_pt(),
@@ -769,12 +1697,17 @@ fn apply_rectangle(generator: &mut Generator, spec: RectSpec) {
     if let Some(anchor) = spec.anchor {
         shape.with_anchor(anchor);
     }
+    if let Some(link) = spec.link {
+        shape.with_link(link);
+    }
+    if let Some(tag) = spec.tag {
+        shape.with_tag(tag);
+    }
 
     if let Some(color) = spec.color.clone() {
         with_color(&mut shape, color);
-    } else {
-        shape.draw();
     }
+    shape.draw()
 }
 
 #[cfg(all(test, not(feature = "wasm")))]
@@ -818,6 +1751,318 @@ mod tests {
         let _ = fs::remove_file(output);
     }
 
+    #[test]
+    fn pdfa_script_embeds_metadata_and_id() {
+        let script = r#"
+            set conformance pdfa-2b
+            page a4
+            circle 30mm 40mm 10mm color=blue
+        "#;
+        let instructions = parse_script(script).expect("Failed to parse script");
+        let mut generator = Generator::new(PathBuf::new());
+        execute_instructions(&mut generator, &instructions).expect("Execution failed");
+        let bytes = generator
+            .to_pdf_bytes()
+            .expect("PDF/A output should succeed");
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(text.contains("<pdfaid:part>2</pdfaid:part>"));
+        assert!(text.contains("/OutputIntents ["));
+        assert!(text.contains("/ID [<"));
+    }
+
+    #[test]
+    fn pdfa_rejects_oversized_page() {
+        let script = r#"
+            set conformance pdfa-2b
+            page size 6000mm 100mm
+        "#;
+        let instructions = parse_script(script).expect("Failed to parse script");
+        let mut generator = Generator::new(PathBuf::new());
+        execute_instructions(&mut generator, &instructions).expect("Execution failed");
+        assert!(generator.to_pdf_bytes().is_err());
+    }
+
+    #[test]
+    fn meta_directive_writes_info_and_xmp() {
+        let script = r#"
+            meta title="Quarterly \"Sales\" Chart" author="Ada Lovelace"
+            meta creation_date=2025-01-31T10:00:00Z
+            page default
+        "#;
+        let instructions = parse_script(script).expect("Failed to parse script");
+        let mut generator = Generator::new(PathBuf::new());
+        execute_instructions(&mut generator, &instructions).expect("Execution failed");
+        assert_eq!(
+            generator.get_metadata().title.as_deref(),
+            Some("Quarterly \"Sales\" Chart")
+        );
+        let bytes = generator.to_pdf_bytes().expect("PDF output should succeed");
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(text.contains("/Title (Quarterly \"Sales\" Chart) /Author (Ada Lovelace)"));
+        assert!(text.contains("/CreationDate (D:20250131100000Z)"));
+        assert!(text.contains("<rdf:li>Ada Lovelace</rdf:li>"));
+        assert!(text.contains("/Info "));
+    }
+
+    #[test]
+    fn bookmarks_build_nested_outline() {
+        let script = r#"
+            page a4
+            bookmark "Overview"
+            page a4
+            bookmark "Details" open=false
+            bookmark "Part A" level=2 y=100mm
+        "#;
+        let instructions = parse_script(script).expect("Failed to parse script");
+        let mut generator = Generator::new(PathBuf::new());
+        execute_instructions(&mut generator, &instructions).expect("Execution failed");
+        let bookmarks = generator.get_bookmarks();
+        assert_eq!(bookmarks.len(), 3);
+        assert_eq!(bookmarks[2].parent, Some(BookmarkId(1)));
+        assert_eq!(bookmarks[2].page, 1);
+        let bytes = generator.to_pdf_bytes().expect("PDF output should succeed");
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(text.contains("/Type /Outlines"));
+        assert!(text.contains("/PageMode /UseOutlines"));
+
+        // Positions follow the coordinates of the page
+        let script = r#"
+            page a4 origin=top-left
+            bookmark "Top"
+            bookmark "Lower" y=100pt
+        "#;
+        let instructions = parse_script(script).expect("Failed to parse script");
+        let mut generator = Generator::new(PathBuf::new());
+        execute_instructions(&mut generator, &instructions).expect("Execution failed");
+        assert_eq!(generator.get_bookmarks()[0].y, 0.0);
+        let bytes = generator.to_pdf_bytes().expect("PDF output should succeed");
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(text.contains("/XYZ null 841.89 null"));
+        assert!(text.contains("/XYZ null 741.89 null"));
+
+        let invalid = parse_script("page a4\nbookmark \"Deep\" level=3").unwrap();
+        let mut generator = Generator::new(PathBuf::new());
+        assert!(execute_instructions(&mut generator, &invalid).is_err());
+    }
+
+    #[test]
+    fn page_options_set_boxes_rotation_and_labels() {
+        let script = r#"
+            page size 100pt 200pt trim=10pt,10pt,80pt,180pt rotate=-90 label=roman
+            page a4 label_prefix="A-" label_start=5
+        "#;
+        let instructions = parse_script(script).expect("Failed to parse script");
+        let mut generator = Generator::new(PathBuf::new());
+        execute_instructions(&mut generator, &instructions).expect("Execution failed");
+        let bytes = generator.to_pdf_bytes().expect("PDF output should succeed");
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(text.contains("/MediaBox [0 0 100 200] /TrimBox [10 10 90 190] /Rotate 270"));
+        assert!(
+            text.contains("/PageLabels << /Nums [0 << /S /r >> 1 << /S /D /P (A-) /St 5 >>] >>")
+        );
+
+        assert!(parse_script("page a4 rotate=45").is_err());
+        assert!(parse_script("page a4 trim=1,2,3").is_err());
+        assert!(parse_script("page a4 label_start=0").is_err());
+    }
+
+    #[test]
+    fn layer_blocks_wrap_marked_content() {
+        let script = r#"
+            page a4
+            layer "Grid" visible=false
+                line 0 0 100mm 0
+                layer Dimensions
+                    circle 10mm 10mm 2mm
+                end
+            end
+            rectangle 20mm 20mm 40mm 10mm
+        "#;
+        let instructions = parse_script(script).expect("Failed to parse script");
+        assert_eq!(instructions.len(), 3);
+        let mut generator = Generator::new(PathBuf::new());
+        execute_instructions(&mut generator, &instructions).expect("Execution failed");
+        let layers = generator.get_layers();
+        assert_eq!(layers.len(), 2);
+        assert!(!layers[0].visible && layers[1].visible);
+
+        assert!(parse_script("layer Grid\nline 0 0 1 1").is_err());
+        assert!(parse_script("end").is_err());
+    }
+
+    #[test]
+    fn tags_build_structure_tree() {
+        let script = r#"
+            set reproducible true
+            page a4
+            circle 5mm 5mm 1mm
+            tag figure alt="Two bars"
+                rectangle 20mm 20mm 10mm 30mm
+                rectangle 35mm 20mm 10mm 50mm
+            end
+            line 15mm 20mm 60mm 20mm role=artifact
+            circle 80mm 80mm 5mm alt="Marker"
+        "#;
+        let instructions = parse_script(script).expect("Failed to parse script");
+        let mut generator = Generator::new(PathBuf::new());
+        execute_instructions(&mut generator, &instructions).expect("Execution failed");
+        let bytes = generator.to_pdf_bytes().expect("PDF output should succeed");
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(text.contains("/MarkInfo << /Marked true >> /StructTreeRoot"));
+        assert!(text.contains("/StructParents 0"));
+        assert!(text.contains("/S /Figure /P 6 0 R /Pg 3 0 R /K [0] /Alt (Two bars)"));
+        assert!(text.contains("/K [1] /Alt (Marker)"));
+        // Untagged content, even before the first tag, is an artifact
+        assert!(text.contains("stream\n/Artifact BMC\n"));
+
+        assert!(parse_script("line 0 0 1 1 role=heading").is_err());
+    }
+
+    #[test]
+    fn reproducible_output_is_uncompressed_and_stable() {
+        let script = r#"
+            set reproducible true
+            meta title="Report"
+            page a4
+            circle 50mm 50mm 5mm color=red
+        "#;
+        let render = || {
+            let instructions = parse_script(script).expect("Failed to parse script");
+            let mut generator = Generator::new(PathBuf::new());
+            execute_instructions(&mut generator, &instructions).expect("Execution failed");
+            generator.to_pdf_bytes().expect("PDF output should succeed")
+        };
+        let bytes = render();
+        assert_eq!(bytes, render());
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(!text.contains("/FlateDecode"));
+        assert!(text.contains("/Producer (shapdf)"));
+        assert!(text.contains("1 0 0 RG\n28.346 w\n1 J\n141.732 141.732 m\n"));
+        assert!(parse_script("set reproducible maybe").is_err());
+    }
+
+    #[test]
+    fn style_is_isolated_per_document() {
+        let render = |script: &str| {
+            let instructions = parse_script(script).expect("Failed to parse script");
+            let mut generator = Generator::new(PathBuf::new());
+            execute_instructions(&mut generator, &instructions).expect("Execution failed");
+            generator
+        };
+        let first =
+            render("set default_color red\nset default_page_size 100pt 100pt\npage default");
+        assert_eq!(first.get_default_color(), Rgb(1.0, 0.0, 0.0));
+        let second = render("page default");
+        assert_eq!(second.get_default_color(), Rgb(0.0, 0.0, 0.0));
+        let (width, height) = second.get_page_size(0).unwrap();
+        assert_eq!((width.0, height.0), (612.0, 792.0));
+
+        let nested = render("page a4\npush_style\nset default_width 5mm\npop_style");
+        assert_eq!(nested.get_default_width().0, 1.0);
+        let unbalanced = parse_script("page a4\npop_style").unwrap();
+        let mut generator = Generator::new(PathBuf::new());
+        assert!(execute_instructions(&mut generator, &unbalanced).is_err());
+    }
+
+    #[test]
+    fn link_option_adds_annotations() {
+        let script = r#"
+            page a4
+            rectangle 20mm 20mm 40mm 10mm link=page:2
+            page a4
+            circle 50mm 50mm 5mm link=https://example.com/details
+        "#;
+        let instructions = parse_script(script).expect("Failed to parse script");
+        let mut generator = Generator::new(PathBuf::new());
+        execute_instructions(&mut generator, &instructions).expect("Execution failed");
+        let bytes = generator.to_pdf_bytes().expect("PDF output should succeed");
+        let text = String::from_utf8_lossy(&bytes);
+        assert_eq!(text.matches("/Subtype /Link").count(), 2);
+        assert!(text.contains("/URI (https://example.com/details)"));
+        assert!(text.contains("/Dest [5 0 R /XYZ null null null]"));
+        assert!(parse_script("circle 1mm 1mm 1mm link=page:0").is_err());
+    }
+
+    #[test]
+    fn page_coordinates_map_shapes() {
+        let script = r#"
+            page size 100pt 100pt origin=top-left
+            rectangle 10 10 20 30
+            circle 50 10 5 link=https://example.com
+            page size 200pt 100pt viewport=0,100,-1,1
+            circle 50 0 1
+            page size 200pt 100pt viewport=0,10,0,10 viewport_box=100pt,0pt,100pt,100pt
+            line 0 0 10 10
+        "#;
+        let instructions = parse_script(script).expect("Failed to parse script");
+        let mut generator = Generator::new(PathBuf::new());
+        generator.set_reproducible(true);
+        execute_instructions(&mut generator, &instructions).expect("Execution failed");
+        assert_eq!(generator.get_page_bounds(0), Some([10., 60., 55., 95.]));
+        let bytes = generator.to_pdf_bytes().expect("PDF output should succeed");
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(text.contains("10 90 20 -30 re f"));
+        assert!(text.contains("/Rect [45 85 55 95]"));
+        assert!(text.contains("100 50 m\n100 50 l"));
+        assert!(text.contains("100 0 m\n200 100 l"));
+        assert!(parse_script("page a4 origin=center").is_err());
+        assert!(parse_script("page a4 viewport=0,0,0,1").is_err());
+        assert!(parse_script("page a4 origin=top-left viewport=0,1,0,1").is_err());
+    }
+
+    #[test]
+    fn paths_use_the_fill_rule() {
+        let script = r#"
+            set default_fill_rule evenodd
+            path M 0,0 10,0 10,10 0,10 Z M 3,3 L 7,3 7,7 3,7 Z
+            polygon 0,0 10,0 5,5 fill_rule=nonzero stroke=red width=2
+            path M 0,0 C 0,10 10,10 10,0 color=blue
+        "#;
+        let instructions = parse_script(script).expect("Failed to parse script");
+        let mut generator = Generator::new(PathBuf::new());
+        generator.set_reproducible(true);
+        execute_instructions(&mut generator, &instructions).expect("Execution failed");
+        let bytes = generator.to_pdf_bytes().expect("PDF output should succeed");
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(text.contains("0 0 m\n10 0 l\n10 10 l\n0 10 l\nh\n3 3 m\n"));
+        assert!(text.contains("3 7 l\nh\nf*\n"));
+        assert!(text.contains("1 0 0 RG\n2 w\n0 J\n0 0 m\n10 0 l\n5 5 l\nh\nB\n"));
+        assert!(text.contains("0 0 1 rg\n0 0 m\n0 10 10 10 10 0 c\nf*\n"));
+        assert!(parse_script("polygon 0,0 1,1").is_err());
+        assert!(parse_script("path 0,0 L 1,1").is_err());
+        assert!(parse_script("path M 0,0 C 1,1 2,2").is_err());
+        assert!(parse_script("polygon 0,0 1,0 1,1 fill_rule=odd").is_err());
+    }
+
+    #[test]
+    fn fit_pages_wrap_their_content() {
+        let script = r#"
+            page fit margin=10pt
+            circle 100pt 100pt 10pt
+            line 100pt 100pt 150pt 100pt width=2pt
+            page fit
+        "#;
+        let instructions = parse_script(script).expect("Failed to parse script");
+        let mut generator = Generator::to_writer(Vec::new());
+        execute_instructions(&mut generator, &instructions).expect("Execution failed");
+        let bytes = generator.finish().expect("PDF output should succeed");
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(text.contains("/MediaBox [80 80 160 120]"));
+        // Nothing drawn: the default page size is kept
+        assert!(text.contains("/MediaBox [0 0 612 792]"));
+        assert!(parse_script("page a4 margin=5mm").is_err());
+        assert!(parse_script("page fit margin=-5mm").is_err());
+    }
+
+    #[test]
+    fn invalid_shapes_report_the_line() {
+        let instructions = parse_script("page a4\ncircle 10mm 10mm -5mm").unwrap();
+        let mut generator = Generator::new(PathBuf::new());
+        let err = execute_instructions(&mut generator, &instructions).unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(err.message.contains("radius"));
+    }
+
     #[test]
     fn render_script_to_pdf_writes_file() {
         let script = r#"
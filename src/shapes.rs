@@ -1,8 +1,14 @@
+use crate::content::ContentStream;
+use crate::error::{self, Error};
+use crate::link::{Link, LinkArea};
+use crate::path::{self, FillRule, Path, Polyline, FLATTEN_TOLERANCE};
+use crate::style::Style;
+use crate::tag::{StructureTree, Tag};
+use crate::transform::Transform;
 use crate::units::*;
-use once_cell::sync::Lazy;
-use std::sync::Mutex;
+use std::borrow::Cow;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum CapType {
     Butt,
     Round,
@@ -19,7 +25,7 @@ impl CapType {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub enum Anchor {
     Center,
     North,
@@ -29,31 +35,22 @@ pub enum Anchor {
     NorthEast,
     NorthWest,
     SouthEast,
+    #[default]
     SouthWest,
     Point(f64, f64),
 }
 
-impl Default for Anchor {
-    fn default() -> Self {
-        Anchor::SouthWest
-    }
-}
-
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy)]
 pub enum ShapeType {
     Line,
     Circle,
     Rectangle,
     Polygon,
+    Path,
+    #[default]
     Unknown,
 }
 
-impl Default for ShapeType {
-    fn default() -> Self {
-        ShapeType::Unknown
-    }
-}
-
 /// A shape to draw on the PDF.
 ///
 /// This struct is mostly internal and should not be used directly.
@@ -61,7 +58,7 @@ impl Default for ShapeType {
 #[derive(Debug, Default)]
 pub struct Shape<'a> {
     pub enum_type: ShapeType,
-    pub content_stream: Option<&'a mut Vec<u8>>,
+    pub content_stream: Option<&'a mut ContentStream>,
     pub x: Vec<f64>,
     pub y: Vec<f64>,
     pub width: Option<f64>,
@@ -70,92 +67,173 @@ pub struct Shape<'a> {
     pub anchor: Option<Anchor>,
     pub cap_type: Option<CapType>,
     pub color: Option<(f64, f64, f64)>,
+    pub link: Option<Link>,
+    pub links: Option<&'a mut Vec<LinkArea>>,
+    pub bounds: Option<&'a mut Option<[f64; 4]>>,
+    pub tag: Option<Tag>,
+    pub structure: Option<&'a mut StructureTree>,
+    /// Style providing the options not set on the shape.
+    pub style: Style,
+    /// Map from the coordinates of the page to PDF points (see [`Coordinates`](crate::Coordinates)),
+    /// made of a scaling and a translation.
+    pub coordinates: Transform,
+    /// Instances drawn by the batch APIs (e.g. [`Generator::circles`](crate::Generator::circles))
+    /// instead of `x`, `y` and `radius`: `[x1, y1, x2, y2]` for lines, `[x, y, radius, 0]`
+    /// for circles and `[x, y, width, height]` for rectangles.
+    pub batch: Vec<[f64; 4]>,
+    /// Outline of polygons and paths.
+    pub path: Path,
+    pub fill_rule: Option<FillRule>,
+    /// Color of the outline of polygons and paths, which are only filled if `None`.
+    pub stroke: Option<(f64, f64, f64)>,
 }
 
-static DEFAULT_WIDTH: Lazy<Mutex<f64>> = Lazy::new(|| Pt(1.).to_points().into());
-static DEFAULT_CAP_TYPE: Lazy<Mutex<CapType>> = Lazy::new(|| CapType::Butt.into());
-static DEFAULT_COLOR: Lazy<Mutex<(f64, f64, f64)>> =
-    Lazy::new(|| NamedColor("black").to_rgb().into());
-static DEFAULT_ANGLE: Lazy<Mutex<f64>> = Lazy::new(|| Degree(0.).to_degrees().into());
-static DEFAULT_ANCHOR: Lazy<Mutex<Anchor>> = Lazy::new(|| Anchor::SouthWest.into());
-
 impl<'a> Shape<'a> {
-    pub fn draw(&mut self) {
-        if let Some(content) = self.content_stream.as_mut() {
-            let (r, g, b) = self.color.unwrap_or(*DEFAULT_COLOR.lock().unwrap());
-            let width = self.width.unwrap_or(*DEFAULT_WIDTH.lock().unwrap());
-            let cap_type = self
-                .cap_type
-                .unwrap_or(*DEFAULT_CAP_TYPE.lock().unwrap())
-                .to_int();
-            match self.enum_type {
-                ShapeType::Line => {
-                    content.extend_from_slice(format!("{} {} {} RG\n", r, g, b).as_bytes());
-                    content.extend_from_slice(
-                        format!(
-                            "{} w\n{} J\n{} {} m\n{} {} l\nS\n",
-                            width, cap_type, self.x[0], self.y[0], self.x[1], self.y[1]
-                        )
-                        .as_bytes(),
-                    );
-                }
-                ShapeType::Circle => {
-                    content.extend_from_slice(format!("{} {} {} RG\n", r, g, b).as_bytes());
-                    content.extend_from_slice(
-                        // Ref: https://stackoverflow.com/a/46897816/15080514
-                        format!(
-                            "{} w\n1 J\n{} {} m\n{} {} l\nS\n",
-                            self.radius.unwrap() * 2.0,
-                            self.x[0],
-                            self.y[0],
-                            self.x[0],
-                            self.y[0]
-                        )
-                        .as_bytes(),
-                    );
-                }
-                ShapeType::Rectangle => {
-                    content.extend_from_slice(format!("{} {} {} rg\n", r, g, b).as_bytes());
-                    let angle = self.angle.unwrap_or(*DEFAULT_ANGLE.lock().unwrap());
-                    let cos_theta = angle.cos();
-                    let sin_theta = angle.sin();
-                    let (width, height) = (self.x[1], self.y[1]);
-                    // (cx, cy): rotation center
-                    let (cx, cy) = (self.x[0], self.y[0]);
-                    // (x0, y0): south west corner of the rectangle before rotation
-                    let (x0, y0) = match self.anchor.unwrap_or(*DEFAULT_ANCHOR.lock().unwrap()) {
-                        Anchor::Center => (self.x[0] - width / 2.0, self.y[0] - height / 2.0),
-                        Anchor::North => (self.x[0] - width / 2.0, self.y[0] - height),
-                        Anchor::South => (self.x[0] - width / 2.0, self.y[0]),
-                        Anchor::East => (self.x[0] - width, self.y[0] - height / 2.0),
-                        Anchor::West => (self.x[0], self.y[0] - height / 2.0),
-                        Anchor::NorthEast => (self.x[0] - width, self.y[0] - height),
-                        Anchor::NorthWest => (self.x[0], self.y[0] - height),
-                        Anchor::SouthEast => (self.x[0] - width, self.y[0]),
-                        Anchor::SouthWest => (self.x[0], self.y[0]),
-                        Anchor::Point(px, py) => (px, py),
-                    };
-                    let translate_x = cx - cos_theta * cx + sin_theta * cy;
-                    let translate_y = cy - sin_theta * cx - cos_theta * cy;
-                    content.extend_from_slice(
-                        format!(
-                            "{} {} {} {} {} {} cm\n{} {} {} {} re f\n",
-                            cos_theta,
-                            sin_theta,
-                            -sin_theta,
-                            cos_theta,
-                            translate_x,
-                            translate_y,
-                            x0,
-                            y0,
-                            width,
-                            height
-                        )
-                        .as_bytes(),
-                    );
-                }
-                _ => {}
-            };
+    /// Draw the shape, after checking that its geometry and options are valid.
+    ///
+    /// Nothing is drawn if an error is returned.
+    pub fn draw(&mut self) -> Result<(), Error> {
+        let options = self.options();
+        options.validate()?;
+        if let Some(link) = &self.link {
+            link.validate()?;
+        }
+        let placed = place(
+            self.enum_type,
+            &self.x,
+            &self.y,
+            self.radius,
+            &self.batch,
+            &self.path,
+            &options,
+        )?;
+
+        let Some(content) = self.content_stream.as_deref_mut() else {
+            return Ok(());
+        };
+        let marked = match (self.tag.take(), self.structure.as_mut()) {
+            (Some(tag), Some(structure)) => structure.begin(&tag),
+            (None, Some(structure)) => structure.begin_untagged(),
+            _ => None,
+        };
+        if let Some(op) = &marked {
+            content.extend_from_slice(op.as_bytes());
+        }
+        let rects: Vec<[f64; 4]> = match &placed {
+            Placed::Instances(instances) => instances
+                .iter()
+                .filter_map(|instance| {
+                    draw_instance(content, self.enum_type, *instance, &options);
+                    instance_bounds(self.enum_type, *instance, &options)
+                })
+                .collect(),
+            Placed::Path(path) => {
+                draw_path(content, path, &options);
+                path_bounds(path, &options).into_iter().collect()
+            }
+        };
+        for rect in rects {
+            if let Some(bounds) = self.bounds.as_mut() {
+                **bounds = Some(union_rect(**bounds, rect));
+            }
+            if let (Some(link), Some(links)) = (&self.link, self.links.as_mut()) {
+                links.push(LinkArea {
+                    rect,
+                    link: link.clone(),
+                });
+            }
+        }
+        if marked.is_some() {
+            content.extend_from_slice(b"EMC\n");
+        }
+        Ok(())
+    }
+
+    /// Bounding box `[x1, y1, x2, y2]` of the painted area in points, including the
+    /// stroke width, line caps, rotation and anchor.
+    ///
+    /// The box is in PDF points (origin at the bottom-left corner, `y` up) whatever the
+    /// [`Coordinates`](crate::Coordinates) of the page, like the page bounds used to fit
+    /// the media box. Returns `None` if the shape is not valid (see [`draw`](Self::draw)).
+    ///
+    /// # Example
+    /// ```
+    /// use shapdf::*;
+    /// let mut generator = Generator::new("output/bounds.pdf".into());
+    /// generator.add_page();
+    /// let mut label = generator.rectangle(Pt(50.), Pt(50.), Pt(40.), Pt(20.));
+    /// label.with_anchor(Anchor::Center);
+    /// assert_eq!(label.bounds(), Some([30., 40., 70., 60.]));
+    /// assert!(label.contains(Pt(35.), Pt(45.)));
+    /// ```
+    pub fn bounds(&self) -> Option<[f64; 4]> {
+        let options = self.options();
+        options.validate().ok()?;
+        match self.place(&options).ok()? {
+            Placed::Instances(instances) => instances
+                .iter()
+                .filter_map(|instance| instance_bounds(self.enum_type, *instance, &options))
+                .reduce(|acc, rect| union_rect(Some(acc), rect)),
+            Placed::Path(path) => path_bounds(&path, &options),
+        }
+    }
+
+    /// Whether the point `(x, y)` is on the painted area of the shape.
+    ///
+    /// The point is in the [`Coordinates`](crate::Coordinates) of the page, as the shape
+    /// itself, unlike [`bounds`](Self::bounds) which are in PDF points.
+    pub fn contains(&self, x: impl Length, y: impl Length) -> bool {
+        let point = self.coordinates.apply(x.to_points(), y.to_points());
+        let options = self.options();
+        if options.validate().is_err() {
+            return false;
+        }
+        match self.place(&options) {
+            Ok(Placed::Instances(instances)) => instances
+                .iter()
+                .any(|instance| instance_contains(self.enum_type, *instance, &options, point)),
+            Ok(Placed::Path(path)) => path_contains(&path, &options, point),
+            Err(_) => false,
+        }
+    }
+
+    fn place(&self, options: &DrawOptions) -> Result<Placed<'_>, Error> {
+        place(
+            self.enum_type,
+            &self.x,
+            &self.y,
+            self.radius,
+            &self.batch,
+            &self.path,
+            options,
+        )
+    }
+
+    /// Options of the shape, falling back to the style, in PDF points.
+    fn options(&self) -> DrawOptions {
+        let t = self.coordinates;
+        let mut angle = self.angle.unwrap_or(self.style.angle);
+        if t.a * t.d < 0.0 {
+            // Turning from +x towards +y is clockwise on the page
+            angle = -angle;
+        }
+        let anchor = match self.anchor.unwrap_or(self.style.anchor) {
+            Anchor::Point(x, y) => {
+                let (x, y) = t.apply(x, y);
+                Anchor::Point(x, y)
+            }
+            anchor => anchor,
+        };
+        DrawOptions {
+            color: self.color.unwrap_or(self.style.color),
+            width: self.width.unwrap_or(self.style.width),
+            cap_type: self.cap_type.unwrap_or(self.style.cap_type),
+            angle,
+            anchor,
+            decimals: self.style.precision,
+            fill_rule: self.fill_rule.unwrap_or(self.style.fill_rule),
+            stroke: self.stroke,
+            coordinates: t,
         }
     }
 
@@ -185,44 +263,642 @@ impl<'a> Shape<'a> {
         self
     }
 
-    pub fn get_default_width() -> Pt {
-        Pt(*DEFAULT_WIDTH.lock().unwrap())
+    /// Set how the areas enclosed by a polygon or path are filled, e.g. [`FillRule::EvenOdd`]
+    /// to leave holes empty whatever the direction of their outlines.
+    pub fn with_fill_rule(&mut self, fill_rule: FillRule) -> &mut Self {
+        self.fill_rule = Some(fill_rule);
+        self
     }
 
-    pub fn set_default_width(width: impl Length) {
-        *DEFAULT_WIDTH.lock().unwrap() = width.to_points();
+    /// Also stroke the outline of a polygon or path, with the width and cap type of the shape.
+    pub fn with_stroke(&mut self, color: impl Color) -> &mut Self {
+        self.stroke = Some(color.to_rgb());
+        self
     }
 
-    pub fn get_default_cap_type() -> CapType {
-        *DEFAULT_CAP_TYPE.lock().unwrap()
+    /// Make the bounds of the shape a clickable link.
+    pub fn with_link(&mut self, link: Link) -> &mut Self {
+        self.link = Some(link);
+        self
     }
 
-    pub fn set_default_cap_type(cap_type: CapType) {
-        *DEFAULT_CAP_TYPE.lock().unwrap() = cap_type;
+    /// Tag the shape with a role and alternate text for tagged PDF.
+    ///
+    /// The tag is ignored inside a group tagged with [`Generator::tag`](crate::Generator::tag).
+    pub fn with_tag(&mut self, tag: Tag) -> &mut Self {
+        self.tag = Some(tag);
+        self
     }
+}
 
-    pub fn get_default_color() -> Rgb {
-        let (r, g, b) = *DEFAULT_COLOR.lock().unwrap();
-        Rgb(r, g, b)
+/// Options of a shape, resolved against the style.
+struct DrawOptions {
+    color: (f64, f64, f64),
+    width: f64,
+    cap_type: CapType,
+    angle: f64,
+    anchor: Anchor,
+    decimals: u8,
+    fill_rule: FillRule,
+    stroke: Option<(f64, f64, f64)>,
+    coordinates: Transform,
+}
+
+impl DrawOptions {
+    fn validate(&self) -> Result<(), Error> {
+        error::color(self.color)?;
+        if let Some(stroke) = self.stroke {
+            error::color(stroke)?;
+        }
+        error::non_negative("line width", self.width)?;
+        error::finite("angle", self.angle)?;
+        if let Anchor::Point(x, y) = self.anchor {
+            error::finite("anchor x", x)?;
+            error::finite("anchor y", y)?;
+        }
+        Ok(())
     }
+}
+
+/// Checked geometry of a shape in PDF points.
+enum Placed<'b> {
+    /// The batch, or the single geometry in the layout of [`Shape::batch`].
+    Instances(Cow<'b, [[f64; 4]]>),
+    /// Outline of a polygon or path, rotated about its anchor.
+    Path(Path),
+}
 
-    pub fn set_default_color(color: impl Color) {
-        *DEFAULT_COLOR.lock().unwrap() = color.to_rgb();
+fn place<'b>(
+    enum_type: ShapeType,
+    x: &[f64],
+    y: &[f64],
+    radius: Option<f64>,
+    batch: &'b [[f64; 4]],
+    path: &Path,
+    options: &DrawOptions,
+) -> Result<Placed<'b>, Error> {
+    match enum_type {
+        ShapeType::Polygon | ShapeType::Path => place_path(path, options).map(Placed::Path),
+        _ => instances(enum_type, x, y, radius, batch, &options.coordinates).map(Placed::Instances),
     }
+}
 
-    pub fn get_default_angle() -> Degree {
-        Degree(*DEFAULT_ANGLE.lock().unwrap())
+/// Map a path to PDF points and rotate it about its anchor, which is either a point or
+/// taken on its bounding box (in the directions of the page coordinates).
+fn place_path(path: &Path, options: &DrawOptions) -> Result<Path, Error> {
+    path.validate()?;
+    let t = &options.coordinates;
+    let path = if t.is_identity() {
+        path.clone()
+    } else {
+        path.transformed(t)
+    };
+    if options.angle == 0.0 {
+        return Ok(path);
     }
+    let pivot = match options.anchor {
+        Anchor::Point(x, y) => (x, y),
+        anchor => {
+            let points: Vec<(f64, f64)> = path
+                .flatten(FLATTEN_TOLERANCE)
+                .into_iter()
+                .flat_map(|polyline| polyline.points)
+                .collect();
+            let [x1, y1, x2, y2] = bounding_rect(&points, 0.0);
+            // Sides of the box towards the negative and positive axes of the page coordinates
+            let (west, east) = if t.a < 0.0 { (x2, x1) } else { (x1, x2) };
+            let (south, north) = if t.d < 0.0 { (y2, y1) } else { (y1, y2) };
+            let (center_x, center_y) = ((x1 + x2) / 2.0, (y1 + y2) / 2.0);
+            match anchor {
+                Anchor::Center => (center_x, center_y),
+                Anchor::North => (center_x, north),
+                Anchor::South => (center_x, south),
+                Anchor::East => (east, center_y),
+                Anchor::West => (west, center_y),
+                Anchor::NorthEast => (east, north),
+                Anchor::NorthWest => (west, north),
+                Anchor::SouthEast => (east, south),
+                _ => (west, south),
+            }
+        }
+    };
+    let (x, y) = pivot;
+    let rotation = Transform::translate(Pt(-x), Pt(-y))
+        .then(Transform::rotate(Radian(options.angle)))
+        .then(Transform::translate(Pt(x), Pt(y)));
+    Ok(path.transformed(&rotation))
+}
 
-    pub fn set_default_angle(angle: impl Angle) {
-        *DEFAULT_ANGLE.lock().unwrap() = angle.to_radians();
+/// Checked instances of a shape in PDF points: the batch, or the single geometry in the
+/// layout of [`Shape::batch`].
+fn instances<'b>(
+    enum_type: ShapeType,
+    x: &[f64],
+    y: &[f64],
+    radius: Option<f64>,
+    batch: &'b [[f64; 4]],
+    coordinates: &Transform,
+) -> Result<Cow<'b, [[f64; 4]]>, Error> {
+    let instances = if batch.is_empty() {
+        let points = |name| match (x, y) {
+            ([x1, x2, ..], [y1, y2, ..]) => Ok([*x1, *y1, *x2, *y2]),
+            _ => Err(Error::MissingGeometry(name)),
+        };
+        let single = match enum_type {
+            ShapeType::Line => points("line")?,
+            ShapeType::Rectangle => points("rectangle")?,
+            ShapeType::Circle => match (x.first(), y.first(), radius) {
+                (Some(x), Some(y), Some(radius)) => [*x, *y, radius, 0.0],
+                _ => return Err(Error::MissingGeometry("circle")),
+            },
+            _ => [0.0; 4],
+        };
+        Cow::Owned(vec![single])
+    } else {
+        Cow::Borrowed(batch)
+    };
+    let instances = if coordinates.is_identity() {
+        instances
+    } else {
+        instances
+            .iter()
+            .map(|instance| to_page(enum_type, *instance, coordinates))
+            .collect()
+    };
+    for instance in instances.iter() {
+        validate_instance(enum_type, *instance)?;
     }
+    Ok(instances)
+}
 
-    pub fn get_default_anchor() -> Anchor {
-        *DEFAULT_ANCHOR.lock().unwrap()
+/// Map one instance of a shape from page coordinates to PDF points.
+fn to_page(enum_type: ShapeType, [x, y, a, b]: [f64; 4], t: &Transform) -> [f64; 4] {
+    let (px, py) = t.apply(x, y);
+    match enum_type {
+        ShapeType::Line => {
+            let (qx, qy) = t.apply(a, b);
+            [px, py, qx, qy]
+        }
+        // Radii stay in points
+        ShapeType::Circle => [px, py, a, b],
+        _ => [px, py, a * t.a, b * t.d],
+    }
+}
+
+/// Check the geometry of one instance of a shape.
+fn validate_instance(enum_type: ShapeType, [x, y, a, b]: [f64; 4]) -> Result<(), Error> {
+    error::finite("x", x)?;
+    error::finite("y", y)?;
+    match enum_type {
+        ShapeType::Line => {
+            error::finite("x", a)?;
+            error::finite("y", b)?;
+        }
+        ShapeType::Circle => {
+            error::non_negative("radius", a)?;
+        }
+        _ => {
+            error::finite("width", a)?;
+            error::finite("height", b)?;
+        }
     }
+    Ok(())
+}
+
+/// South west corner (before rotation) of a rectangle and the rotation about its anchor point.
+fn rectangle_placement(
+    [x, y, width, height]: [f64; 4],
+    options: &DrawOptions,
+) -> ((f64, f64), Transform) {
+    let (cos_theta, sin_theta) = (options.angle.cos(), options.angle.sin());
+    // (cx, cy): rotation center
+    let (cx, cy) = (x, y);
+    // (x0, y0): south west corner of the rectangle before rotation
+    let origin = match options.anchor {
+        Anchor::Center => (x - width / 2.0, y - height / 2.0),
+        Anchor::North => (x - width / 2.0, y - height),
+        Anchor::South => (x - width / 2.0, y),
+        Anchor::East => (x - width, y - height / 2.0),
+        Anchor::West => (x, y - height / 2.0),
+        Anchor::NorthEast => (x - width, y - height),
+        Anchor::NorthWest => (x, y - height),
+        Anchor::SouthEast => (x - width, y),
+        Anchor::SouthWest => (x, y),
+        Anchor::Point(px, py) => (px, py),
+    };
+    let rotation = Transform {
+        a: cos_theta,
+        b: sin_theta,
+        c: -sin_theta,
+        d: cos_theta,
+        e: cx - cos_theta * cx + sin_theta * cy,
+        f: cy - sin_theta * cx - cos_theta * cy,
+    };
+    (origin, rotation)
+}
 
-    pub fn set_default_anchor(anchor: Anchor) {
-        *DEFAULT_ANCHOR.lock().unwrap() = anchor;
+/// Draw one instance of a shape (see [`Shape::batch`]).
+fn draw_instance(
+    content: &mut ContentStream,
+    enum_type: ShapeType,
+    instance: [f64; 4],
+    options: &DrawOptions,
+) {
+    let decimals = options.decimals;
+    let [x, y, a, b] = instance;
+    match enum_type {
+        ShapeType::Line => {
+            content.stroke_color(options.color);
+            content.line_width(options.width, decimals);
+            content.cap(options.cap_type);
+            content.op(&[x, y], "m", decimals);
+            content.op(&[a, b], "l", decimals);
+            content.extend_from_slice(b"S\n");
+        }
+        ShapeType::Circle => {
+            // Ref: https://stackoverflow.com/a/46897816/15080514
+            content.stroke_color(options.color);
+            content.line_width(a * 2.0, decimals);
+            content.cap(CapType::Round);
+            content.op(&[x, y], "m", decimals);
+            content.op(&[x, y], "l", decimals);
+            content.extend_from_slice(b"S\n");
+        }
+        ShapeType::Rectangle => {
+            let ((x0, y0), t) = rectangle_placement(instance, options);
+            content.fill_color(options.color);
+            if options.angle == 0.0 {
+                content.op(&[x0, y0, a, b], "re f", decimals);
+            } else {
+                // The rotation is scoped with q/Q so that it does not leak to later shapes.
+                content.save();
+                content.transform([t.a, t.b, t.c, t.d, t.e, t.f], decimals);
+                content.op(&[x0, y0, a, b], "re f", decimals);
+                content.restore();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Bounding box of one instance of a shape.
+fn instance_bounds(
+    enum_type: ShapeType,
+    instance: [f64; 4],
+    options: &DrawOptions,
+) -> Option<[f64; 4]> {
+    let [x, y, a, b] = instance;
+    match enum_type {
+        ShapeType::Line => {
+            let half = options.width / 2.0;
+            let (dx, dy) = (a - x, b - y);
+            let length = dx.hypot(dy);
+            match options.cap_type {
+                CapType::Butt | CapType::Square if length > 0.0 => {
+                    // Unit direction and normal, scaled to half the width
+                    let (ux, uy) = (dx / length * half, dy / length * half);
+                    let extend = match options.cap_type {
+                        CapType::Square => 1.0,
+                        _ => 0.0,
+                    };
+                    let (sx, sy) = (x - ux * extend, y - uy * extend);
+                    let (ex, ey) = (a + ux * extend, b + uy * extend);
+                    Some(bounding_rect(
+                        &[
+                            (sx - uy, sy + ux),
+                            (sx + uy, sy - ux),
+                            (ex - uy, ey + ux),
+                            (ex + uy, ey - ux),
+                        ],
+                        0.0,
+                    ))
+                }
+                _ => Some(bounding_rect(&[(x, y), (a, b)], half)),
+            }
+        }
+        ShapeType::Circle => Some(bounding_rect(&[(x, y)], a)),
+        ShapeType::Rectangle => {
+            let ((x0, y0), t) = rectangle_placement(instance, options);
+            Some(t.apply_rect([x0, y0, x0 + a, y0 + b]))
+        }
+        _ => None,
+    }
+}
+
+/// Whether one instance of a shape paints the point `(px, py)`.
+fn instance_contains(
+    enum_type: ShapeType,
+    instance: [f64; 4],
+    options: &DrawOptions,
+    (px, py): (f64, f64),
+) -> bool {
+    let [x, y, a, b] = instance;
+    match enum_type {
+        ShapeType::Line => {
+            let half = options.width / 2.0;
+            let (dx, dy) = (a - x, b - y);
+            let length = dx.hypot(dy);
+            if length == 0.0 {
+                return match options.cap_type {
+                    CapType::Butt => false,
+                    CapType::Round => (px - x).hypot(py - y) <= half,
+                    CapType::Square => (px - x).abs() <= half && (py - y).abs() <= half,
+                };
+            }
+            // Position along the line and distance from it
+            let along = ((px - x) * dx + (py - y) * dy) / length;
+            let across = ((py - y) * dx - (px - x) * dy).abs() / length;
+            match options.cap_type {
+                CapType::Butt => (0.0..=length).contains(&along) && across <= half,
+                CapType::Square => (-half..=length + half).contains(&along) && across <= half,
+                CapType::Round => {
+                    let along = along.clamp(0.0, length);
+                    (px - x - along * dx / length).hypot(py - y - along * dy / length) <= half
+                }
+            }
+        }
+        ShapeType::Circle => (px - x).hypot(py - y) <= a,
+        ShapeType::Rectangle => {
+            let ((x0, y0), t) = rectangle_placement(instance, options);
+            let Some((qx, qy)) = t.inverse().map(|inverse| inverse.apply(px, py)) else {
+                return false;
+            };
+            (x0.min(x0 + a)..=x0.max(x0 + a)).contains(&qx)
+                && (y0.min(y0 + b)..=y0.max(y0 + b)).contains(&qy)
+        }
+        _ => false,
+    }
+}
+
+/// Fill (and stroke) a placed polygon or path.
+fn draw_path(content: &mut ContentStream, path: &Path, options: &DrawOptions) {
+    let decimals = options.decimals;
+    content.fill_color(options.color);
+    if let Some(stroke) = options.stroke {
+        content.stroke_color(stroke);
+        content.line_width(options.width, decimals);
+        content.cap(options.cap_type);
+    }
+    path.write(content, decimals);
+    let operator = match (options.stroke, options.fill_rule) {
+        (None, FillRule::NonZero) => "f",
+        (None, FillRule::EvenOdd) => "f*",
+        (Some(_), FillRule::NonZero) => "B",
+        (Some(_), FillRule::EvenOdd) => "B*",
+    };
+    content.op(&[], operator, decimals);
+}
+
+/// Miter limit of the PDF graphics state, which is left at its default.
+const MITER_LIMIT: f64 = 10.0;
+
+/// Bounding box of a placed polygon or path, including the miter joins and caps of the stroke.
+fn path_bounds(path: &Path, options: &DrawOptions) -> Option<[f64; 4]> {
+    let polylines = path.flatten(FLATTEN_TOLERANCE);
+    let points: Vec<(f64, f64)> = polylines
+        .iter()
+        .flat_map(|polyline| polyline.points.iter().copied())
+        .collect();
+    if points.is_empty() {
+        return None;
+    }
+    if options.stroke.is_none() {
+        return Some(bounding_rect(&points, 0.0));
+    }
+    let half = options.width / 2.0;
+    let margin = match options.cap_type {
+        // Corners of the square caps
+        CapType::Square => half * std::f64::consts::SQRT_2,
+        _ => half,
+    };
+    let tips: Vec<(f64, f64)> = polylines
+        .iter()
+        .flat_map(|polyline| miter_tips(polyline, half))
+        .collect();
+    let rect = bounding_rect(&points, margin);
+    if tips.is_empty() {
+        Some(rect)
+    } else {
+        Some(union_rect(Some(rect), bounding_rect(&tips, 0.0)))
+    }
+}
+
+/// Tips of the miter joins of a stroked polyline that are within the miter limit.
+fn miter_tips(polyline: &Polyline, half: f64) -> Vec<(f64, f64)> {
+    let mut points = polyline.points.clone();
+    points.dedup();
+    if polyline.closed && points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    let n = points.len();
+    if n < 3 {
+        return Vec::new();
+    }
+    let direction = |(x1, y1): (f64, f64), (x2, y2): (f64, f64)| {
+        let length = (x2 - x1).hypot(y2 - y1);
+        ((x2 - x1) / length, (y2 - y1) / length)
+    };
+    let joins = if polyline.closed { 0..n } else { 1..n - 1 };
+    joins
+        .filter_map(|i| {
+            let vertex = points[i];
+            let (ix, iy) = direction(points[(i + n - 1) % n], vertex);
+            let (ox, oy) = direction(vertex, points[(i + 1) % n]);
+            // 1 / sin of half the angle between the segments
+            let ratio = 1.0 / ((1.0 + ix * ox + iy * oy) / 2.0).sqrt();
+            let (bx, by) = (ix - ox, iy - oy);
+            let length = bx.hypot(by);
+            (ratio <= MITER_LIMIT && length > 1e-9).then(|| {
+                let distance = half * ratio;
+                (
+                    vertex.0 + bx / length * distance,
+                    vertex.1 + by / length * distance,
+                )
+            })
+        })
+        .collect()
+}
+
+/// Whether a placed polygon or path paints the point `(px, py)`.
+fn path_contains(path: &Path, options: &DrawOptions, (px, py): (f64, f64)) -> bool {
+    let polylines = path.flatten(FLATTEN_TOLERANCE);
+    if options
+        .fill_rule
+        .is_inside(path::winding(&polylines, (px, py)))
+    {
+        return true;
+    }
+    if options.stroke.is_none() {
+        return false;
+    }
+    let half = options.width / 2.0;
+    polylines.iter().any(|polyline| {
+        let points = &polyline.points;
+        let edges = if polyline.closed {
+            points.len()
+        } else {
+            points.len().saturating_sub(1)
+        };
+        (0..edges.max(1)).any(|i| {
+            let (x, y) = points[i];
+            let (a, b) = points[(i + 1) % points.len()];
+            let (dx, dy) = (a - x, b - y);
+            let length_squared = dx * dx + dy * dy;
+            let along = if length_squared > 0.0 {
+                (((px - x) * dx + (py - y) * dy) / length_squared).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            (px - x - along * dx).hypot(py - y - along * dy) <= half
+        })
+    })
+}
+
+/// Smallest rectangle containing both `acc` (if any) and `rect`.
+pub(crate) fn union_rect(acc: Option<[f64; 4]>, rect: [f64; 4]) -> [f64; 4] {
+    match acc {
+        Some(acc) => [
+            acc[0].min(rect[0]),
+            acc[1].min(rect[1]),
+            acc[2].max(rect[2]),
+            acc[3].max(rect[3]),
+        ],
+        None => rect,
+    }
+}
+
+/// Axis-aligned `[x1, y1, x2, y2]` rectangle around the points, grown by `margin`.
+pub(crate) fn bounding_rect(points: &[(f64, f64)], margin: f64) -> [f64; 4] {
+    let mut rect = [f64::MAX, f64::MAX, f64::MIN, f64::MIN];
+    for (x, y) in points {
+        rect = [
+            rect[0].min(*x),
+            rect[1].min(*y),
+            rect[2].max(*x),
+            rect[3].max(*y),
+        ];
+    }
+    [
+        rect[0] - margin,
+        rect[1] - margin,
+        rect[2] + margin,
+        rect[3] + margin,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(cap_type: CapType) -> Shape<'static> {
+        Shape {
+            enum_type: ShapeType::Line,
+            x: vec![0., 10.],
+            y: vec![0., 0.],
+            width: Some(2.),
+            cap_type: Some(cap_type),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn bounds_include_caps_rotation_and_anchor() {
+        assert_eq!(line(CapType::Butt).bounds(), Some([0., -1., 10., 1.]));
+        assert_eq!(line(CapType::Square).bounds(), Some([-1., -1., 11., 1.]));
+        assert_eq!(line(CapType::Round).bounds(), Some([-1., -1., 11., 1.]));
+
+        let mut rectangle = Shape {
+            enum_type: ShapeType::Rectangle,
+            x: vec![10., 4.],
+            y: vec![10., 2.],
+            ..Default::default()
+        };
+        rectangle.with_anchor(Anchor::NorthEast);
+        assert_eq!(rectangle.bounds(), Some([6., 8., 10., 10.]));
+        rectangle.with_angle(Degree(90.));
+        let [x1, y1, x2, y2] = rectangle.bounds().unwrap();
+        for (value, expected) in [(x1, 10.), (y1, 6.), (x2, 12.), (y2, 10.)] {
+            assert!((value - expected).abs() < 1e-9);
+        }
+        rectangle.x[0] = f64::NAN;
+        assert_eq!(rectangle.bounds(), None);
+    }
+
+    #[test]
+    fn points_hit_the_painted_area() {
+        assert!(line(CapType::Butt).contains(Pt(5.), Pt(0.9)));
+        assert!(!line(CapType::Butt).contains(Pt(-0.5), Pt(0.)));
+        assert!(line(CapType::Square).contains(Pt(-0.5), Pt(0.9)));
+        assert!(line(CapType::Round).contains(Pt(-0.5), Pt(0.5)));
+        assert!(!line(CapType::Round).contains(Pt(-0.9), Pt(0.9)));
+
+        let mut rectangle = Shape {
+            enum_type: ShapeType::Rectangle,
+            x: vec![0., 10.],
+            y: vec![0., 2.],
+            ..Default::default()
+        };
+        rectangle.with_angle(Degree(90.));
+        assert!(rectangle.contains(Pt(-1.), Pt(9.)));
+        assert!(!rectangle.contains(Pt(9.), Pt(1.)));
+
+        let circles = Shape {
+            enum_type: ShapeType::Circle,
+            batch: vec![[0., 0., 1., 0.], [10., 0., 2., 0.]],
+            ..Default::default()
+        };
+        assert!(circles.contains(Pt(11.5), Pt(0.)));
+        assert!(!circles.contains(Pt(5.), Pt(0.)));
+        assert_eq!(circles.bounds(), Some([-1., -2., 12., 2.]));
+    }
+
+    #[test]
+    fn bounds_are_in_pdf_points_and_points_in_page_coordinates() {
+        let rectangle = Shape {
+            enum_type: ShapeType::Rectangle,
+            x: vec![10., 20.],
+            y: vec![10., 5.],
+            coordinates: crate::page::Coordinates::TopLeft.to_page((100., 100.)),
+            ..Default::default()
+        };
+        assert_eq!(rectangle.bounds(), Some([10., 85., 30., 90.]));
+        assert!(rectangle.contains(Pt(15.), Pt(12.)));
+        assert!(!rectangle.contains(Pt(15.), Pt(87.)));
+    }
+
+    #[test]
+    fn paths_follow_the_fill_rule_and_stroke() {
+        let square = |x: f64, size: f64| Path::rectangle(Pt(x), Pt(x), Pt(size), Pt(size));
+        let mut ring = Shape {
+            enum_type: ShapeType::Path,
+            path: square(0., 10.).append(square(3., 4.)),
+            ..Default::default()
+        };
+        assert!(ring.contains(Pt(5.), Pt(5.)));
+        ring.with_fill_rule(FillRule::EvenOdd);
+        assert!(!ring.contains(Pt(5.), Pt(5.)));
+        assert!(ring.contains(Pt(1.), Pt(5.)));
+        assert_eq!(ring.bounds(), Some([0., 0., 10., 10.]));
+
+        // Miter joins reach the corners of the stroke
+        ring.with_stroke(Rgb(1., 0., 0.)).with_width(Pt(2.));
+        let [x1, y1, x2, y2] = ring.bounds().unwrap();
+        for (value, expected) in [(x1, -1.), (y1, -1.), (x2, 11.), (y2, 11.)] {
+            assert!((value - expected).abs() < 1e-9);
+        }
+        assert!(ring.contains(Pt(3.5), Pt(5.)));
+
+        let mut triangle = Shape {
+            enum_type: ShapeType::Polygon,
+            path: Path::polygon(&[(Pt(0.), Pt(0.)), (Pt(4.), Pt(0.)), (Pt(0.), Pt(2.))]),
+            ..Default::default()
+        };
+        triangle.with_angle(Degree(90.)).with_anchor(Anchor::Center);
+        let [x1, y1, x2, y2] = triangle.bounds().unwrap();
+        for (value, expected) in [(x1, 1.), (y1, -1.), (x2, 3.), (y2, 3.)] {
+            assert!((value - expected).abs() < 1e-9);
+        }
+        triangle.path = Path::new();
+        assert!(triangle.draw().is_err());
     }
 }
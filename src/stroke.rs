@@ -0,0 +1,460 @@
+use crate::boolean::PathOp;
+use crate::error::{self, Error};
+use crate::path::{FillRule, Path, Polyline, FLATTEN_TOLERANCE};
+use crate::shapes::CapType;
+use crate::units::{Length, Pt};
+
+type Point = (f64, f64);
+
+/// Shape of the corners where the segments of a stroked path meet.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum LineJoin {
+    /// Sharp corners, beveled beyond the miter limit.
+    #[default]
+    Miter,
+    Round,
+    Bevel,
+}
+
+/// Parameters of a stroke, to convert it to a filled outline with [`Path::stroke_outline`].
+///
+/// The defaults match those of PDF: butt caps and miter joins with a miter limit of 10.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stroke {
+    /// Line width in points.
+    pub width: f64,
+    pub cap_type: CapType,
+    pub join: LineJoin,
+    /// Longest miter relative to the line width; longer miters are beveled.
+    pub miter_limit: f64,
+    /// Lengths in points of the dashes and gaps repeated along the path (solid if empty).
+    pub dash: Vec<f64>,
+    /// Distance in points into the dash pattern at which the path starts.
+    pub dash_offset: f64,
+}
+
+impl Stroke {
+    pub fn new(width: impl Length) -> Self {
+        Self {
+            width: width.to_points(),
+            cap_type: CapType::Butt,
+            join: LineJoin::Miter,
+            miter_limit: 10.0,
+            dash: Vec::new(),
+            dash_offset: 0.0,
+        }
+    }
+
+    pub fn with_cap_type(mut self, cap_type: CapType) -> Self {
+        self.cap_type = cap_type;
+        self
+    }
+
+    pub fn with_join(mut self, join: LineJoin) -> Self {
+        self.join = join;
+        self
+    }
+
+    pub fn with_miter_limit(mut self, miter_limit: f64) -> Self {
+        self.miter_limit = miter_limit;
+        self
+    }
+
+    /// Dash the stroke with the lengths of the dashes and gaps, starting `offset` into the pattern.
+    pub fn with_dash<L: Length>(mut self, dash: &[L], offset: impl Length) -> Self {
+        self.dash = dash.iter().map(|length| length.to_points()).collect();
+        self.dash_offset = offset.to_points();
+        self
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        error::non_negative("line width", self.width)?;
+        error::finite("miter limit", self.miter_limit)?;
+        error::finite("dash offset", self.dash_offset)?;
+        for length in &self.dash {
+            error::non_negative("dash length", *length)?;
+        }
+        Ok(())
+    }
+}
+
+impl Path {
+    /// Straight line from `(x1, y1)` to `(x2, y2)`, e.g. to convert a stroked line to an outline.
+    pub fn line(x1: impl Length, y1: impl Length, x2: impl Length, y2: impl Length) -> Self {
+        Self::new().move_to(x1, y1).line_to(x2, y2)
+    }
+
+    /// Filled outline of the area painted by stroking the path, e.g. for cutting
+    /// machines that do not accept strokes.
+    ///
+    /// Curves are approximated by lines; the outline does not overlap itself (see
+    /// [`boolean`](Self::boolean)). A stroke of zero width paints nothing, so its
+    /// outline is empty and drawing it fails with [`Error::MissingGeometry`].
+    ///
+    /// # Example
+    /// ```
+    /// use shapdf::*;
+    /// let mut generator = Generator::new("output/outline.pdf".into());
+    /// generator.add_page();
+    /// let stroke = Stroke::new(Mm(2.))
+    ///     .with_cap_type(CapType::Round)
+    ///     .with_join(LineJoin::Round)
+    ///     .with_dash(&[Mm(6.), Mm(3.)], Mm(0.));
+    /// let wave = Path::new()
+    ///     .move_to(Mm(20.), Mm(50.))
+    ///     .curve_to(Mm(40.), Mm(80.), Mm(60.), Mm(20.), Mm(80.), Mm(50.));
+    /// let outline = wave.stroke_outline(&stroke).unwrap();
+    /// generator.path(outline).draw().unwrap();
+    /// ```
+    pub fn stroke_outline(&self, stroke: &Stroke) -> Result<Path, Error> {
+        stroke.validate()?;
+        self.validate()?;
+        Ok(stroke_polylines(self.flatten(FLATTEN_TOLERANCE), stroke))
+    }
+
+    /// Outline of the area of the path grown by `distance` on every side, or shrunk
+    /// if `distance` is negative, with corners shaped by `join` (e.g. engraving margins).
+    ///
+    /// Open subpaths are closed, as when filling.
+    pub fn offset(&self, distance: impl Length, join: LineJoin) -> Result<Path, Error> {
+        let distance = distance.to_points();
+        error::finite("offset", distance)?;
+        self.validate()?;
+        let mut polylines = self.flatten(FLATTEN_TOLERANCE);
+        for polyline in &mut polylines {
+            polyline.closed = true;
+        }
+        let band = stroke_polylines(
+            polylines,
+            &Stroke::new(Pt(2.0 * distance.abs())).with_join(join),
+        );
+        let op = if distance < 0.0 {
+            PathOp::Difference
+        } else {
+            PathOp::Union
+        };
+        Ok(self.boolean(&band, op, FillRule::NonZero))
+    }
+}
+
+/// Outline of the stroke of the polylines, without overlaps.
+fn stroke_polylines(polylines: Vec<Polyline>, stroke: &Stroke) -> Path {
+    let pieces = if dashed(stroke) {
+        polylines
+            .iter()
+            .flat_map(|polyline| dash(polyline, &stroke.dash, stroke.dash_offset))
+            .collect()
+    } else {
+        polylines
+    };
+    // Rings whose nonzero fill is the stroke: each part of the stroke winds clockwise
+    let rings = pieces
+        .into_iter()
+        .flat_map(|polyline| stroke_rings(polyline, stroke));
+    let path = rings.fold(Path::new(), |path, ring| {
+        let points: Vec<(Pt, Pt)> = ring.into_iter().map(|(x, y)| (Pt(x), Pt(y))).collect();
+        path.append(Path::polygon(&points))
+    });
+    path.boolean(&Path::new(), PathOp::Union, FillRule::NonZero)
+}
+
+fn dashed(stroke: &Stroke) -> bool {
+    stroke.dash.iter().sum::<f64>() > 0.0
+}
+
+/// Split a polyline into the dashes of the pattern (which has a positive length).
+fn dash(polyline: &Polyline, pattern: &[f64], offset: f64) -> Vec<Polyline> {
+    // An odd number of lengths alternates between dashes and gaps when repeated
+    let pattern: Vec<f64> = match pattern.len() % 2 {
+        0 => pattern.to_vec(),
+        _ => pattern.iter().chain(pattern).copied().collect(),
+    };
+    let mut points = polyline.points.clone();
+    if polyline.closed {
+        points.extend(points.first().copied());
+    }
+    let Some(&first) = points.first() else {
+        return Vec::new();
+    };
+
+    let mut index = 0;
+    let mut remaining = pattern[0];
+    let mut skip = offset.rem_euclid(pattern.iter().sum());
+    while skip > remaining {
+        skip -= remaining;
+        index = (index + 1) % pattern.len();
+        remaining = pattern[index];
+    }
+    remaining -= skip;
+
+    let mut dashes = Vec::new();
+    let mut current = vec![first];
+    for pair in points.windows(2) {
+        let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+        let length = (x2 - x1).hypot(y2 - y1);
+        let mut along = 0.0;
+        // Switch between dashes and gaps within the segment
+        while length - along > remaining {
+            along += remaining;
+            let t = along / length;
+            let point = (x1 + t * (x2 - x1), y1 + t * (y2 - y1));
+            if index % 2 == 0 {
+                current.push(point);
+                dashes.push(Polyline {
+                    points: std::mem::take(&mut current),
+                    closed: false,
+                });
+            } else {
+                current = vec![point];
+            }
+            index = (index + 1) % pattern.len();
+            remaining = pattern[index];
+        }
+        remaining -= length - along;
+        if index % 2 == 0 {
+            current.push((x2, y2));
+        }
+    }
+    if index % 2 == 0 && !current.is_empty() {
+        dashes.push(Polyline {
+            points: current,
+            closed: false,
+        });
+    }
+    dashes
+}
+
+/// Rings whose nonzero fill is the stroke of one polyline.
+///
+/// The sides of the stroke are offset by half the width and meet through the vertices
+/// on the inside of the corners, so that overlapping parts add up instead of cancelling.
+fn stroke_rings(polyline: Polyline, stroke: &Stroke) -> Vec<Vec<Point>> {
+    let half = stroke.width / 2.0;
+    let mut points = polyline.points;
+    points.dedup();
+    if polyline.closed && points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    if half == 0.0 || points.is_empty() {
+        return Vec::new();
+    }
+    if points.len() == 1 {
+        return dot(points[0], half, stroke.cap_type).into_iter().collect();
+    }
+
+    let n = points.len();
+    let segments = if polyline.closed { n } else { n - 1 };
+    // Unit directions and left normals of the segments
+    let directions: Vec<Point> = (0..segments)
+        .map(|i| {
+            let ((x1, y1), (x2, y2)) = (points[i], points[(i + 1) % n]);
+            let length = (x2 - x1).hypot(y2 - y1);
+            ((x2 - x1) / length, (y2 - y1) / length)
+        })
+        .collect();
+    let normal = |i: usize| {
+        let (dx, dy) = directions[i];
+        (-dy, dx)
+    };
+    let at = |(x, y): Point, (nx, ny): Point, scale: f64| (x + nx * scale, y + ny * scale);
+
+    // Corner at `vertex` between the normals, on the side given by `sign` (1 for left)
+    let corner = |ring: &mut Vec<Point>, vertex: Point, from: Point, to: Point, sign: f64| {
+        let (from, to) = ((from.0 * sign, from.1 * sign), (to.0 * sign, to.1 * sign));
+        ring.push(at(vertex, from, half));
+        // The side turns clockwise on the outside of the corner
+        let turn = from.0 * to.1 - from.1 * to.0;
+        if turn < 0.0 {
+            ring.extend(join(vertex, from, to, half, stroke));
+        } else if turn > 0.0 {
+            ring.push(vertex);
+        }
+        ring.push(at(vertex, to, half));
+    };
+
+    if polyline.closed {
+        let mut left = Vec::new();
+        for (k, &vertex) in points.iter().enumerate() {
+            corner(&mut left, vertex, normal((k + n - 1) % n), normal(k), 1.0);
+        }
+        let mut right = Vec::new();
+        for k in (0..n).rev() {
+            corner(
+                &mut right,
+                points[k],
+                normal(k),
+                normal((k + n - 1) % n),
+                -1.0,
+            );
+        }
+        return vec![left, right];
+    }
+
+    let mut ring = vec![at(points[0], normal(0), half)];
+    for (k, &vertex) in points.iter().enumerate().take(n - 1).skip(1) {
+        corner(&mut ring, vertex, normal(k - 1), normal(k), 1.0);
+    }
+    let last = segments - 1;
+    ring.push(at(points[n - 1], normal(last), half));
+    ring.extend(cap(points[n - 1], normal(last), half, stroke.cap_type));
+    ring.push(at(points[n - 1], normal(last), -half));
+    for k in (1..n - 1).rev() {
+        corner(&mut ring, points[k], normal(k), normal(k - 1), -1.0);
+    }
+    ring.push(at(points[0], normal(0), -half));
+    let (nx, ny) = normal(0);
+    ring.extend(cap(points[0], (-nx, -ny), half, stroke.cap_type));
+    vec![ring]
+}
+
+/// Points of the outside of a corner, between the offset points along the normals.
+fn join(vertex: Point, from: Point, to: Point, half: f64, stroke: &Stroke) -> Vec<Point> {
+    match stroke.join {
+        LineJoin::Miter => {
+            let sum = (from.0 + to.0, from.1 + to.1);
+            let length = sum.0.hypot(sum.1);
+            // Ratio of the miter length to the line width
+            if length > 0.0 && 2.0 / length <= stroke.miter_limit.max(1.0) {
+                let scale = 2.0 * half / (length * length);
+                vec![(vertex.0 + sum.0 * scale, vertex.1 + sum.1 * scale)]
+            } else {
+                Vec::new()
+            }
+        }
+        LineJoin::Round => {
+            let sweep = (from.1.atan2(from.0) - to.1.atan2(to.0)).rem_euclid(std::f64::consts::TAU);
+            arc(vertex, from, sweep, half)
+        }
+        LineJoin::Bevel => Vec::new(),
+    }
+}
+
+/// Points of a cap at the end of a stroke, from the side along `normal` to the other side.
+fn cap(end: Point, normal: Point, half: f64, cap_type: CapType) -> Vec<Point> {
+    // Direction of the stroke at the end
+    let (dx, dy) = (normal.1, -normal.0);
+    match cap_type {
+        CapType::Butt => Vec::new(),
+        CapType::Round => arc(end, normal, std::f64::consts::PI, half),
+        CapType::Square => vec![
+            (
+                end.0 + (normal.0 + dx) * half,
+                end.1 + (normal.1 + dy) * half,
+            ),
+            (
+                end.0 + (dx - normal.0) * half,
+                end.1 + (dy - normal.1) * half,
+            ),
+        ],
+    }
+}
+
+/// Stroke of a zero-length subpath, which only round and square caps paint.
+fn dot(center: Point, half: f64, cap_type: CapType) -> Option<Vec<Point>> {
+    let (x, y) = center;
+    match cap_type {
+        CapType::Butt => None,
+        CapType::Round => {
+            let mut ring = vec![(x + half, y)];
+            ring.extend(arc(center, (1.0, 0.0), std::f64::consts::TAU, half));
+            Some(ring)
+        }
+        CapType::Square => Some(vec![
+            (x - half, y - half),
+            (x - half, y + half),
+            (x + half, y + half),
+            (x + half, y - half),
+        ]),
+    }
+}
+
+/// Points strictly inside a clockwise arc of `sweep` radians around `center`, starting
+/// along the unit vector `from`.
+fn arc(center: Point, from: Point, sweep: f64, radius: f64) -> Vec<Point> {
+    // Largest step keeping the chords within the tolerance of the arc
+    let step = 2.0 * (1.0 - (FLATTEN_TOLERANCE / radius).min(1.0)).acos();
+    let count = (sweep / step).ceil().max(1.0) as usize;
+    let start = from.1.atan2(from.0);
+    (1..count)
+        .map(|i| {
+            let angle = start - sweep * i as f64 / count as f64;
+            (
+                center.0 + radius * angle.cos(),
+                center.1 + radius * angle.sin(),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::{area, winding};
+
+    fn close(value: f64, expected: f64, tolerance: f64) -> bool {
+        (value - expected).abs() < tolerance
+    }
+
+    #[test]
+    fn strokes_follow_caps_joins_and_dashes() {
+        let line = Path::line(Pt(0.), Pt(0.), Pt(10.), Pt(0.));
+        let outline = |stroke: Stroke| area(&line.stroke_outline(&stroke).unwrap());
+        assert!(close(outline(Stroke::new(Pt(2.))), 20., 1e-9));
+        let square = Stroke::new(Pt(2.)).with_cap_type(CapType::Square);
+        assert!(close(outline(square), 24., 1e-9));
+        let round = Stroke::new(Pt(2.)).with_cap_type(CapType::Round);
+        assert!(close(outline(round), 20. + std::f64::consts::PI, 0.05));
+        // Dashes of 2 with gaps of 1: 0..2, 3..5, 6..8 and 9..10
+        let dashed = Stroke::new(Pt(2.)).with_dash(&[Pt(2.), Pt(1.)], Pt(0.));
+        let dashes = line.stroke_outline(&dashed).unwrap();
+        assert!(close(area(&dashes), 14., 1e-9));
+        assert_eq!(dashes.segments().len(), 4 * 5);
+
+        // A right angle: the miter fills the outer corner, the bevel cuts it in half
+        let corner = Path::new()
+            .move_to(Pt(0.), Pt(0.))
+            .line_to(Pt(10.), Pt(0.))
+            .line_to(Pt(10.), Pt(10.));
+        let stroke = Stroke::new(Pt(2.));
+        let miter = corner.stroke_outline(&stroke).unwrap();
+        assert!(close(area(&miter), 40., 1e-9));
+        let bevel = corner
+            .stroke_outline(&stroke.clone().with_join(LineJoin::Bevel))
+            .unwrap();
+        assert!(close(area(&bevel), 39.5, 1e-9));
+        let beveled_miter = corner
+            .stroke_outline(&stroke.with_miter_limit(1.2))
+            .unwrap();
+        assert!(close(area(&beveled_miter), 39.5, 1e-9));
+        assert!(Path::line(Pt(0.), Pt(0.), Pt(1.), Pt(1.))
+            .stroke_outline(&Stroke::new(Pt(-1.)))
+            .is_err());
+        assert!(line
+            .stroke_outline(&Stroke::new(Pt(0.)))
+            .unwrap()
+            .segments()
+            .is_empty());
+    }
+
+    #[test]
+    fn closed_paths_offset_in_and_out() {
+        let square = Path::rectangle(Pt(0.), Pt(0.), Pt(10.), Pt(10.));
+        let outset = square.offset(Pt(1.), LineJoin::Miter).unwrap();
+        assert!(close(area(&outset), 144., 1e-9));
+        let rounded = square.offset(Pt(1.), LineJoin::Round).unwrap();
+        assert!(close(area(&rounded), 140. + std::f64::consts::PI, 0.05));
+        let inset = square.offset(Pt(-1.), LineJoin::Round).unwrap();
+        assert!(close(area(&inset), 64., 1e-9));
+
+        // Stroking a closed path leaves its inside empty
+        let frame = square.stroke_outline(&Stroke::new(Pt(2.))).unwrap();
+        assert!(close(area(&frame), 144. - 64., 1e-9));
+        let polylines = frame.flatten(FLATTEN_TOLERANCE);
+        assert_eq!(winding(&polylines, (5., 5.)), 0);
+        assert_eq!(winding(&polylines, (0., 5.)), 1);
+
+        let circle = Path::circle(Pt(0.), Pt(0.), Pt(10.));
+        let ring = circle.offset(Pt(-2.), LineJoin::Round).unwrap();
+        assert!(close(area(&ring), 64. * std::f64::consts::PI, 0.5));
+    }
+}
@@ -0,0 +1,41 @@
+use crate::number::DEFAULT_PRECISION;
+use crate::path::FillRule;
+use crate::shapes::{Anchor, CapType};
+use crate::units::*;
+
+/// Default drawing style of a [`Generator`](crate::Generator), used for options not set on a shape.
+///
+/// Each generator has its own style, so documents generated in the same process
+/// (or concurrently) do not affect each other.
+#[derive(Debug, Copy, Clone)]
+pub struct Style {
+    /// Line width in points.
+    pub width: f64,
+    pub cap_type: CapType,
+    /// RGB color with components in `[0, 1]`.
+    pub color: (f64, f64, f64),
+    /// Rotation angle of rectangles in radians.
+    pub angle: f64,
+    pub anchor: Anchor,
+    /// Fill rule of polygons and paths.
+    pub fill_rule: FillRule,
+    /// Width and height in points of pages added with [`add_page`](crate::Generator::add_page).
+    pub page_size: (f64, f64),
+    /// Decimal places of coordinates and lengths written to the PDF.
+    pub precision: u8,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            width: Pt(1.).to_points(),
+            cap_type: CapType::Butt,
+            color: NamedColor("black").to_rgb(),
+            angle: Degree(0.).to_radians(),
+            anchor: Anchor::SouthWest,
+            fill_rule: FillRule::NonZero,
+            page_size: (Inch(8.5).to_points(), Inch(11.0).to_points()),
+            precision: DEFAULT_PRECISION,
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use std::collections::BTreeSet;
+
+/// Handle to a reusable symbol defined with [`Generator::define_symbol`](crate::Generator::define_symbol).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SymbolId(pub(crate) usize);
+
+impl SymbolId {
+    /// Resource name of the symbol's Form XObject.
+    pub(crate) fn name(&self) -> String {
+        format!("S{}", self.0)
+    }
+}
+
+/// A group of shapes written once as a Form XObject.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Symbol {
+    pub obj: usize,
+    /// Bounding box `[x1, y1, x2, y2]` of the symbol content in points.
+    pub bbox: [f64; 4],
+}
+
+/// `/XObject` resource entry referencing the Form XObjects of the used symbols, if any.
+pub(crate) fn xobject_entry(used: &BTreeSet<SymbolId>, symbols: &[Symbol]) -> Option<String> {
+    if used.is_empty() {
+        return None;
+    }
+    let xobjects: Vec<String> = used
+        .iter()
+        .map(|id| format!("/{} {} 0 R", id.name(), symbols[id.0].obj))
+        .collect();
+    Some(format!("/XObject << {} >>", xobjects.join(" ")))
+}
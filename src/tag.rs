@@ -0,0 +1,361 @@
+use crate::generator::Generator;
+use crate::metadata::encode_text_string;
+use std::io::Write;
+use std::ops::{Deref, DerefMut};
+
+/// Semantic role of tagged content.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Role {
+    /// Graphic content such as a chart or a diagram (should have alternate text).
+    Figure,
+    /// Mathematical formula (should have alternate text).
+    Formula,
+    /// Decoration or layout content (e.g. grid lines, borders) skipped by assistive technology.
+    Artifact,
+}
+
+impl Role {
+    /// Structure type of the role, or `None` for artifacts.
+    fn structure_type(&self) -> Option<&'static str> {
+        match self {
+            Role::Figure => Some("Figure"),
+            Role::Formula => Some("Formula"),
+            Role::Artifact => None,
+        }
+    }
+}
+
+/// Role and alternate text attached to a shape or a group of shapes.
+///
+/// # Example
+/// ```
+/// use shapdf::{Role, Tag};
+/// let chart = Tag::new(Role::Figure).with_alt("Quarterly revenue, rising from 2 to 5 million");
+/// let grid = Tag::artifact();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tag {
+    pub role: Role,
+    /// Alternate text read by assistive technology.
+    pub alt: Option<String>,
+}
+
+impl Tag {
+    pub fn new(role: Role) -> Self {
+        Self { role, alt: None }
+    }
+
+    pub fn figure(alt: impl Into<String>) -> Self {
+        Self::new(Role::Figure).with_alt(alt)
+    }
+
+    pub fn artifact() -> Self {
+        Self::new(Role::Artifact)
+    }
+
+    pub fn with_alt(mut self, alt: impl Into<String>) -> Self {
+        self.alt = Some(alt.into());
+        self
+    }
+}
+
+/// A structure element and its marked-content sequences `(page, MCID)`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct StructElem {
+    pub tag: Tag,
+    pub parts: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum OpenTag {
+    Element(usize),
+    Artifact,
+    /// Nested in another tagged group, which it is part of.
+    Ignored,
+}
+
+/// Structure elements and marked content of a tagged document.
+///
+/// This struct is mostly internal; content is tagged with
+/// [`Shape::with_tag`](crate::Shape::with_tag) and [`Generator::tag`].
+#[derive(Debug, Default)]
+pub struct StructureTree {
+    pub(crate) elements: Vec<StructElem>,
+    /// Structure element of each MCID of the current page.
+    pub(crate) page_mcids: Vec<usize>,
+    /// Index ([`PageId`](crate::PageId)) of the current page.
+    pub(crate) page: usize,
+    /// Whether any content was tagged (including artifacts).
+    pub(crate) used: bool,
+    /// Whether tags are ignored (e.g. while drawing a symbol).
+    pub(crate) suspended: bool,
+    open: Vec<OpenTag>,
+}
+
+impl StructureTree {
+    /// Operator starting the marked content of `tag`, or `None` inside a tagged group.
+    pub(crate) fn begin(&mut self, tag: &Tag) -> Option<String> {
+        self.begin_open(tag).1
+    }
+
+    fn begin_open(&mut self, tag: &Tag) -> (OpenTag, Option<String>) {
+        if self.suspended || self.open.iter().any(|open| *open != OpenTag::Ignored) {
+            return (OpenTag::Ignored, None);
+        }
+        self.used = true;
+        match tag.role.structure_type() {
+            Some(role) => {
+                let mcid = self.page_mcids.len();
+                self.elements.push(StructElem {
+                    tag: tag.clone(),
+                    parts: vec![(self.page, mcid)],
+                });
+                self.page_mcids.push(self.elements.len() - 1);
+                (
+                    OpenTag::Element(self.elements.len() - 1),
+                    Some(format!("/{} <</MCID {}>> BDC\n", role, mcid)),
+                )
+            }
+            None => (OpenTag::Artifact, Some("/Artifact BMC\n".to_string())),
+        }
+    }
+
+    /// Operator marking untagged content as an artifact, once the document is tagged and
+    /// outside of tagged groups.
+    pub(crate) fn begin_untagged(&self) -> Option<String> {
+        let outside = self.open.iter().all(|open| *open == OpenTag::Ignored);
+        (self.used && !self.suspended && outside).then(|| "/Artifact BMC\n".to_string())
+    }
+
+    /// Start a tagged group, returning the operator to write (if any).
+    pub(crate) fn open(&mut self, tag: &Tag) -> Option<String> {
+        let (open, op) = self.begin_open(tag);
+        self.open.push(open);
+        op
+    }
+
+    /// End the innermost tagged group, returning whether `EMC` must be written.
+    pub(crate) fn close(&mut self) -> bool {
+        matches!(
+            self.open.pop(),
+            Some(OpenTag::Element(_)) | Some(OpenTag::Artifact)
+        )
+    }
+
+    /// Operators ending the open groups when leaving a page.
+    pub(crate) fn leave_page(&self) -> String {
+        self.open
+            .iter()
+            .filter(|open| **open != OpenTag::Ignored)
+            .map(|_| "EMC\n")
+            .collect()
+    }
+
+    /// Number of open tagged groups, including those nested in another group.
+    pub(crate) fn depth(&self) -> usize {
+        self.open.len()
+    }
+
+    /// Operators continuing each open group on `page` (empty for nested groups), whose
+    /// MCIDs are in `page_mcids`.
+    pub(crate) fn enter_page(&mut self, page: usize) -> Vec<String> {
+        self.page = page;
+        let mut ops = Vec::new();
+        for open in self.open.clone() {
+            ops.push(match open {
+                OpenTag::Element(idx) => {
+                    let mcid = self.page_mcids.len();
+                    self.page_mcids.push(idx);
+                    self.elements[idx].parts.push((page, mcid));
+                    let role = self.elements[idx]
+                        .tag
+                        .role
+                        .structure_type()
+                        .unwrap_or("Span");
+                    format!("/{} <</MCID {}>> BDC\n", role, mcid)
+                }
+                OpenTag::Artifact => "/Artifact BMC\n".to_string(),
+                OpenTag::Ignored => String::new(),
+            });
+        }
+        ops
+    }
+
+    /// Structure tree objects numbered consecutively from `first_obj`: the root,
+    /// the `Document` element and one element per tagged shape or group.
+    ///
+    /// `page_objs` maps page indices to object numbers (`None` for deleted pages),
+    /// `page_positions` to positions in the document, and `parent_tree` lists the MCID
+    /// elements of each page with tagged content, by page index.
+    pub(crate) fn objects(
+        &self,
+        first_obj: usize,
+        page_objs: &[Option<usize>],
+        page_positions: &[usize],
+        parent_tree: &[(usize, &[usize])],
+    ) -> Vec<String> {
+        let doc_obj = first_obj + 1;
+        let elem_obj = |idx: usize| doc_obj + 1 + idx;
+        let refs = |objs: &mut dyn Iterator<Item = usize>| -> String {
+            objs.map(|obj| format!("{} 0 R", obj))
+                .collect::<Vec<String>>()
+                .join(" ")
+        };
+
+        let nums: Vec<String> = parent_tree
+            .iter()
+            .map(|(page, mcids)| {
+                format!(
+                    "{} [{}]",
+                    page,
+                    refs(&mut mcids.iter().map(|idx| elem_obj(*idx)))
+                )
+            })
+            .collect();
+        let next_key = parent_tree.last().map_or(0, |(page, _)| page + 1);
+        // Elements in reading order: by page of their first part, then by MCID
+        let mut order: Vec<usize> = (0..self.elements.len()).collect();
+        order.sort_by_key(|idx| {
+            let first = self.elements[*idx]
+                .parts
+                .iter()
+                .find(|(page, _)| page_objs[*page].is_some());
+            first.map_or((usize::MAX, 0), |(page, mcid)| {
+                (page_positions[*page], *mcid)
+            })
+        });
+        let mut objects = vec![
+            format!(
+                "<< /Type /StructTreeRoot /K {} 0 R /ParentTree << /Nums [{}] >> /ParentTreeNextKey {} >>",
+                doc_obj,
+                nums.join(" "),
+                next_key
+            ),
+            format!(
+                "<< /Type /StructElem /S /Document /P {} 0 R /K [{}] >>",
+                first_obj,
+                refs(&mut order.iter().map(|idx| elem_obj(*idx)))
+            ),
+        ];
+
+        for elem in &self.elements {
+            // Content on deleted pages is dropped
+            let parts: Vec<(usize, usize)> = elem
+                .parts
+                .iter()
+                .filter_map(|(page, mcid)| page_objs[*page].map(|obj| (obj, *mcid)))
+                .collect();
+            let first_page = parts.first().map(|(obj, _)| *obj);
+            let kids: Vec<String> = parts
+                .iter()
+                .map(|(page_obj, mcid)| {
+                    if Some(*page_obj) == first_page {
+                        mcid.to_string()
+                    } else {
+                        format!("<< /Type /MCR /Pg {} 0 R /MCID {} >>", page_obj, mcid)
+                    }
+                })
+                .collect();
+            let mut dict = format!(
+                "<< /Type /StructElem /S /{} /P {} 0 R",
+                elem.tag.role.structure_type().unwrap_or("Span"),
+                doc_obj
+            );
+            if let Some(page_obj) = first_page {
+                dict.push_str(&format!(" /Pg {} 0 R", page_obj));
+            }
+            dict.push_str(&format!(" /K [{}]", kids.join(" ")));
+            if let Some(alt) = &elem.tag.alt {
+                dict.push_str(&format!(" /Alt {}", encode_text_string(alt)));
+            }
+            dict.push_str(" >>");
+            objects.push(dict);
+        }
+        objects
+    }
+}
+
+/// Drawing scope of a tagged group, returned by [`Generator::tag`].
+///
+/// Shapes drawn through the scope form a single tagged element until it is dropped.
+#[derive(Debug)]
+pub struct TagScope<'a, W: Write = Vec<u8>> {
+    pub(crate) generator: &'a mut Generator<W>,
+}
+
+impl<W: Write> Deref for TagScope<'_, W> {
+    type Target = Generator<W>;
+
+    fn deref(&self) -> &Generator<W> {
+        self.generator
+    }
+}
+
+impl<W: Write> DerefMut for TagScope<'_, W> {
+    fn deref_mut(&mut self) -> &mut Generator<W> {
+        self.generator
+    }
+}
+
+impl<W: Write> Drop for TagScope<'_, W> {
+    fn drop(&mut self) {
+        self.generator.end_tag();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_continue_across_pages() {
+        let mut tree = StructureTree::default();
+        assert_eq!(
+            tree.open(&Tag::figure("Chart")).as_deref(),
+            Some("/Figure <</MCID 0>> BDC\n")
+        );
+        // Nested tags are part of the open group
+        assert_eq!(tree.begin(&Tag::artifact()), None);
+        assert_eq!(tree.leave_page(), "EMC\n");
+        let first_mcids = std::mem::take(&mut tree.page_mcids);
+        assert_eq!(tree.enter_page(1), ["/Figure <</MCID 0>> BDC\n"]);
+        assert!(tree.close());
+        assert_eq!(tree.leave_page(), "");
+
+        let parent_tree = [(0, &first_mcids[..]), (1, &tree.page_mcids[..])];
+        let objects = tree.objects(10, &[Some(3), Some(5)], &[0, 1], &parent_tree);
+        assert_eq!(
+            objects[0],
+            "<< /Type /StructTreeRoot /K 11 0 R /ParentTree << /Nums [0 [12 0 R] 1 [12 0 R]] >> /ParentTreeNextKey 2 >>"
+        );
+        assert_eq!(
+            objects[2],
+            "<< /Type /StructElem /S /Figure /P 11 0 R /Pg 3 0 R /K [0 << /Type /MCR /Pg 5 0 R /MCID 0 >>] /Alt (Chart) >>"
+        );
+    }
+
+    #[test]
+    fn document_lists_elements_in_page_order() {
+        let mut tree = StructureTree::default();
+        tree.begin(&Tag::figure("Later"));
+        tree.enter_page(1);
+        tree.begin(&Tag::figure("First"));
+        tree.begin(&Tag::figure("Second"));
+        // Page 1 comes first in the document
+        let objects = tree.objects(10, &[Some(3), Some(5)], &[1, 0], &[]);
+        assert_eq!(
+            objects[1],
+            "<< /Type /StructElem /S /Document /P 10 0 R /K [13 0 R 14 0 R 12 0 R] >>"
+        );
+    }
+
+    #[test]
+    fn untagged_content_is_an_artifact_once_tagged() {
+        let mut tree = StructureTree::default();
+        assert_eq!(tree.begin_untagged(), None);
+        tree.open(&Tag::figure("Chart"));
+        assert_eq!(tree.begin_untagged(), None);
+        tree.close();
+        assert_eq!(tree.begin_untagged().as_deref(), Some("/Artifact BMC\n"));
+    }
+}
@@ -0,0 +1,141 @@
+use crate::error::{self, Error};
+use crate::shapes::bounding_rect;
+use crate::units::{Angle, Length};
+
+/// 2D affine transformation `[a b c d e f]`, as used by the PDF `cm` operator.
+///
+/// A point `(x, y)` maps to `(a * x + c * y + e, b * x + d * y + f)`.
+///
+/// # Example
+/// ```
+/// use shapdf::{Degree, Transform};
+/// let t = Transform::scale(2., 2.).then(Transform::rotate(Degree(90.)));
+/// let (x, y) = t.apply(1., 0.);
+/// assert!((x - 0.).abs() < 1e-9 && (y - 2.).abs() < 1e-9);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Transform {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub e: f64,
+    pub f: f64,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Transform {
+    pub fn identity() -> Self {
+        Self {
+            a: 1.,
+            b: 0.,
+            c: 0.,
+            d: 1.,
+            e: 0.,
+            f: 0.,
+        }
+    }
+
+    pub fn translate(x: impl Length, y: impl Length) -> Self {
+        Self {
+            e: x.to_points(),
+            f: y.to_points(),
+            ..Self::identity()
+        }
+    }
+
+    pub fn scale(sx: f64, sy: f64) -> Self {
+        Self {
+            a: sx,
+            d: sy,
+            ..Self::identity()
+        }
+    }
+
+    /// Counterclockwise rotation about the origin.
+    pub fn rotate(angle: impl Angle) -> Self {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        Self {
+            a: cos,
+            b: sin,
+            c: -sin,
+            d: cos,
+            ..Self::identity()
+        }
+    }
+
+    /// Apply `self` first, then `next`.
+    pub fn then(&self, next: Transform) -> Self {
+        Self {
+            a: self.a * next.a + self.b * next.c,
+            b: self.a * next.b + self.b * next.d,
+            c: self.c * next.a + self.d * next.c,
+            d: self.c * next.b + self.d * next.d,
+            e: self.e * next.a + self.f * next.c + next.e,
+            f: self.e * next.b + self.f * next.d + next.f,
+        }
+    }
+
+    pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            self.a * x + self.c * y + self.e,
+            self.b * x + self.d * y + self.f,
+        )
+    }
+
+    /// Inverse transform, if the matrix is invertible.
+    pub fn inverse(&self) -> Option<Transform> {
+        let det = self.a * self.d - self.b * self.c;
+        if det == 0.0 || !det.is_finite() {
+            return None;
+        }
+        Some(Self {
+            a: self.d / det,
+            b: -self.b / det,
+            c: -self.c / det,
+            d: self.a / det,
+            e: (self.c * self.f - self.d * self.e) / det,
+            f: (self.b * self.e - self.a * self.f) / det,
+        })
+    }
+
+    /// Bounding box `[x1, y1, x2, y2]` of a transformed rectangle.
+    pub(crate) fn apply_rect(&self, [x1, y1, x2, y2]: [f64; 4]) -> [f64; 4] {
+        let corners = [
+            self.apply(x1, y1),
+            self.apply(x2, y1),
+            self.apply(x2, y2),
+            self.apply(x1, y2),
+        ];
+        bounding_rect(&corners, 0.0)
+    }
+
+    /// The same transform, expressed in page space for shapes drawn in `coordinates`.
+    pub(crate) fn in_coordinates(&self, coordinates: &Transform) -> Result<Transform, Error> {
+        if coordinates.is_identity() {
+            return Ok(*self);
+        }
+        let inverse = coordinates.inverse().ok_or(Error::NonFinite {
+            name: "coordinates",
+            value: f64::INFINITY,
+        })?;
+        Ok(inverse.then(*self).then(*coordinates))
+    }
+
+    /// Check that the coefficients are finite.
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        for coefficient in [self.a, self.b, self.c, self.d, self.e, self.f] {
+            error::finite("transform", coefficient)?;
+        }
+        Ok(())
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Self::identity()
+    }
+}
@@ -0,0 +1,102 @@
+/// Location of an object, listed in the cross-reference table.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum XrefEntry {
+    /// Reserved object that was never written.
+    Free,
+    /// Byte offset of an object written directly in the file.
+    Offset(usize),
+    /// Object stored at `index` in the object stream `stream`.
+    Compressed { stream: usize, index: usize },
+}
+
+/// Classic cross-reference table for objects `1..=entries.len()`.
+pub(crate) fn xref_table(entries: &[XrefEntry]) -> String {
+    let mut xref = format!("xref\n0 {}\n0000000000 65535 f \n", entries.len() + 1);
+    for entry in entries {
+        match entry {
+            XrefEntry::Offset(offset) => xref.push_str(&format!("{:010} 00000 n \n", offset)),
+            _ => xref.push_str("0000000000 00000 f \n"),
+        }
+    }
+    xref
+}
+
+/// Binary rows of a cross-reference stream (PDF 1.5) for objects `0..=entries.len()`,
+/// with the field widths (`/W`), in bytes, that fit the largest values.
+pub(crate) fn xref_stream_data(entries: &[XrefEntry]) -> ([usize; 3], Vec<u8>) {
+    let rows: Vec<[u64; 3]> = std::iter::once([0, 0, 0xFFFF])
+        .chain(entries.iter().map(|entry| match *entry {
+            XrefEntry::Free => [0, 0, 0],
+            XrefEntry::Offset(offset) => [1, offset as u64, 0],
+            XrefEntry::Compressed { stream, index } => [2, stream as u64, index as u64],
+        }))
+        .collect();
+    let mut widths = [1; 3];
+    for row in &rows {
+        for (width, value) in widths.iter_mut().zip(row) {
+            let bytes = (u64::BITS - value.leading_zeros()).div_ceil(8) as usize;
+            *width = (*width).max(bytes);
+        }
+    }
+    let mut data = Vec::with_capacity(rows.len() * widths.iter().sum::<usize>());
+    for row in &rows {
+        for (value, width) in row.iter().zip(widths) {
+            data.extend_from_slice(&value.to_be_bytes()[8 - width..]);
+        }
+    }
+    (widths, data)
+}
+
+/// Header (pairs of object number and offset) and body of an object stream.
+///
+/// Returns the stream data and the offset of the first object (`/First`).
+pub(crate) fn object_stream_data(objects: &[(usize, Vec<u8>)]) -> (Vec<u8>, usize) {
+    let mut header = String::new();
+    let mut body = Vec::new();
+    for (obj, content) in objects {
+        header.push_str(&format!("{} {} ", obj, body.len()));
+        body.extend_from_slice(content);
+        body.push(b'\n');
+    }
+    let first = header.len();
+    let mut data = header.into_bytes();
+    data.extend_from_slice(&body);
+    (data, first)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xref_stream_rows() {
+        let entries = [
+            XrefEntry::Offset(15),
+            XrefEntry::Compressed {
+                stream: 3,
+                index: 1,
+            },
+            XrefEntry::Free,
+        ];
+        assert_eq!(
+            xref_stream_data(&entries),
+            (
+                [1, 1, 2],
+                [
+                    [0, 0, 0xFF, 0xFF],
+                    [1, 15, 0, 0],
+                    [2, 3, 0, 1],
+                    [0, 0, 0, 0],
+                ]
+                .concat()
+            )
+        );
+        // Offsets beyond 4 GB widen the second field
+        let (widths, data) = xref_stream_data(&[XrefEntry::Offset(1 << 40)]);
+        assert_eq!(widths, [1, 6, 2]);
+        assert_eq!(data[9..], [1, 1, 0, 0, 0, 0, 0, 0, 0]);
+        let (data, first) = object_stream_data(&[(4, b"<< >>".to_vec()), (5, b"[1 2]".to_vec())]);
+        assert_eq!(first, 8);
+        assert_eq!(data, b"4 0 5 6 << >>\n[1 2]\n");
+    }
+}